@@ -0,0 +1,222 @@
+// Fixed-point decimal math for valuations, shares and interest. Values are u128 scaled by WAD
+// (1e18); every operation is checked and rounds in an explicit direction, so callers decide who
+// absorbs the rounding and overflow surfaces as an error instead of clamping.
+
+mod math;
+
+pub use math::mul_div;
+
+use anchor_lang::error_code;
+use core::fmt;
+
+// 1.0 as a scaled value
+pub const WAD: u128 = 1_000_000_000_000_000_000;
+// Basis points denominator (100% = 10_000 bps)
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+// Numbered well clear of the programs' own error codes
+#[error_code]
+#[derive(PartialEq, Eq)]
+pub enum MathError {
+    #[msg("Math operation overflowed")]
+    Overflow = 9000,
+    #[msg("Math operation underflowed")]
+    Underflow,
+    #[msg("Division by zero")]
+    DivisionByZero,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rounding {
+    Floor,
+    Ceil,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Decimal(u128);
+
+impl Decimal {
+    pub const ZERO: Decimal = Decimal(0);
+    pub const ONE: Decimal = Decimal(WAD);
+
+    pub const fn from_wad(wad: u128) -> Self {
+        Self(wad)
+    }
+
+    pub const fn to_wad(self) -> u128 {
+        self.0
+    }
+
+    pub const fn from_integer(value: u64) -> Self {
+        // u64::MAX * WAD < u128::MAX
+        Self(value as u128 * WAD)
+    }
+
+    pub fn from_bps(bps: u64) -> Self {
+        Self(bps as u128 * (WAD / BPS_DENOMINATOR as u128))
+    }
+
+    // `value * 10^exponent`, e.g. a Pyth price and its exponent
+    pub fn from_price(value: u64, exponent: i32) -> Result<Self, MathError> {
+        Self::from_integer(value).try_scale(exponent, Rounding::Floor)
+    }
+
+    // Whole tokens held in `amount` base units of a mint with `decimals`
+    pub fn from_token_amount(amount: u64, decimals: u8) -> Result<Self, MathError> {
+        Self::from_integer(amount).try_scale(-(decimals as i32), Rounding::Floor)
+    }
+
+    // `numerator / denominator`
+    pub fn from_ratio(numerator: u64, denominator: u64, rounding: Rounding) -> Result<Self, MathError> {
+        Self::from_integer(numerator).try_div_int(denominator, rounding)
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn try_add(self, other: Decimal) -> Result<Self, MathError> {
+        self.0.checked_add(other.0).map(Self).ok_or(MathError::Overflow)
+    }
+
+    pub fn try_sub(self, other: Decimal) -> Result<Self, MathError> {
+        self.0.checked_sub(other.0).map(Self).ok_or(MathError::Underflow)
+    }
+
+    // Difference floored at zero, for headroom-style quantities that are legitimately exhausted
+    pub fn saturating_sub(self, other: Decimal) -> Self {
+        Self(self.0.saturating_sub(other.0))
+    }
+
+    pub fn try_mul(self, other: Decimal, rounding: Rounding) -> Result<Self, MathError> {
+        mul_div(self.0, other.0, WAD, rounding).map(Self)
+    }
+
+    pub fn try_div(self, other: Decimal, rounding: Rounding) -> Result<Self, MathError> {
+        mul_div(self.0, WAD, other.0, rounding).map(Self)
+    }
+
+    // Exact product with an integer, e.g. a price times a token amount
+    pub fn try_mul_int(self, value: u64) -> Result<Self, MathError> {
+        self.0.checked_mul(value as u128).map(Self).ok_or(MathError::Overflow)
+    }
+
+    pub fn try_div_int(self, value: u64, rounding: Rounding) -> Result<Self, MathError> {
+        mul_div(self.0, 1, value as u128, rounding).map(Self)
+    }
+
+    pub fn try_mul_bps(self, bps: u64, rounding: Rounding) -> Result<Self, MathError> {
+        mul_div(self.0, bps as u128, BPS_DENOMINATOR as u128, rounding).map(Self)
+    }
+
+    // Multiply by 10^exponent
+    pub fn try_scale(self, exponent: i32, rounding: Rounding) -> Result<Self, MathError> {
+        let factor = 10u128.checked_pow(exponent.unsigned_abs()).ok_or(MathError::Overflow)?;
+        if exponent >= 0 {
+            self.0.checked_mul(factor).map(Self).ok_or(MathError::Overflow)
+        } else {
+            mul_div(self.0, 1, factor, rounding).map(Self)
+        }
+    }
+
+    pub fn try_to_u64(self, rounding: Rounding) -> Result<u64, MathError> {
+        let value = match rounding {
+            Rounding::Floor => self.0 / WAD,
+            Rounding::Ceil => self.0.div_ceil(WAD),
+        };
+        u64::try_from(value).map_err(|_| MathError::Overflow)
+    }
+
+    // Base units of a mint with `decimals` worth this many whole tokens
+    pub fn try_to_token_amount(self, decimals: u8, rounding: Rounding) -> Result<u64, MathError> {
+        self.try_scale(decimals as i32, rounding)?.try_to_u64(rounding)
+    }
+
+    // Integer quoted with `exponent`, the inverse of from_price
+    pub fn try_to_price(self, exponent: i32, rounding: Rounding) -> Result<u64, MathError> {
+        self.try_scale(-exponent, rounding)?.try_to_u64(rounding)
+    }
+
+    pub fn try_to_bps(self, rounding: Rounding) -> Result<u64, MathError> {
+        Self(mul_div(self.0, BPS_DENOMINATOR as u128, 1, rounding)?).try_to_u64(rounding)
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fraction = format!("{:018}", self.0 % WAD);
+        let fraction = fraction.trim_end_matches('0');
+        if fraction.is_empty() {
+            write!(f, "{}", self.0 / WAD)
+        } else {
+            write!(f, "{}.{}", self.0 / WAD, fraction)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conversions_from_prices_and_token_amounts() {
+        assert_eq!(Decimal::from_price(15_000_000_000, -8), Ok(Decimal::from_integer(150)));
+        assert_eq!(Decimal::from_token_amount(1_500_000_000, 9), Ok(Decimal::from_ratio(3, 2, Rounding::Floor).unwrap()));
+        assert_eq!(Decimal::from_bps(2_500), Decimal::from_ratio(1, 4, Rounding::Floor).unwrap());
+        assert_eq!(Decimal::from_integer(150).try_to_price(-8, Rounding::Floor), Ok(15_000_000_000));
+        assert_eq!(Decimal::from_integer(2).try_to_token_amount(6, Rounding::Floor), Ok(2_000_000));
+    }
+
+    #[test]
+    fn floor_and_ceil_round_in_opposite_directions() {
+        let third = Decimal::from_ratio(1, 3, Rounding::Floor).unwrap();
+        assert_eq!(third.to_wad(), 333_333_333_333_333_333);
+        assert_eq!(Decimal::from_ratio(1, 3, Rounding::Ceil).unwrap().to_wad(), 333_333_333_333_333_334);
+
+        let tiny = Decimal::from_wad(1);
+        assert_eq!(tiny.try_mul(tiny, Rounding::Floor), Ok(Decimal::ZERO));
+        assert_eq!(tiny.try_mul(tiny, Rounding::Ceil), Ok(tiny));
+        assert_eq!(tiny.try_mul_bps(1, Rounding::Floor), Ok(Decimal::ZERO));
+        assert_eq!(tiny.try_mul_bps(1, Rounding::Ceil), Ok(tiny));
+
+        assert_eq!(third.try_to_token_amount(6, Rounding::Floor), Ok(333_333));
+        assert_eq!(third.try_to_token_amount(6, Rounding::Ceil), Ok(333_334));
+        let two_thirds = Decimal::from_ratio(2, 3, Rounding::Floor).unwrap();
+        assert_eq!(two_thirds.try_to_bps(Rounding::Floor), Ok(6_666));
+        assert_eq!(two_thirds.try_to_bps(Rounding::Ceil), Ok(6_667));
+        assert_eq!(Decimal::from_wad(WAD + 1).try_to_u64(Rounding::Floor), Ok(1));
+        assert_eq!(Decimal::from_wad(WAD + 1).try_to_u64(Rounding::Ceil), Ok(2));
+        // Whole values are never rounded up
+        assert_eq!(Decimal::ONE.try_to_u64(Rounding::Ceil), Ok(1));
+        assert_eq!(Decimal::from_integer(10).try_div_int(5, Rounding::Ceil), Ok(Decimal::from_integer(2)));
+    }
+
+    #[test]
+    fn division_by_zero_is_an_error() {
+        assert_eq!(Decimal::ONE.try_div(Decimal::ZERO, Rounding::Floor), Err(MathError::DivisionByZero));
+        assert_eq!(Decimal::ONE.try_div_int(0, Rounding::Ceil), Err(MathError::DivisionByZero));
+        assert_eq!(Decimal::from_ratio(1, 0, Rounding::Floor), Err(MathError::DivisionByZero));
+    }
+
+    #[test]
+    fn overflow_and_underflow_at_the_boundaries() {
+        let max_integer = Decimal::from_integer(u64::MAX);
+        assert_eq!(max_integer.try_to_u64(Rounding::Ceil), Ok(u64::MAX));
+        assert_eq!(max_integer.try_add(Decimal::ONE).unwrap().try_to_u64(Rounding::Floor), Err(MathError::Overflow));
+        assert_eq!(max_integer.try_mul(max_integer, Rounding::Floor), Err(MathError::Overflow));
+        assert_eq!(Decimal::from_wad(u128::MAX).try_add(Decimal::from_wad(1)), Err(MathError::Overflow));
+        assert_eq!(Decimal::from_wad(u128::MAX).try_mul_int(2), Err(MathError::Overflow));
+        assert_eq!(Decimal::ONE.try_scale(39, Rounding::Floor), Err(MathError::Overflow));
+        assert_eq!(Decimal::from_integer(1_000).try_to_price(-18, Rounding::Floor), Err(MathError::Overflow));
+
+        assert_eq!(Decimal::ZERO.try_sub(Decimal::from_wad(1)), Err(MathError::Underflow));
+        assert_eq!(Decimal::ONE.saturating_sub(Decimal::from_integer(2)), Decimal::ZERO);
+    }
+
+    #[test]
+    fn display_trims_trailing_zeros() {
+        assert_eq!(Decimal::from_integer(42).to_string(), "42");
+        assert_eq!(Decimal::from_ratio(3, 2, Rounding::Floor).unwrap().to_string(), "1.5");
+        assert_eq!(Decimal::from_wad(1).to_string(), "0.000000000000000001");
+    }
+}
@@ -0,0 +1,115 @@
+use crate::{MathError, Rounding};
+
+// `a * b / denominator` with a 256-bit intermediate product, so the result only overflows when it
+// does not fit in a u128 itself
+pub fn mul_div(a: u128, b: u128, denominator: u128, rounding: Rounding) -> Result<u128, MathError> {
+    if denominator == 0 {
+        return Err(MathError::DivisionByZero);
+    }
+    let (high, low) = full_mul(a, b);
+    let (quotient, remainder) = if high == 0 {
+        (low / denominator, low % denominator)
+    } else {
+        if high >= denominator {
+            return Err(MathError::Overflow);
+        }
+        div_rem_wide(high, low, denominator)
+    };
+    match rounding {
+        Rounding::Ceil if remainder != 0 => quotient.checked_add(1).ok_or(MathError::Overflow),
+        _ => Ok(quotient),
+    }
+}
+
+// 256-bit product of two u128s as (high, low) halves
+fn full_mul(a: u128, b: u128) -> (u128, u128) {
+    const MASK: u128 = u64::MAX as u128;
+    let (a_high, a_low) = (a >> 64, a & MASK);
+    let (b_high, b_low) = (b >> 64, b & MASK);
+
+    let low_low = a_low * b_low;
+    let low_high = a_low * b_high;
+    let high_low = a_high * b_low;
+    let high_high = a_high * b_high;
+
+    let middle = (low_low >> 64) + (low_high & MASK) + (high_low & MASK);
+    let low = (low_low & MASK) | (middle << 64);
+    let high = high_high + (low_high >> 64) + (high_low >> 64) + (middle >> 64);
+    (high, low)
+}
+
+// (high * 2^128 + low) / denominator by binary long division; requires high < denominator so the
+// quotient fits in a u128
+fn div_rem_wide(high: u128, low: u128, denominator: u128) -> (u128, u128) {
+    let mut remainder = high;
+    let mut quotient = 0u128;
+    for bit in (0..128).rev() {
+        let carry = remainder >> 127;
+        remainder = (remainder << 1) | ((low >> bit) & 1);
+        if carry == 1 || remainder >= denominator {
+            remainder = remainder.wrapping_sub(denominator);
+            quotient |= 1 << bit;
+        }
+    }
+    (quotient, remainder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAX: u128 = u128::MAX;
+
+    #[test]
+    fn mul_div_rounds_in_the_requested_direction() {
+        assert_eq!(mul_div(10, 10, 3, Rounding::Floor), Ok(33));
+        assert_eq!(mul_div(10, 10, 3, Rounding::Ceil), Ok(34));
+        // Exact quotients are not rounded up
+        assert_eq!(mul_div(6, 2, 3, Rounding::Ceil), Ok(4));
+        assert_eq!(mul_div(0, 5, 7, Rounding::Ceil), Ok(0));
+    }
+
+    #[test]
+    fn mul_div_rejects_a_zero_denominator() {
+        assert_eq!(mul_div(1, 1, 0, Rounding::Floor), Err(MathError::DivisionByZero));
+        assert_eq!(mul_div(0, 0, 0, Rounding::Ceil), Err(MathError::DivisionByZero));
+    }
+
+    #[test]
+    fn mul_div_keeps_the_full_intermediate_product() {
+        assert_eq!(mul_div(MAX, MAX, MAX, Rounding::Floor), Ok(MAX));
+        assert_eq!(mul_div(MAX, 2, 4, Rounding::Floor), Ok(MAX / 2));
+        assert_eq!(mul_div(MAX, 2, 4, Rounding::Ceil), Ok(MAX / 2 + 1));
+        assert_eq!(mul_div(1 << 100, 1 << 100, 1 << 90, Rounding::Floor), Ok(1 << 110));
+    }
+
+    #[test]
+    fn mul_div_overflows_only_when_the_result_does_not_fit() {
+        assert_eq!(mul_div(MAX, 2, 1, Rounding::Floor), Err(MathError::Overflow));
+        assert_eq!(mul_div(MAX, MAX, MAX - 1, Rounding::Floor), Err(MathError::Overflow));
+        // 7 * b = 2 * MAX + 1: the floor is MAX, rounding up would exceed it
+        let b = 97_223_533_405_982_418_132_392_744_980_505_203_273;
+        assert_eq!(mul_div(7, b, 2, Rounding::Floor), Ok(MAX));
+        assert_eq!(mul_div(7, b, 2, Rounding::Ceil), Err(MathError::Overflow));
+    }
+
+    #[test]
+    fn full_mul_splits_the_product_into_halves() {
+        assert_eq!(full_mul(3, 4), (0, 12));
+        assert_eq!(full_mul(1 << 64, 1 << 64), (1, 0));
+        assert_eq!(full_mul(MAX, 2), (1, MAX - 1));
+        assert_eq!(full_mul(MAX, MAX), (MAX - 1, 1));
+        assert_eq!(full_mul(MAX, 0), (0, 0));
+    }
+
+    #[test]
+    fn div_rem_wide_divides_256_bit_values() {
+        assert_eq!(div_rem_wide(1, 0, 2), (1 << 127, 0));
+        assert_eq!(div_rem_wide(1, 1, 2), (1 << 127, 1));
+        assert_eq!(div_rem_wide(0, 100, 7), (14, 2));
+        // Denominators above 2^127 carry out of the shifted remainder
+        assert_eq!(div_rem_wide(MAX - 1, MAX, MAX), (MAX, MAX - 1));
+        let (high, low) = full_mul(MAX, MAX);
+        assert_eq!(div_rem_wide(high, low, MAX), (MAX, 0));
+    }
+}
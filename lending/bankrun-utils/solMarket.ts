@@ -0,0 +1,221 @@
+import { BN, Program } from "@coral-xyz/anchor";
+import { BankrunProvider } from "anchor-bankrun";
+import { TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { createAccount, createMint, mintTo } from "spl-token-bankrun";
+import { startAnchor, BanksClient, ProgramTestContext } from "solana-bankrun";
+import { PublicKey, Keypair, Connection } from "@solana/web3.js";
+import { createHash } from "crypto";
+import { readFileSync } from "fs";
+
+// @ts-ignore
+import IDL from "../target/idl/lending_protocol.json";
+import { LendingProtocol } from "../target/types/lending_protocol";
+import { BankrunContextWrapper } from "./bankrunConnection";
+
+export const PYTH_PRICE_FEED = new PublicKey("7UVimffxr9ow1uXYxsr4LHAcV58mLzhmwaeKvJ1pjLiE");
+export const SOL_PRICE_FEED_ID = "0xef0d8b6fda2ceba41da15d4095d1da392a0d2f8ed0c6c7bc0f4cfac8c280b56d";
+export const USDC_PRICE_FEED_ID = "0xeaa020c61cc479712813461ce153894a96a6c00b21ed0cfc2798d1f9a9e9c94a";
+export const PYTH_RECEIVER_PROGRAM = new PublicKey("rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LtFJ");
+export const BPF_LOADER_UPGRADEABLE = new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111");
+
+export const feedIdBytes = (hex: string) => Array.from(Buffer.from(hex.slice(2), "hex"));
+
+export const programDataAddress = (programId: PublicKey) =>
+  PublicKey.findProgramAddressSync([programId.toBuffer()], BPF_LOADER_UPGRADEABLE)[0];
+
+// Local mock of a fully verified PriceUpdateV2 account quoting `price` (expo -8) for the feed,
+// with the EMA price equal to the spot price unless `emaPrice` is given
+export function setPythPrice(
+  context: ProgramTestContext,
+  address: PublicKey,
+  feedId: string,
+  price: number,
+  conf: number,
+  publishTime: number,
+  emaPrice: number = price
+) {
+  const data = Buffer.alloc(8 + 32 + 1 + 84 + 8);
+  createHash("sha256").update("account:PriceUpdateV2").digest().copy(data, 0, 0, 8);
+  data.writeUInt8(1, 40); // VerificationLevel::Full
+  Buffer.from(feedIdBytes(feedId)).copy(data, 41);
+  data.writeBigInt64LE(BigInt(price), 73);
+  data.writeBigUInt64LE(BigInt(conf), 81);
+  data.writeInt32LE(-8, 89);
+  data.writeBigInt64LE(BigInt(publishTime), 93);
+  data.writeBigInt64LE(BigInt(publishTime - 1), 101);
+  data.writeBigInt64LE(BigInt(emaPrice), 109);
+  data.writeBigUInt64LE(BigInt(conf), 117);
+  context.setAccount(address, {
+    lamports: 1_000_000_000,
+    data,
+    owner: PYTH_RECEIVER_PROGRAM,
+    executable: false,
+  });
+}
+
+// Redeploy the lending program as an upgradeable program whose upgrade authority is `authority`,
+// as it is on devnet, so instructions gated on the upgrade authority can run
+export function setUpgradeAuthority(context: ProgramTestContext, programId: PublicKey, authority: PublicKey) {
+  const programData = programDataAddress(programId);
+  const elf = readFileSync("target/deploy/lending.so");
+
+  const programAccount = Buffer.alloc(4 + 32);
+  programAccount.writeUInt32LE(2, 0); // UpgradeableLoaderState::Program
+  programData.toBuffer().copy(programAccount, 4);
+
+  const programDataAccount = Buffer.alloc(4 + 8 + 1 + 32 + elf.length);
+  programDataAccount.writeUInt32LE(3, 0); // UpgradeableLoaderState::ProgramData, deployed at slot 0
+  programDataAccount.writeUInt8(1, 12); // Some(authority)
+  authority.toBuffer().copy(programDataAccount, 13);
+  elf.copy(programDataAccount, 45);
+
+  context.setAccount(programId, {
+    lamports: 1_000_000_000,
+    data: programAccount,
+    owner: BPF_LOADER_UPGRADEABLE,
+    executable: true,
+  });
+  context.setAccount(programData, {
+    lamports: 1_000_000_000,
+    data: programDataAccount,
+    owner: BPF_LOADER_UPGRADEABLE,
+    executable: false,
+  });
+}
+
+export interface SolMarket {
+  context: ProgramTestContext;
+  provider: BankrunProvider;
+  program: Program<LendingProtocol>;
+  banksClient: BanksClient;
+  bankrunContextWrapper: BankrunContextWrapper;
+  signer: Keypair;
+  mintSOL: PublicKey;
+  solBankAccount: PublicKey;
+  solTreasuryAccount: PublicKey;
+  userAccount: PublicKey;
+  solTokenAccount: PublicKey;
+  solUsdPriceFeedAccount: PublicKey;
+}
+
+// Bankrun market with a single SOL bank (also used as the user's USDC mint), a treasury funded
+// directly with 1000 SOL, and a signer holding 40 SOL with 10 SOL deposited as collateral. The
+// signer is the program's upgrade authority.
+export async function setupSolMarket(): Promise<SolMarket> {
+  // Setup Bankrun context with a real Pyth feed account cloned from devnet
+  const devnetConnection = new Connection("https://api.devnet.solana.com");
+  const pythAccountInfo = await devnetConnection.getAccountInfo(PYTH_PRICE_FEED);
+
+  const context = await startAnchor(
+    "",
+    [{ name: "lending", programId: new PublicKey(IDL.address) }],
+    [
+      {
+        address: PYTH_PRICE_FEED,
+        info: pythAccountInfo,
+      },
+    ]
+  );
+
+  const provider = new BankrunProvider(context);
+  const bankrunContextWrapper = new BankrunContextWrapper(context);
+
+  // Derive the SOL/USD price update account address used by the program
+  const solUsdPriceFeedAccount = PublicKey.findProgramAddressSync(
+    [Buffer.from("pyth-price"), Buffer.from(SOL_PRICE_FEED_ID)],
+    PYTH_PRICE_FEED
+  )[0];
+
+  // If the feed account exists on devnet, inject it (best-effort)
+  try {
+    const feedInfo = await devnetConnection.getAccountInfo(solUsdPriceFeedAccount);
+    if (feedInfo) {
+      context.setAccount(solUsdPriceFeedAccount, feedInfo);
+    }
+  } catch (_) {}
+
+  const program = new Program<LendingProtocol>(IDL as LendingProtocol, provider);
+  const banksClient = context.banksClient;
+  const signer = provider.wallet.payer;
+  setUpgradeAuthority(context, program.programId, signer.publicKey);
+
+  // Create SOL mint (9 decimals)
+  const mintSOL = await createMint(
+    // @ts-ignore
+    banksClient,
+    signer,
+    signer.publicKey,
+    null,
+    9
+  );
+
+  const [solBankAccount] = PublicKey.findProgramAddressSync([mintSOL.toBuffer()], program.programId);
+  const [solTreasuryAccount] = PublicKey.findProgramAddressSync(
+    [Buffer.from("treasury"), mintSOL.toBuffer()],
+    program.programId
+  );
+  const [userAccount] = PublicKey.findProgramAddressSync([signer.publicKey.toBuffer()], program.programId);
+
+  await program.methods
+    .initUser(mintSOL) // use SOL mint as placeholder usdc_address
+    .accounts({ signer: signer.publicKey })
+    .rpc({ commitment: "confirmed" });
+
+  await program.methods
+    .initBank(new BN(8000), new BN(7500))
+    .accounts({ signer: signer.publicKey, mint: mintSOL, tokenProgram: TOKEN_PROGRAM_ID })
+    .rpc({ commitment: "confirmed" });
+
+  // Fund treasury with large liquidity
+  await mintTo(
+    // @ts-ignore
+    banksClient,
+    signer,
+    mintSOL,
+    solTreasuryAccount,
+    signer,
+    new BN(1_000 * 10 ** 9) // 1000 SOL
+  );
+
+  const solTokenAccount = await createAccount(
+    // @ts-ignore
+    banksClient,
+    signer,
+    mintSOL,
+    signer.publicKey
+  );
+  await mintTo(
+    // @ts-ignore
+    banksClient,
+    signer,
+    mintSOL,
+    solTokenAccount,
+    signer,
+    new BN(50 * 10 ** 9) // 50 SOL
+  );
+
+  // Deposit 10 SOL as collateral
+  await program.methods
+    .deposit(new BN(10 * 10 ** 9))
+    .accounts({
+      signer: signer.publicKey,
+      mint: mintSOL,
+      tokenProgram: TOKEN_PROGRAM_ID,
+    })
+    .rpc({ commitment: "confirmed" });
+
+  return {
+    context,
+    provider,
+    program,
+    banksClient,
+    bankrunContextWrapper,
+    signer,
+    mintSOL,
+    solBankAccount,
+    solTreasuryAccount,
+    userAccount,
+    solTokenAccount,
+    solUsdPriceFeedAccount,
+  };
+}
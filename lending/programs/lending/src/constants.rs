@@ -1,12 +1,52 @@
 use anchor_lang::prelude::*;
+use price_oracle::OraclePolicy;
 
 #[constant]
 pub const GOLD_FEED_ID: &str = "0x765d2ba906dbc32ca17cc11f5310a89e9ee1f6420508c63861f2f8ba4ee34bb2";
-pub const SOL_USD_FEED_ID: &str = "0xef0d8b6fda2ceba41da15d4095d1da392a0d2f8ed0c6c7bc0f4cfac8c280b56d";
+pub const SOL_USD_FEED_ID: &str = price_oracle::SOL_USD_FEED_ID;
 pub const USDC_USD_FEED_ID: &str = "0xeaa020c61cc479712813461ce153894a96a6c00b21ed0cfc2798d1f9a9e9c94a";
-pub const MAXIMUM_AGE: u64 = 100; // allow price feed 100 sec old, to avoid stale price feed errors
+// Default oracle acceptance rules per feed, until a bank sets its own; SOL/USD shares the gold
+// program's default
+pub const SOL_USD_POLICY: OraclePolicy = price_oracle::SOL_USD_POLICY;
+pub const USDC_USD_POLICY: OraclePolicy = OraclePolicy {
+    maximum_age: 100,
+    max_confidence_bps: 50,
+    max_ema_deviation_bps: price_oracle::MAX_EMA_DEVIATION_BPS,
+};
+// Decimals of the SOL and USDC mints, used to value position balances in whole tokens
+pub const SOL_DECIMALS: u8 = 9;
+pub const USDC_DECIMALS: u8 = 6;
+// Longest a guardian-set manual price stays valid
+pub const MAX_MANUAL_PRICE_VALIDITY: i64 = 900;
 
 // Basis points denominator (100% = 10_000 bps)
 pub const BPS_DENOMINATOR: u64 = 10_000;
+// Adaptive rate model: rate at 0% / 100% utilization is rate_at_target divided / multiplied by this
+pub const ADAPTIVE_CURVE_STEEPNESS: u64 = 4;
+// Fixed-point scale of the adaptive model's per-second adjustment speed
+pub const ADAPTIVE_SPEED_SCALE: u64 = 1_000_000_000;
+// Upper bound for the borrow origination fee
+pub const MAX_BORROW_FEE_BPS: u64 = 1_000;
+// Transfer hook programs a Token-2022 bank mint may use; hooks outside this list are rejected
+pub const ALLOWED_TRANSFER_HOOK_PROGRAMS: &[Pubkey] = &[];
 // Approx seconds in a year, used to convert APR (per year) to per-second
 pub const SECONDS_PER_YEAR: i64 = 31_536_000;
+
+// Default health alert tiers, in the same bps scale as the health factor (10_000 = 1.0)
+pub const DEFAULT_WARNING_ALERT_BPS: u64 = 15_000;
+pub const DEFAULT_CRITICAL_ALERT_BPS: u64 = 12_500;
+pub const DEFAULT_IMMINENT_ALERT_BPS: u64 = 11_000;
+// Bounds accepted for any enabled alert tier
+pub const MIN_ALERT_THRESHOLD_BPS: u64 = 10_000;
+pub const MAX_ALERT_THRESHOLD_BPS: u64 = 30_000;
+
+// Fixed-term loans: premium over the variable curve at origination, allowed terms and
+// grace period after maturity before an unpaid loan can be liquidated
+pub const FIXED_RATE_PREMIUM_BPS: u64 = 200;
+pub const MIN_FIXED_LOAN_TERM: i64 = 86_400;
+pub const MAX_FIXED_LOAN_TERM: i64 = 31_536_000;
+pub const FIXED_LOAN_GRACE_PERIOD: i64 = 259_200;
+
+// Upper bounds for user-configured deleverage orders
+pub const MAX_DELEVERAGE_SLIPPAGE_BPS: u64 = 1_000;
+pub const MAX_KEEPER_FEE_BPS: u64 = 500;
@@ -16,8 +16,90 @@ pub enum ErrorCode {
     NotUndercollateralized,
     #[msg("Oracle price error")] 
     OracleError,
-    #[msg("Invalid health factor threshold. Must be between 110-300 bps.")]
+    #[msg("Invalid health factor threshold. Must be between 10000-30000 bps.")]
     InvalidThreshold,
     #[msg("Invalid alert frequency. Must be between 1-168 hours.")]
     InvalidAlertFrequency,
-}
\ No newline at end of file
+    #[msg("Alert tiers must be ordered warning > critical > liquidation imminent.")]
+    InvalidAlertTierOrder,
+    #[msg("Signer is not the bank authority.")]
+    Unauthorized,
+    #[msg("Health monitoring must be enabled to use automatic deleveraging.")]
+    MonitoringDisabled,
+    #[msg("Invalid deleverage order parameters.")]
+    InvalidDeleverageOrder,
+    #[msg("Health factor is above the deleverage trigger.")]
+    DeleverageNotTriggered,
+    #[msg("Deleverage did not restore the target health factor.")]
+    DeleverageTargetNotReached,
+    #[msg("Swap program is not whitelisted for this bank.")]
+    SwapProgramNotAllowed,
+    #[msg("Swap spent more collateral than requested.")]
+    SwapInputExceeded,
+    #[msg("Swap output is below the allowed slippage.")]
+    SlippageExceeded,
+    #[msg("Swap changed the treasury's owner, delegate or close authority.")]
+    SwapAlteredTreasury,
+    #[msg("Collateral and debt assets must differ.")]
+    InvalidAssetPair,
+    #[msg("Invalid bank configuration.")]
+    InvalidBankConfig,
+    #[msg("Invalid interest rate model parameters.")]
+    InvalidRateModel,
+    #[msg("Interest rate model account does not match the bank.")]
+    RateModelMismatch,
+    #[msg("Fixed loan term is outside the allowed range.")]
+    InvalidLoanTerm,
+    #[msg("Quoted fixed rate exceeds the maximum accepted rate.")]
+    FixedRateTooHigh,
+    #[msg("Fixed loan is not past its maturity grace period.")]
+    LoanNotOverdue,
+    #[msg("Referrer fee account does not match this borrow.")]
+    InvalidReferrer,
+    #[msg("No referral fees to claim.")]
+    NothingToClaim,
+    #[msg("Invalid reward pool parameters.")]
+    InvalidRewardPool,
+    #[msg("Reward accounts do not match the bank's active reward pool.")]
+    RewardPoolMismatch,
+    #[msg("The active reward pool has not ended yet.")]
+    RewardPoolActive,
+    #[msg("Native SOL instructions require the wrapped SOL bank.")]
+    NotNativeMint,
+    #[msg("Mint has a Token-2022 extension the protocol does not support.")]
+    UnsupportedMintExtension,
+    #[msg("Treasury holds no tokens beyond what the bank accounts for.")]
+    NoExcessToSkim,
+    #[msg("Oracle confidence interval is too wide relative to the price.")]
+    PriceConfidenceTooWide,
+    #[msg("Oracle price deviates too far from its EMA price.")]
+    PriceDeviatesFromEma,
+    #[msg("Fresh oracle sources disagree beyond the allowed tolerance.")]
+    OracleSourcesDisagree,
+    #[msg("Manual price must be positive with a short, non-zero validity.")]
+    InvalidManualPrice,
+    #[msg("No fully verified Pyth price update for the feed was passed.")]
+    InvalidPriceUpdate,
+    #[msg("Mint is neither the USDC nor the SOL side of the user's position.")]
+    UnsupportedPositionMint,
+    #[msg("Treasury received less than the amount due.")]
+    RepaymentShortfall,
+    #[msg("More oracle fallback sources than remaining accounts were declared.")]
+    InvalidFallbackSourceCount,
+}
+
+impl From<price_oracle::OracleError> for ErrorCode {
+    fn from(error: price_oracle::OracleError) -> Self {
+        use price_oracle::OracleError;
+        match error {
+            OracleError::ConfidenceTooWide => ErrorCode::PriceConfidenceTooWide,
+            OracleError::DeviatesFromEma => ErrorCode::PriceDeviatesFromEma,
+            OracleError::SourcesDisagree => ErrorCode::OracleSourcesDisagree,
+            OracleError::InvalidPriceUpdate => ErrorCode::InvalidPriceUpdate,
+            OracleError::InvalidFeedId
+            | OracleError::StalePrice
+            | OracleError::NonPositivePrice
+            | OracleError::PriceOverflow => ErrorCode::OracleError,
+        }
+    }
+}
@@ -1,6 +1,15 @@
 use anchor_lang::prelude::*;
 use anchor_spl::token_interface::{ Mint, TokenAccount, TokenInterface };
+use anchor_spl::token_interface::spl_token_2022::{
+    self,
+    extension::{ BaseStateWithExtensions, ExtensionType, StateWithExtensions, transfer_hook::TransferHook },
+};
 use crate::state::*;
+use crate::error::ErrorCode;
+use crate::constants::{
+    BPS_DENOMINATOR, MAX_BORROW_FEE_BPS, ALLOWED_TRANSFER_HOOK_PROGRAMS,
+    DEFAULT_WARNING_ALERT_BPS, DEFAULT_CRITICAL_ALERT_BPS, DEFAULT_IMMINENT_ALERT_BPS,
+};
 
 #[derive(Accounts)]
 pub struct InitBank<'info> {
@@ -43,7 +52,20 @@ pub struct InitUser<'info> {
     pub system_program: Program <'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct UpdateBank<'info> {
+    pub signer: Signer<'info>,
+    #[account(
+        mut,
+        constraint = bank.authority == signer.key() @ ErrorCode::Unauthorized,
+    )]
+    pub bank: Account<'info, Bank>,
+}
+
 pub fn process_init_bank(ctx: Context<InitBank>, liquidation_threshold: u64, max_ltv: u64) -> Result<()> {
+    validate_mint_extensions(&ctx.accounts.mint)?;
+    validate_risk_params(liquidation_threshold, max_ltv)?;
+
     let bank = &mut ctx.accounts.bank;
     bank.mint_address = ctx.accounts.mint.key();
     bank.authority = ctx.accounts.signer.key();
@@ -57,6 +79,46 @@ pub fn process_init_bank(ctx: Context<InitBank>, liquidation_threshold: u64, max
     Ok(())
 }
 
+// Liquidation threshold and max LTV are bps of collateral value, and a position must reach its
+// borrow limit before it can be liquidated
+fn validate_risk_params(liquidation_threshold: u64, max_ltv: u64) -> Result<()> {
+    require!(
+        0 < max_ltv && max_ltv < liquidation_threshold && liquidation_threshold <= BPS_DENOMINATOR,
+        ErrorCode::InvalidBankConfig
+    );
+    Ok(())
+}
+
+// Reject Token-2022 mints whose extensions could move or freeze treasury funds behind the
+// protocol's back, or make transfers in and out of the treasury impossible
+fn validate_mint_extensions(mint: &InterfaceAccount<Mint>) -> Result<()> {
+    let mint_info = mint.to_account_info();
+    if *mint_info.owner != spl_token_2022::ID {
+        return Ok(());
+    }
+
+    let mint_data = mint_info.try_borrow_data()?;
+    let mint_state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_data)?;
+    for extension in mint_state.get_extension_types()? {
+        match extension {
+            ExtensionType::PermanentDelegate | ExtensionType::NonTransferable => {
+                return Err(ErrorCode::UnsupportedMintExtension.into());
+            },
+            ExtensionType::TransferHook => {
+                let hook = mint_state.get_extension::<TransferHook>()?;
+                if let Some(hook_program) = Option::<Pubkey>::from(hook.program_id) {
+                    require!(
+                        ALLOWED_TRANSFER_HOOK_PROGRAMS.contains(&hook_program),
+                        ErrorCode::UnsupportedMintExtension
+                    );
+                }
+            },
+            _ => {},
+        }
+    }
+    Ok(())
+}
+
 pub fn process_init_user(ctx: Context<InitUser>, usdc_address: Pubkey) -> Result<()> {
     let user = &mut ctx.accounts.user_account;
     user.owner = ctx.accounts.signer.key();
@@ -65,12 +127,94 @@ pub fn process_init_user(ctx: Context<InitUser>, usdc_address: Pubkey) -> Result
     let now = Clock::get()?.unix_timestamp; 
     user.last_updated = now;
     
-    user.alert_threshold = 150; 
+    user.alert_threshold = DEFAULT_WARNING_ALERT_BPS; 
     user.last_health_check = now;
     user.health_history_count = 0;
     user.is_monitoring_enabled = false; 
     user.last_alert_sent = 0;
     user.alert_frequency_hours = 24; 
 
+    user.critical_alert_threshold = DEFAULT_CRITICAL_ALERT_BPS;
+    user.last_critical_alert_sent = 0;
+    user.critical_alert_frequency_hours = 6;
+    user.imminent_alert_threshold = DEFAULT_IMMINENT_ALERT_BPS;
+    user.last_imminent_alert_sent = 0;
+    user.imminent_alert_frequency_hours = 1;
+
     Ok(())
-}
\ No newline at end of file
+}
+
+// Whitelist the swap program used to sell this bank's collateral during auto-deleverage (default disables it)
+pub fn process_update_bank_swap_program(ctx: Context<UpdateBank>, swap_program: Pubkey) -> Result<()> {
+    ctx.accounts.bank.swap_program = swap_program;
+    msg!("Bank {} swap program set to: {}", ctx.accounts.bank.mint_address, swap_program);
+    Ok(())
+}
+
+// Set the liquidation threshold and max LTV, both in bps. Banks created while these were percents
+// (e.g. 80 for 80%) leave every position liquidatable and must be migrated through here.
+pub fn process_update_bank_risk_params(ctx: Context<UpdateBank>, liquidation_threshold: u64, max_ltv: u64) -> Result<()> {
+    validate_risk_params(liquidation_threshold, max_ltv)?;
+
+    let bank = &mut ctx.accounts.bank;
+    bank.liquidation_threshold = liquidation_threshold;
+    bank.max_ltv = max_ltv;
+
+    msg!("Bank {} risk params: liquidation threshold {} bps, max LTV {} bps",
+         bank.mint_address, liquidation_threshold, max_ltv);
+    Ok(())
+}
+
+// Set the borrow origination fee and the share of it paid to referrers
+pub fn process_update_bank_fees(ctx: Context<UpdateBank>, borrow_fee_bps: u64, referral_fee_bps: u64) -> Result<()> {
+    require!(
+        borrow_fee_bps <= MAX_BORROW_FEE_BPS && referral_fee_bps <= BPS_DENOMINATOR,
+        ErrorCode::InvalidBankConfig
+    );
+
+    let bank = &mut ctx.accounts.bank;
+    bank.borrow_fee_bps = borrow_fee_bps;
+    bank.referral_fee_bps = referral_fee_bps;
+
+    msg!("Bank {} fees: borrow fee {} bps, referral share {} bps",
+         bank.mint_address, borrow_fee_bps, referral_fee_bps);
+    Ok(())
+}
+
+// Choose the oracle price used for this bank's borrow-limit checks and liquidation eligibility
+pub fn process_update_bank_valuation_modes(
+    ctx: Context<UpdateBank>,
+    borrow_valuation_mode: ValuationMode,
+    liquidation_valuation_mode: ValuationMode,
+) -> Result<()> {
+    let bank = &mut ctx.accounts.bank;
+    bank.borrow_valuation_mode = borrow_valuation_mode;
+    bank.liquidation_valuation_mode = liquidation_valuation_mode;
+
+    msg!("Bank {} valuation modes: borrow {:?}, liquidation {:?}",
+         bank.mint_address, borrow_valuation_mode, liquidation_valuation_mode);
+    Ok(())
+}
+
+// Set the oracle policy prices of this bank's asset must pass. A max age of 0 restores the feed's
+// default policy.
+pub fn process_update_bank_oracle_policy(
+    ctx: Context<UpdateBank>,
+    max_price_age: u64,
+    max_confidence_bps: u64,
+    max_ema_deviation_bps: u64,
+) -> Result<()> {
+    require!(
+        max_confidence_bps <= BPS_DENOMINATOR && max_ema_deviation_bps <= BPS_DENOMINATOR,
+        ErrorCode::InvalidBankConfig
+    );
+
+    let bank = &mut ctx.accounts.bank;
+    bank.oracle_max_price_age = max_price_age;
+    bank.oracle_max_confidence_bps = max_confidence_bps;
+    bank.oracle_max_ema_deviation_bps = max_ema_deviation_bps;
+
+    msg!("Bank {} oracle policy: max age {} s, max confidence {} bps, max EMA deviation {} bps",
+         bank.mint_address, max_price_age, max_confidence_bps, max_ema_deviation_bps);
+    Ok(())
+}
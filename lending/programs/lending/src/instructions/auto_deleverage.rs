@@ -0,0 +1,413 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_interface::{ self, Mint, TokenAccount, TokenInterface, TransferChecked };
+use pyth_solana_receiver_sdk::price_update::PriceUpdateV2;
+use crate::constants::{
+    BPS_DENOMINATOR, MIN_ALERT_THRESHOLD_BPS, MAX_ALERT_THRESHOLD_BPS,
+    MAX_DELEVERAGE_SLIPPAGE_BPS, MAX_KEEPER_FEE_BPS,
+};
+use decimal::{Decimal, Rounding};
+use crate::state::*;
+use crate::error::ErrorCode;
+use super::interest::accrue_interest;
+use super::rewards::checkpoint_rewards;
+use super::referral::realize_referral_fee;
+use super::utils::{position_is_usdc, get_sol_and_usdc_prices, calculate_position_values, calculate_health_factor, shares_from_amount, usd_price};
+
+#[derive(Accounts)]
+pub struct SetDeleverageOrder<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    #[account(
+        seeds = [signer.key().as_ref()],
+        bump,
+    )]
+    pub user_account: Account<'info, User>,
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 8 + DeleverageOrder::INIT_SPACE,
+        seeds = [b"deleverage", signer.key().as_ref()],
+        bump,
+    )]
+    pub deleverage_order: Account<'info, DeleverageOrder>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelDeleverageOrder<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    #[account(
+        mut,
+        close = signer,
+        seeds = [b"deleverage", signer.key().as_ref()],
+        bump = deleverage_order.bump,
+    )]
+    pub deleverage_order: Account<'info, DeleverageOrder>,
+}
+
+#[derive(Accounts)]
+pub struct AutoDeleverage<'info> {
+    #[account(mut)]
+    pub keeper: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"deleverage", deleverage_order.owner.as_ref()],
+        bump = deleverage_order.bump,
+    )]
+    pub deleverage_order: Box<Account<'info, DeleverageOrder>>,
+    #[account(
+        mut,
+        seeds = [deleverage_order.owner.as_ref()],
+        bump,
+    )]
+    pub user_account: Box<Account<'info, User>>,
+    pub price_update: Box<Account<'info, PriceUpdateV2>>,
+    pub collateral_mint: Box<InterfaceAccount<'info, Mint>>,
+    pub debt_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        seeds = [collateral_mint.key().as_ref()],
+        bump,
+    )]
+    pub collateral_bank: Box<Account<'info, Bank>>,
+    pub collateral_rate_model: Option<Box<Account<'info, InterestRateModel>>>,
+    #[account(
+        mut,
+        seeds = [b"treasury", collateral_mint.key().as_ref()],
+        bump,
+    )]
+    pub collateral_bank_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [debt_mint.key().as_ref()],
+        bump,
+    )]
+    pub debt_bank: Box<Account<'info, Bank>>,
+    pub debt_rate_model: Option<Box<Account<'info, InterestRateModel>>>,
+    // Required while the matching reward pool is emitting, user_rewards once the owner opted in: the
+    // collateral bank's deposit pool, and the debt bank's borrow pool and deposit pool (for a surplus)
+    #[account(mut)]
+    pub collateral_reward_pool: Option<Box<Account<'info, RewardPool>>>,
+    #[account(mut)]
+    pub collateral_user_rewards: Option<Box<Account<'info, UserRewards>>>,
+    #[account(mut)]
+    pub debt_reward_pool: Option<Box<Account<'info, RewardPool>>>,
+    #[account(mut)]
+    pub debt_user_rewards: Option<Box<Account<'info, UserRewards>>>,
+    #[account(mut)]
+    pub surplus_reward_pool: Option<Box<Account<'info, RewardPool>>>,
+    #[account(mut)]
+    pub surplus_user_rewards: Option<Box<Account<'info, UserRewards>>>,
+    // Required while the owner's debt in the debt mint carries a pending referral fee
+    #[account(mut)]
+    pub debt_referrer_fees: Option<Box<Account<'info, ReferrerFees>>>,
+    #[account(
+        mut,
+        seeds = [b"treasury", debt_mint.key().as_ref()],
+        bump,
+    )]
+    pub debt_bank_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = keeper,
+        associated_token::mint = collateral_mint,
+        associated_token::authority = keeper,
+        associated_token::token_program = token_program,
+    )]
+    pub keeper_collateral_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    /// CHECK: must be the swap program whitelisted on the collateral bank, which is unset (and the
+    /// bank's collateral cannot be deleveraged) while it is the default key
+    #[account(
+        executable,
+        constraint = collateral_bank.swap_program != Pubkey::default()
+            && swap_program.key() == collateral_bank.swap_program @ ErrorCode::SwapProgramNotAllowed,
+    )]
+    pub swap_program: UncheckedAccount<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn process_set_deleverage_order(
+    ctx: Context<SetDeleverageOrder>,
+    trigger_health_factor: u64,
+    target_health_factor: u64,
+    max_slippage_bps: u64,
+    keeper_fee_bps: u64,
+) -> Result<()> {
+    let user = &ctx.accounts.user_account;
+    require!(user.is_monitoring_enabled, ErrorCode::MonitoringDisabled);
+
+    // The stop-loss must sit at or below the warning alert so the user is notified first
+    require!(
+        trigger_health_factor >= MIN_ALERT_THRESHOLD_BPS && trigger_health_factor <= user.alert_threshold,
+        ErrorCode::InvalidDeleverageOrder
+    );
+    require!(
+        target_health_factor > trigger_health_factor && target_health_factor <= MAX_ALERT_THRESHOLD_BPS,
+        ErrorCode::InvalidDeleverageOrder
+    );
+    require!(max_slippage_bps <= MAX_DELEVERAGE_SLIPPAGE_BPS, ErrorCode::InvalidDeleverageOrder);
+    require!(keeper_fee_bps <= MAX_KEEPER_FEE_BPS, ErrorCode::InvalidDeleverageOrder);
+
+    let order = &mut ctx.accounts.deleverage_order;
+    order.owner = ctx.accounts.signer.key();
+    order.trigger_health_factor = trigger_health_factor;
+    order.target_health_factor = target_health_factor;
+    order.max_slippage_bps = max_slippage_bps;
+    order.keeper_fee_bps = keeper_fee_bps;
+    order.bump = ctx.bumps.deleverage_order;
+
+    msg!("Deleverage order set: trigger {} bps, target {} bps", trigger_health_factor, target_health_factor);
+    Ok(())
+}
+
+pub fn process_cancel_deleverage_order(ctx: Context<CancelDeleverageOrder>) -> Result<()> {
+    msg!("Deleverage order cancelled for user: {}", ctx.accounts.deleverage_order.owner);
+    Ok(())
+}
+
+// Sell up to `collateral_amount` of the user's collateral through the whitelisted swap program,
+// repay debt with the proceeds and pay the keeper. The first `fallback_source_count` remaining
+// accounts are oracle fallback sources; `swap_data` and the rest are forwarded verbatim to the swap
+// program. The collateral treasury signs for the input transfer and proceeds must land in the debt
+// treasury.
+pub fn process_auto_deleverage<'info>(
+    ctx: Context<'_, '_, 'info, 'info, AutoDeleverage<'info>>,
+    collateral_amount: u64,
+    fallback_source_count: u8,
+    swap_data: Vec<u8>,
+) -> Result<()> {
+    require!(
+        usize::from(fallback_source_count) <= ctx.remaining_accounts.len(),
+        ErrorCode::InvalidFallbackSourceCount
+    );
+    let (fallback_sources, swap_accounts) = ctx.remaining_accounts.split_at(usize::from(fallback_source_count));
+
+    let collateral_mint_key = ctx.accounts.collateral_mint.key();
+    let debt_mint_key = ctx.accounts.debt_mint.key();
+    require_keys_neq!(collateral_mint_key, debt_mint_key, ErrorCode::InvalidAssetPair);
+
+    accrue_interest(&mut ctx.accounts.collateral_bank, ctx.accounts.collateral_rate_model.as_deref())?;
+    accrue_interest(&mut ctx.accounts.debt_bank, ctx.accounts.debt_rate_model.as_deref())?;
+
+    let order = &ctx.accounts.deleverage_order;
+    let user = &ctx.accounts.user_account;
+    require!(user.is_monitoring_enabled, ErrorCode::MonitoringDisabled);
+
+    let collateral_is_usdc = position_is_usdc(user, collateral_mint_key)?;
+    let debt_is_usdc = position_is_usdc(user, debt_mint_key)?;
+    require!(collateral_is_usdc != debt_is_usdc, ErrorCode::InvalidAssetPair);
+
+    let (sol_price, usdc_price) = get_sol_and_usdc_prices(
+        &ctx.accounts.price_update,
+        fallback_sources,
+        user,
+        &[&ctx.accounts.collateral_bank, &ctx.accounts.debt_bank],
+    )?;
+    let mode = ctx.accounts.collateral_bank.liquidation_valuation_mode;
+    let (total_collateral_value, total_borrowed_value) = calculate_position_values(user, sol_price, usdc_price, mode)?;
+    let health_before = calculate_health_factor(total_collateral_value, total_borrowed_value);
+    require!(health_before < order.trigger_health_factor, ErrorCode::DeleverageNotTriggered);
+
+    let max_keeper_fee = bps_of(collateral_amount, order.keeper_fee_bps)?;
+    let deposited_collateral = if collateral_is_usdc { user.deposited_usdc } else { user.deposited_sol };
+    require!(
+        collateral_amount.saturating_add(max_keeper_fee) <= deposited_collateral,
+        ErrorCode::InsufficientFunds
+    );
+
+    let collateral_before = ctx.accounts.collateral_bank_token_account.amount;
+    let treasury_authorities_before = treasury_authorities(&ctx.accounts.collateral_bank_token_account);
+    let debt_before = ctx.accounts.debt_bank_token_account.amount;
+
+    let treasury_seeds: &[&[&[u8]]] = &[
+        &[
+            b"treasury",
+            collateral_mint_key.as_ref(),
+            &[ctx.bumps.collateral_bank_token_account],
+        ],
+    ];
+
+    let treasury_key = ctx.accounts.collateral_bank_token_account.key();
+    let swap_ix = Instruction {
+        program_id: ctx.accounts.swap_program.key(),
+        accounts: swap_accounts
+            .iter()
+            .map(|account| AccountMeta {
+                pubkey: account.key(),
+                is_signer: account.is_signer || account.key() == treasury_key,
+                is_writable: account.is_writable,
+            })
+            .collect(),
+        data: swap_data,
+    };
+    let mut swap_account_infos = swap_accounts.to_vec();
+    swap_account_infos.push(ctx.accounts.swap_program.to_account_info());
+    invoke_signed(&swap_ix, &swap_account_infos, treasury_seeds)?;
+
+    ctx.accounts.collateral_bank_token_account.reload()?;
+    ctx.accounts.debt_bank_token_account.reload()?;
+
+    // The treasury signed the swap, so a route could have approved a delegate or handed over its
+    // authorities instead of only moving the collateral sold
+    require!(
+        treasury_authorities(&ctx.accounts.collateral_bank_token_account) == treasury_authorities_before,
+        ErrorCode::SwapAlteredTreasury
+    );
+
+    let collateral_sold = collateral_before.saturating_sub(ctx.accounts.collateral_bank_token_account.amount);
+    require!(collateral_sold <= collateral_amount, ErrorCode::SwapInputExceeded);
+    let debt_received = ctx.accounts.debt_bank_token_account.amount.saturating_sub(debt_before);
+
+    // Oracle value of the collateral sold, expressed in debt token base units
+    let (collateral_price, debt_price) = if collateral_is_usdc { (usdc_price.price, sol_price.price) } else { (sol_price.price, usdc_price.price) };
+    let expected_out = Decimal::from_token_amount(collateral_sold, ctx.accounts.collateral_mint.decimals)?
+        .try_mul(usd_price(collateral_price)?, Rounding::Floor)?
+        .try_div(usd_price(debt_price)?, Rounding::Floor)?;
+    let min_out = expected_out
+        .try_mul_bps(BPS_DENOMINATOR.saturating_sub(order.max_slippage_bps), Rounding::Floor)?
+        .try_to_token_amount(ctx.accounts.debt_mint.decimals, Rounding::Floor)?;
+    require!(debt_received >= min_out, ErrorCode::SlippageExceeded);
+
+    let keeper_fee = bps_of(collateral_sold, order.keeper_fee_bps)?;
+    if keeper_fee > 0 {
+        let transfer_cpi_accounts = TransferChecked {
+            from: ctx.accounts.collateral_bank_token_account.to_account_info(),
+            mint: ctx.accounts.collateral_mint.to_account_info(),
+            to: ctx.accounts.keeper_collateral_token_account.to_account_info(),
+            authority: ctx.accounts.collateral_bank_token_account.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), transfer_cpi_accounts).with_signer(treasury_seeds);
+        token_interface::transfer_checked(cpi_ctx, keeper_fee, ctx.accounts.collateral_mint.decimals)?;
+    }
+
+    // Remove the sold collateral and keeper fee from the user's deposits
+    let collateral_removed = collateral_sold.saturating_add(keeper_fee);
+    let collateral_bank = &mut ctx.accounts.collateral_bank;
+    let collateral_shares_removed = shares_from_amount(
+        collateral_removed,
+        collateral_bank.total_deposits,
+        collateral_bank.total_deposit_shares,
+        Rounding::Ceil,
+    )?;
+    collateral_bank.total_deposits = collateral_bank.total_deposits.saturating_sub(collateral_removed);
+    collateral_bank.total_deposit_shares = collateral_bank.total_deposit_shares.saturating_sub(collateral_shares_removed);
+
+    // Repay debt with the proceeds, any surplus is credited as a deposit of the debt asset
+    let user = &mut ctx.accounts.user_account;
+    let debt_bank = &mut ctx.accounts.debt_bank;
+    let outstanding_debt = if debt_is_usdc { user.borrowed_usdc } else { user.borrowed_sol };
+    let debt_repaid = debt_received.min(outstanding_debt);
+    realize_referral_fee(user, debt_is_usdc, ctx.accounts.debt_referrer_fees.as_deref_mut(), debt_repaid, outstanding_debt)?;
+    let surplus = debt_received - debt_repaid;
+    let debt_shares_removed = shares_from_amount(debt_repaid, debt_bank.total_borrowed, debt_bank.total_borrowed_shares, Rounding::Floor)?;
+    let surplus_shares = shares_from_amount(surplus, debt_bank.total_deposits, debt_bank.total_deposit_shares, Rounding::Floor)?;
+    debt_bank.total_borrowed = debt_bank.total_borrowed.saturating_sub(debt_repaid);
+    debt_bank.total_borrowed_shares = debt_bank.total_borrowed_shares.saturating_sub(debt_shares_removed);
+    debt_bank.total_deposits = debt_bank.total_deposits.saturating_add(surplus);
+    debt_bank.total_deposit_shares = debt_bank.total_deposit_shares.saturating_add(surplus_shares);
+
+    if collateral_is_usdc {
+        user.deposited_usdc = user.deposited_usdc.saturating_sub(collateral_removed);
+        user.deposited_usdc_shares = user.deposited_usdc_shares.saturating_sub(collateral_shares_removed);
+        user.borrowed_sol -= debt_repaid;
+        user.borrowed_sol_shares = user.borrowed_sol_shares.saturating_sub(debt_shares_removed);
+        user.deposited_sol += surplus;
+        user.deposited_sol_shares += surplus_shares;
+    } else {
+        user.deposited_sol = user.deposited_sol.saturating_sub(collateral_removed);
+        user.deposited_sol_shares = user.deposited_sol_shares.saturating_sub(collateral_shares_removed);
+        user.borrowed_usdc -= debt_repaid;
+        user.borrowed_usdc_shares = user.borrowed_usdc_shares.saturating_sub(debt_shares_removed);
+        user.deposited_usdc += surplus;
+        user.deposited_usdc_shares += surplus_shares;
+    }
+
+    let (total_collateral_value, total_borrowed_value) = calculate_position_values(user, sol_price, usdc_price, mode)?;
+    let health_after = calculate_health_factor(total_collateral_value, total_borrowed_value);
+    require!(
+        health_after >= ctx.accounts.deleverage_order.target_health_factor,
+        ErrorCode::DeleverageTargetNotReached
+    );
+
+    let now = Clock::get()?.unix_timestamp;
+    user.health_factor = health_after;
+    user.last_health_check = now;
+    user.last_updated = now;
+    ctx.accounts.deleverage_order.last_executed = now;
+
+    let accounts = &mut *ctx.accounts;
+    checkpoint_rewards(
+        &accounts.collateral_bank,
+        &accounts.user_account,
+        RewardSide::Deposit,
+        accounts.collateral_reward_pool.as_deref_mut(),
+        accounts.collateral_user_rewards.as_deref_mut(),
+    )?;
+    checkpoint_rewards(
+        &accounts.debt_bank,
+        &accounts.user_account,
+        RewardSide::Borrow,
+        accounts.debt_reward_pool.as_deref_mut(),
+        accounts.debt_user_rewards.as_deref_mut(),
+    )?;
+    if surplus > 0 {
+        checkpoint_rewards(
+            &accounts.debt_bank,
+            &accounts.user_account,
+            RewardSide::Deposit,
+            accounts.surplus_reward_pool.as_deref_mut(),
+            accounts.surplus_user_rewards.as_deref_mut(),
+        )?;
+    }
+
+    emit!(AutoDeleverageEvent {
+        user: ctx.accounts.user_account.owner,
+        keeper: ctx.accounts.keeper.key(),
+        collateral_mint: collateral_mint_key,
+        debt_mint: debt_mint_key,
+        collateral_sold,
+        debt_repaid,
+        keeper_fee,
+        health_before,
+        health_after,
+        timestamp: now,
+    });
+
+    msg!("Auto-deleverage: sold {} collateral, repaid {} debt, health {} -> {} bps",
+         collateral_sold, debt_repaid, health_before, health_after);
+    Ok(())
+}
+
+// Owner, delegate, delegated amount and close authority of a treasury token account
+#[inline(always)]
+fn treasury_authorities(treasury: &TokenAccount) -> (Pubkey, COption<Pubkey>, u64, COption<Pubkey>) {
+    (treasury.owner, treasury.delegate, treasury.delegated_amount, treasury.close_authority)
+}
+
+#[inline(always)]
+fn bps_of(amount: u64, bps: u64) -> Result<u64> {
+    Ok(Decimal::from_integer(amount)
+        .try_mul_bps(bps, Rounding::Floor)?
+        .try_to_u64(Rounding::Floor)?)
+}
+
+#[event]
+pub struct AutoDeleverageEvent {
+    pub user: Pubkey,
+    pub keeper: Pubkey,
+    pub collateral_mint: Pubkey,
+    pub debt_mint: Pubkey,
+    pub collateral_sold: u64,
+    pub debt_repaid: u64,
+    pub keeper_fee: u64,
+    pub health_before: u64,
+    pub health_after: u64,
+    pub timestamp: i64,
+}
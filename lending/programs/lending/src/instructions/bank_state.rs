@@ -0,0 +1,76 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::TokenAccount;
+use crate::state::*;
+use super::interest::{accrue_interest, resolve_rate_model, get_utilization_bps, get_borrow_rate_bps, get_supply_rate_bps};
+
+#[derive(Accounts)]
+pub struct GetBankState<'info> {
+    pub bank: Account<'info, Bank>,
+    // Required when the bank references an interest rate model
+    pub interest_rate_model: Option<Account<'info, InterestRateModel>>,
+    #[account(
+        seeds = [b"treasury", bank.mint_address.as_ref()],
+        bump,
+    )]
+    pub bank_token_account: InterfaceAccount<'info, TokenAccount>,
+}
+
+#[derive(Accounts)]
+pub struct RefreshBank<'info> {
+    #[account(mut)]
+    pub bank: Account<'info, Bank>,
+    // Required when the bank references an interest rate model
+    pub interest_rate_model: Option<Account<'info, InterestRateModel>>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct BankState {
+    pub mint_address: Pubkey,
+    // Totals with interest accrued up to now
+    pub total_deposits: u64,
+    pub total_borrowed: u64,
+    pub total_fixed_borrowed: u64,
+    pub total_reserves: u64,
+    pub utilization_bps: u64,
+    pub borrow_apr_bps: u64,
+    pub supply_apr_bps: u64,        // simple rate, not compounded
+    pub available_liquidity: u64,   // deposits not lent out, bounded by the treasury balance
+    pub treasury_balance: u64,
+    pub timestamp: i64,
+}
+
+// Read-only: market data for dashboards, returned as return data.
+// Interest is accrued on an in-memory copy of the bank, nothing is written.
+pub fn process_get_bank_state(ctx: Context<GetBankState>) -> Result<BankState> {
+    let mut bank = (*ctx.accounts.bank).clone();
+    accrue_interest(&mut bank, ctx.accounts.interest_rate_model.as_ref())?;
+    let rate_model = resolve_rate_model(&bank, ctx.accounts.interest_rate_model.as_ref())?;
+
+    let treasury_balance = ctx.accounts.bank_token_account.amount;
+    let available_liquidity = bank.total_deposits
+        .saturating_sub(bank.total_borrowed)
+        .saturating_sub(bank.total_fixed_borrowed)
+        .min(treasury_balance);
+
+    Ok(BankState {
+        mint_address: bank.mint_address,
+        total_deposits: bank.total_deposits,
+        total_borrowed: bank.total_borrowed,
+        total_fixed_borrowed: bank.total_fixed_borrowed,
+        total_reserves: bank.total_reserves,
+        utilization_bps: get_utilization_bps(&bank)?,
+        borrow_apr_bps: get_borrow_rate_bps(&bank, rate_model.as_ref())?,
+        supply_apr_bps: get_supply_rate_bps(&bank, rate_model.as_ref())?,
+        available_liquidity,
+        treasury_balance,
+        timestamp: Clock::get()?.unix_timestamp,
+    })
+}
+
+// Permissionless: accrues interest up to now without any other action
+pub fn process_refresh_bank(ctx: Context<RefreshBank>) -> Result<()> {
+    accrue_interest(&mut ctx.accounts.bank, ctx.accounts.interest_rate_model.as_ref())?;
+    msg!("Bank {} refreshed: borrowed {}, deposits {}",
+         ctx.accounts.bank.mint_address, ctx.accounts.bank.total_borrowed, ctx.accounts.bank.total_deposits);
+    Ok(())
+}
@@ -1,11 +1,15 @@
 use anchor_lang::prelude::*;
 use anchor_spl::associated_token::AssociatedToken;
 use anchor_spl::token_interface::{ self, Mint, TokenAccount, TokenInterface, TransferChecked };
-use pyth_solana_receiver_sdk::price_update::{get_feed_id_from_hex, PriceUpdateV2};
-use crate::constants::{MAXIMUM_AGE, SOL_USD_FEED_ID, USDC_USD_FEED_ID, BPS_DENOMINATOR};
+use pyth_solana_receiver_sdk::price_update::PriceUpdateV2;
+use decimal::{Decimal, MathError, Rounding};
 use crate::state::*;
 use crate::error::ErrorCode;
 use super::interest::accrue_interest;
+use super::rewards::checkpoint_rewards;
+use super::referral::record_referral_fee;
+use super::health_monitor::update_user_health_factor;
+use super::utils::{pin_position_mint, get_sol_and_usdc_prices, calculate_position_values, calculate_borrow_capacity, shares_from_amount, token_value, ValuationPrice};
 
 #[derive(Accounts)]
 pub struct Borrow<'info> {
@@ -18,6 +22,13 @@ pub struct Borrow<'info> {
         bump,
     )]  
     pub bank: Account<'info, Bank>,
+    // Required when the bank references an interest rate model
+    pub interest_rate_model: Option<Account<'info, InterestRateModel>>,
+    // Required while the bank's borrow reward pool is emitting, user_rewards once the user opted in
+    #[account(mut)]
+    pub reward_pool: Option<Box<Account<'info, RewardPool>>>,
+    #[account(mut)]
+    pub user_rewards: Option<Box<Account<'info, UserRewards>>>,
     #[account(
         mut, 
         seeds = [b"treasury", mint.key().as_ref()],
@@ -38,6 +49,13 @@ pub struct Borrow<'info> {
         associated_token::token_program = token_program,
     )]
     pub user_token_account: InterfaceAccount<'info, TokenAccount>, 
+    // Optional referrer credited with a share of the origination fee
+    #[account(
+        mut,
+        constraint = referrer_fees.mint_address == mint.key()
+            && referrer_fees.referrer != signer.key() @ ErrorCode::InvalidReferrer,
+    )]
+    pub referrer_fees: Option<Account<'info, ReferrerFees>>,
     pub price_update: Account<'info, PriceUpdateV2>,
     pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
@@ -45,43 +63,19 @@ pub struct Borrow<'info> {
 }
 
 pub fn process_borrow(ctx: Context<Borrow>, amount: u64) -> Result<()> {
-    accrue_interest(&mut ctx.accounts.bank)?;
-    let bank = &mut ctx.accounts.bank;
-    let user = &mut ctx.accounts.user_account;
+    let accounts = &mut *ctx.accounts;
+    record_borrow(&mut BorrowPosition {
+        mint: &accounts.mint,
+        bank: &mut accounts.bank,
+        interest_rate_model: accounts.interest_rate_model.as_ref(),
+        reward_pool: accounts.reward_pool.as_deref_mut(),
+        user_rewards: accounts.user_rewards.as_deref_mut(),
+        user_account: &mut accounts.user_account,
+        referrer_fees: accounts.referrer_fees.as_mut(),
+        price_update: &accounts.price_update,
+    }, ctx.remaining_accounts, amount)?;
 
-    let price_update = &ctx.accounts.price_update;
     let mint_key = ctx.accounts.mint.key();
-    let user_usdc = user.usdc_address;
-
-    let total_collateral: u64;
-    
-
-    match mint_key {
-        key if key == user_usdc => {
-            let sol_feed_id = get_feed_id_from_hex(SOL_USD_FEED_ID)
-                .map_err(|_| error!(ErrorCode::OracleError))?; 
-            let sol_price = price_update
-                .get_price_no_older_than(&Clock::get()?, MAXIMUM_AGE, &sol_feed_id)
-                .map_err(|_| error!(ErrorCode::OracleError))?;
-            total_collateral = sol_price.price as u64 * user.deposited_sol;
-        },
-        _ => {
-            let usdc_feed_id = get_feed_id_from_hex(USDC_USD_FEED_ID)
-                .map_err(|_| error!(ErrorCode::OracleError))?;
-            let usdc_price = price_update
-                .get_price_no_older_than(&Clock::get()?, MAXIMUM_AGE, &usdc_feed_id)
-                .map_err(|_| error!(ErrorCode::OracleError))?;
-            total_collateral = usdc_price.price as u64 * user.deposited_usdc;
-
-        }
-    }
-
-    let borrowable_amount = total_collateral * bank.liquidation_threshold;
-
-    if borrowable_amount < amount {
-        return Err(ErrorCode::OverBorrowableAmount.into());
-    }       
-
     let transfer_cpi_accounts = TransferChecked {
         from: ctx.accounts.bank_token_account.to_account_info(),
         mint: ctx.accounts.mint.to_account_info(),
@@ -102,90 +96,87 @@ pub fn process_borrow(ctx: Context<Borrow>, amount: u64) -> Result<()> {
 
     token_interface::transfer_checked(cpi_ctx, amount, decimals)?;
 
-    if bank.total_borrowed == 0 {
-        bank.total_borrowed = amount;
-        bank.total_borrowed_shares = amount;
-    } 
-
-    let borrow_ratio = amount.checked_div(bank.total_borrowed).ok_or(ErrorCode::OverBorrowableAmount)?;
-    let users_shares = bank.total_borrowed_shares.checked_mul(borrow_ratio).ok_or(ErrorCode::OverBorrowableAmount)?;
+    Ok(())
+}
 
-    bank.total_borrowed += amount;
-    bank.total_borrowed_shares += users_shares; 
+// The accounts a borrow updates, shared by the token and native SOL borrows
+pub struct BorrowPosition<'a, 'info> {
+    pub mint: &'a InterfaceAccount<'info, Mint>,
+    pub bank: &'a mut Account<'info, Bank>,
+    pub interest_rate_model: Option<&'a Account<'info, InterestRateModel>>,
+    pub reward_pool: Option<&'a mut Account<'info, RewardPool>>,
+    pub user_rewards: Option<&'a mut Account<'info, UserRewards>>,
+    pub user_account: &'a mut Account<'info, User>,
+    pub referrer_fees: Option<&'a mut Account<'info, ReferrerFees>>,
+    pub price_update: &'a Account<'info, PriceUpdateV2>,
+}
 
-    match ctx.accounts.mint.to_account_info().key() {
-        key if key == user.usdc_address => {
-            user.borrowed_usdc += amount;
-            user.deposited_usdc_shares += users_shares;
-        },
-        _ => {
-            user.borrowed_sol += amount;
-            user.deposited_sol_shares += users_shares;
-        }
-    }
+// Add the borrow and its fee to the bank and the signer's position, the caller pays `amount` out of the treasury
+pub fn record_borrow(accounts: &mut BorrowPosition, fallback_sources: &[AccountInfo], amount: u64) -> Result<()> {
+    accrue_interest(accounts.bank, accounts.interest_rate_model)?;
+    let bank = &mut accounts.bank;
+    let user = &mut accounts.user_account;
+
+    let price_update = accounts.price_update;
+    let is_usdc = pin_position_mint(user, accounts.mint.key())?;
+
+    let (sol_price, usdc_price) = get_sol_and_usdc_prices(price_update, fallback_sources, user, &[bank])?;
+    let mode = bank.borrow_valuation_mode;
+    let (total_collateral, total_borrowed) = calculate_position_values(user, sol_price, usdc_price, mode)?;
+    let borrowable_amount = calculate_borrow_capacity(total_collateral, total_borrowed, bank.max_ltv)?;
+
+    // The origination fee is added to the debt, the user receives `amount`. Rounded up so small
+    // borrows cannot dodge it.
+    let fee = Decimal::from_integer(amount)
+        .try_mul_bps(bank.borrow_fee_bps, Rounding::Ceil)?
+        .try_to_u64(Rounding::Ceil)?;
+    let debt = amount.checked_add(fee).ok_or(MathError::Overflow)?;
+
+    let borrow_price = if is_usdc { usdc_price.debt_price(mode)? } else { sol_price.debt_price(mode)? };
+    if borrowable_amount < token_value(borrow_price, debt, accounts.mint.decimals, Rounding::Ceil)? {
+        return Err(ErrorCode::OverBorrowableAmount.into());
+    }       
 
-    // Update health factor after borrowing
-    update_user_health_factor(user, &price_update)?;
+    let users_shares = shares_from_amount(debt, bank.total_borrowed, bank.total_borrowed_shares, Rounding::Ceil)?;
 
-    Ok(())
-}
+    bank.total_borrowed += debt;
+    bank.total_borrowed_shares += users_shares; 
 
-fn update_user_health_factor(user: &mut User, price_update: &PriceUpdateV2) -> Result<()> {
-    let sol_feed_id = get_feed_id_from_hex(SOL_USD_FEED_ID)
-        .map_err(|_| error!(ErrorCode::OracleError))?;
-    let usdc_feed_id = get_feed_id_from_hex(USDC_USD_FEED_ID)
-        .map_err(|_| error!(ErrorCode::OracleError))?;
-
-    let sol_price = price_update
-        .get_price_no_older_than(&Clock::get()?, MAXIMUM_AGE, &sol_feed_id)
-        .map_err(|_| error!(ErrorCode::OracleError))?;
-    let usdc_price = price_update
-        .get_price_no_older_than(&Clock::get()?, MAXIMUM_AGE, &usdc_feed_id)
-        .map_err(|_| error!(ErrorCode::OracleError))?;
-
-    let total_collateral_value = (sol_price.price as u64)
-        .saturating_mul(user.deposited_sol)
-        .saturating_add((usdc_price.price as u64).saturating_mul(user.deposited_usdc));
-    
-    let total_borrowed_value = (sol_price.price as u64)
-        .saturating_mul(user.borrowed_sol)
-        .saturating_add((usdc_price.price as u64).saturating_mul(user.borrowed_usdc));
-
-    let health_factor = if total_borrowed_value == 0 {
-        u64::MAX 
+    if is_usdc {
+        user.borrowed_usdc += debt;
+        user.borrowed_usdc_shares += users_shares;
     } else {
-        (total_collateral_value as u128)
-            .saturating_mul(BPS_DENOMINATOR as u128)
-            .checked_div(total_borrowed_value as u128)
-            .unwrap_or(0) as u64
-    };
+        user.borrowed_sol += debt;
+        user.borrowed_sol_shares += users_shares;
+    }
 
-    // health factor
-    user.health_factor = health_factor;
-    user.last_health_check = Clock::get()?.unix_timestamp;
-
-   
-    if user.is_monitoring_enabled && health_factor < user.alert_threshold {
-        let now = Clock::get()?.unix_timestamp;
-        let hours_since_last_alert = (now - user.last_alert_sent) / 3600;
-        
-        if hours_since_last_alert >= user.alert_frequency_hours as i64 {
-            user.last_alert_sent = now;
-            
-            emit!(crate::instructions::health_monitor::HealthAlertEvent {
-                user: user.owner,
-                health_factor,
-                total_collateral_value,
-                total_borrowed_value,
-                sol_price: sol_price.price as u64,
-                usdc_price: usdc_price.price as u64,
-                timestamp: now,
-            });
-            
-            msg!("HEALTH ALERT: User {} health factor {} below threshold {}", 
-                 user.owner, health_factor, user.alert_threshold);
-        }
+    // Fee goes to reserves, less the referrer's share which is held until the borrower repays and
+    // the referrer claims it
+    if fee > 0 {
+        let referral_fee = match accounts.referrer_fees.as_mut() {
+            Some(referrer_fees) => {
+                let referral_fee = Decimal::from_integer(fee)
+                    .try_mul_bps(bank.referral_fee_bps, Rounding::Floor)?
+                    .try_to_u64(Rounding::Floor)?;
+                record_referral_fee(user, is_usdc, referrer_fees, referral_fee)?;
+                referral_fee
+            }
+            None => 0,
+        };
+        bank.total_referral_fees = bank.total_referral_fees.saturating_add(referral_fee);
+        bank.total_reserves = bank.total_reserves.saturating_add(fee - referral_fee);
+        msg!("Borrow fee {}: {} to referrer", fee, referral_fee);
     }
 
+    // Update health factor after borrowing
+    update_user_health_factor(user, price_update, fallback_sources, &[bank])?;
+    checkpoint_rewards(
+        accounts.bank,
+        accounts.user_account,
+        RewardSide::Borrow,
+        accounts.reward_pool.as_deref_mut(),
+        accounts.user_rewards.as_deref_mut(),
+    )?;
+
     Ok(())
-}
\ No newline at end of file
+}
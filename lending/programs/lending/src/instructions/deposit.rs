@@ -1,11 +1,13 @@
 use anchor_lang::prelude::*;
 use anchor_spl::associated_token::AssociatedToken;
-use anchor_spl::token_interface::{ self, Mint, TokenAccount, TokenInterface, TransferChecked };
-use pyth_solana_receiver_sdk::price_update::{get_feed_id_from_hex, PriceUpdateV2};
-use crate::constants::{MAXIMUM_AGE, SOL_USD_FEED_ID, USDC_USD_FEED_ID, BPS_DENOMINATOR};
+use anchor_spl::token_interface::{ Mint, TokenAccount, TokenInterface };
+use pyth_solana_receiver_sdk::price_update::PriceUpdateV2;
+use decimal::Rounding;
 use crate::state::*;
-use crate::error::ErrorCode;
 use super::interest::accrue_interest;
+use super::rewards::checkpoint_rewards;
+use super::health_monitor::update_user_health_factor;
+use super::utils::{pin_position_mint, shares_from_amount, transfer_into_treasury};
 
 #[derive(Accounts)]
 pub struct Deposit<'info> {
@@ -18,6 +20,13 @@ pub struct Deposit<'info> {
         bump,
     )]  
     pub bank: Account<'info, Bank>,
+    // Required when the bank references an interest rate model
+    pub interest_rate_model: Option<Account<'info, InterestRateModel>>,
+    // Required while the bank's deposit reward pool is emitting, user_rewards once the user opted in
+    #[account(mut)]
+    pub reward_pool: Option<Box<Account<'info, RewardPool>>>,
+    #[account(mut)]
+    pub user_rewards: Option<Box<Account<'info, UserRewards>>>,
     #[account(
         mut, 
         seeds = [b"treasury", mint.key().as_ref()],
@@ -44,43 +53,57 @@ pub struct Deposit<'info> {
 }
 
 pub fn process_deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
-    // Accrue interest before state mutations
-    accrue_interest(&mut ctx.accounts.bank)?;
-    let transfer_cpi_accounts = TransferChecked {
-        from: ctx.accounts.user_token_account.to_account_info(),
-        mint: ctx.accounts.mint.to_account_info(),
-        to: ctx.accounts.bank_token_account.to_account_info(),
-        authority: ctx.accounts.signer.to_account_info(),
-    };
+    // Credit only what arrived, transfer fees are borne by the depositor
+    let received = transfer_into_treasury(
+        ctx.accounts.token_program.to_account_info(),
+        ctx.accounts.user_token_account.to_account_info(),
+        ctx.accounts.signer.to_account_info(),
+        &ctx.accounts.mint,
+        &mut ctx.accounts.bank_token_account,
+        amount,
+    )?;
+
+    let accounts = ctx.accounts;
+    record_deposit(&mut DepositPosition {
+        mint: accounts.mint.key(),
+        bank: &mut accounts.bank,
+        interest_rate_model: accounts.interest_rate_model.as_ref(),
+        reward_pool: accounts.reward_pool.as_deref_mut(),
+        user_rewards: accounts.user_rewards.as_deref_mut(),
+        user_account: &mut accounts.user_account,
+        price_update: &accounts.price_update,
+    }, ctx.remaining_accounts, received)
+}
 
-    let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), transfer_cpi_accounts);
-    let decimals = ctx.accounts.mint.decimals;
+// The accounts a deposit updates, shared by the token and native SOL deposits
+pub struct DepositPosition<'a, 'info> {
+    pub mint: Pubkey,
+    pub bank: &'a mut Account<'info, Bank>,
+    pub interest_rate_model: Option<&'a Account<'info, InterestRateModel>>,
+    pub reward_pool: Option<&'a mut Account<'info, RewardPool>>,
+    pub user_rewards: Option<&'a mut Account<'info, UserRewards>>,
+    pub user_account: &'a mut Account<'info, User>,
+    pub price_update: &'a Account<'info, PriceUpdateV2>,
+}
 
-    token_interface::transfer_checked(cpi_ctx, amount, decimals)?;
+// Credit `amount`, already moved into the treasury, to the bank and the signer's position
+pub fn record_deposit(accounts: &mut DepositPosition, fallback_sources: &[AccountInfo], amount: u64) -> Result<()> {
+    // Accrue interest before state mutations
+    accrue_interest(accounts.bank, accounts.interest_rate_model)?;
 
-    let bank = &mut ctx.accounts.bank;
-    let mint_key = ctx.accounts.mint.key();
+    let bank = &mut accounts.bank;
+    let mint_key = accounts.mint;
 
-    if bank.total_deposits == 0 {
-        bank.total_deposits = amount;
-        bank.total_deposit_shares = amount;
-    }
-    
-    let deposit_ratio = amount.checked_div(bank.total_deposits).ok_or(ErrorCode::InsufficientFunds)?;
-    let users_shares = bank.total_deposit_shares.checked_mul(deposit_ratio).ok_or(ErrorCode::InsufficientFunds)?;
+    let users_shares = shares_from_amount(amount, bank.total_deposits, bank.total_deposit_shares, Rounding::Floor)?;
     
-    let user = &mut ctx.accounts.user_account;
-    let user_usdc = user.usdc_address;
+    let user = &mut accounts.user_account;
     
-    match mint_key {
-        key if key == user_usdc => {
-            user.deposited_usdc += amount;
-            user.deposited_usdc_shares += users_shares;
-        },
-        _ => {
-            user.deposited_sol += amount;
-            user.deposited_sol_shares += users_shares; 
-        }
+    if pin_position_mint(user, mint_key)? {
+        user.deposited_usdc += amount;
+        user.deposited_usdc_shares += users_shares;
+    } else {
+        user.deposited_sol += amount;
+        user.deposited_sol_shares += users_shares; 
     }
 
 
@@ -89,73 +112,16 @@ pub fn process_deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
 
     user.last_updated = Clock::get()?.unix_timestamp;
 
+    checkpoint_rewards(
+        accounts.bank,
+        accounts.user_account,
+        RewardSide::Deposit,
+        accounts.reward_pool.as_deref_mut(),
+        accounts.user_rewards.as_deref_mut(),
+    )?;
+
     // Update health factor after depositing
-    update_user_health_factor(&mut ctx.accounts.user_account, &ctx.accounts.price_update)?;
+    update_user_health_factor(accounts.user_account, accounts.price_update, fallback_sources, &[accounts.bank])?;
 
     Ok(())
 }
-
-// Helper function to update user health factor
-fn update_user_health_factor(user: &mut User, price_update: &PriceUpdateV2) -> Result<()> {
-    // Get current prices
-    let sol_feed_id = get_feed_id_from_hex(SOL_USD_FEED_ID)
-        .map_err(|_| error!(ErrorCode::OracleError))?;
-    let usdc_feed_id = get_feed_id_from_hex(USDC_USD_FEED_ID)
-        .map_err(|_| error!(ErrorCode::OracleError))?;
-
-    let sol_price = price_update
-        .get_price_no_older_than(&Clock::get()?, MAXIMUM_AGE, &sol_feed_id)
-        .map_err(|_| error!(ErrorCode::OracleError))?;
-    let usdc_price = price_update
-        .get_price_no_older_than(&Clock::get()?, MAXIMUM_AGE, &usdc_feed_id)
-        .map_err(|_| error!(ErrorCode::OracleError))?;
-
-    // Calculate total collateral and borrowed values
-    let total_collateral_value = (sol_price.price as u64)
-        .saturating_mul(user.deposited_sol)
-        .saturating_add((usdc_price.price as u64).saturating_mul(user.deposited_usdc));
-    
-    let total_borrowed_value = (sol_price.price as u64)
-        .saturating_mul(user.borrowed_sol)
-        .saturating_add((usdc_price.price as u64).saturating_mul(user.borrowed_usdc));
-
-    // Calculate health factor
-    let health_factor = if total_borrowed_value == 0 {
-        u64::MAX // Perfect health if no debt
-    } else {
-        (total_collateral_value as u128)
-            .saturating_mul(BPS_DENOMINATOR as u128)
-            .checked_div(total_borrowed_value as u128)
-            .unwrap_or(0) as u64
-    };
-
-    // Update user health factor
-    user.health_factor = health_factor;
-    user.last_health_check = Clock::get()?.unix_timestamp;
-
-    // Check if alert should be sent
-    if user.is_monitoring_enabled && health_factor < user.alert_threshold {
-        let now = Clock::get()?.unix_timestamp;
-        let hours_since_last_alert = (now - user.last_alert_sent) / 3600;
-        
-        if hours_since_last_alert >= user.alert_frequency_hours as i64 {
-            user.last_alert_sent = now;
-            
-            // Emit health alert event
-            emit!(crate::instructions::health_monitor::HealthAlertEvent {
-                user: user.owner,
-                health_factor,
-                total_collateral_value,
-                total_borrowed_value,
-                sol_price: sol_price.price as u64,
-                usdc_price: usdc_price.price as u64,
-                timestamp: now,
-            });
-            
-            msg!("HEALTH ALERT: User {} health factor {} below threshold {}", 
-                 user.owner, health_factor, user.alert_threshold);
-        }
-    }
-
-    Ok(())
-}
\ No newline at end of file
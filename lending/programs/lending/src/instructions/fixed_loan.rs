@@ -0,0 +1,450 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_interface::{ self, Mint, TokenAccount, TokenInterface, TransferChecked };
+use pyth_solana_receiver_sdk::price_update::PriceUpdateV2;
+use crate::constants::{
+    BPS_DENOMINATOR, FIXED_RATE_PREMIUM_BPS, MIN_FIXED_LOAN_TERM, MAX_FIXED_LOAN_TERM, FIXED_LOAN_GRACE_PERIOD,
+};
+use decimal::{Decimal, MathError, Rounding};
+use crate::state::*;
+use crate::error::ErrorCode;
+use super::interest::{accrue_interest, resolve_rate_model, get_borrow_rate_bps, distribute_interest, fixed_loan_interest};
+use super::health_monitor::update_user_health_factor;
+use super::rewards::checkpoint_rewards;
+use super::utils::{
+    get_sol_and_usdc_prices, calculate_position_values, calculate_borrow_capacity, shares_from_amount, token_value, usd_price,
+    pin_position_mint, transfer_into_treasury, amount_before_transfer_fee, ValuationPrice,
+};
+
+#[derive(Accounts)]
+#[instruction(loan_id: u64)]
+pub struct BorrowFixed<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        seeds = [mint.key().as_ref()],
+        bump,
+    )]
+    pub bank: Box<Account<'info, Bank>>,
+    // Required when the bank references an interest rate model
+    pub interest_rate_model: Option<Box<Account<'info, InterestRateModel>>>,
+    #[account(
+        mut,
+        seeds = [b"treasury", mint.key().as_ref()],
+        bump,
+    )]
+    pub bank_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [signer.key().as_ref()],
+        bump,
+    )]
+    pub user_account: Box<Account<'info, User>>,
+    #[account(
+        init,
+        payer = signer,
+        space = 8 + FixedLoan::INIT_SPACE,
+        seeds = [b"fixed_loan", signer.key().as_ref(), mint.key().as_ref(), loan_id.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub fixed_loan: Box<Account<'info, FixedLoan>>,
+    #[account(
+        init_if_needed,
+        payer = signer,
+        associated_token::mint = mint,
+        associated_token::authority = signer,
+        associated_token::token_program = token_program,
+    )]
+    pub user_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub price_update: Box<Account<'info, PriceUpdateV2>>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RepayFixed<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        seeds = [mint.key().as_ref()],
+        bump,
+    )]
+    pub bank: Box<Account<'info, Bank>>,
+    // Required when the bank references an interest rate model
+    pub interest_rate_model: Option<Box<Account<'info, InterestRateModel>>>,
+    #[account(
+        mut,
+        seeds = [b"treasury", mint.key().as_ref()],
+        bump,
+    )]
+    pub bank_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [signer.key().as_ref()],
+        bump,
+    )]
+    pub user_account: Box<Account<'info, User>>,
+    #[account(
+        mut,
+        close = signer,
+        seeds = [b"fixed_loan", signer.key().as_ref(), mint.key().as_ref(), fixed_loan.loan_id.to_le_bytes().as_ref()],
+        bump = fixed_loan.bump,
+    )]
+    pub fixed_loan: Box<Account<'info, FixedLoan>>,
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = signer,
+        associated_token::token_program = token_program,
+    )]
+    pub user_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct LiquidateFixedLoan<'info> {
+    #[account(mut)]
+    pub liquidator: Signer<'info>,
+    pub price_update: Box<Account<'info, PriceUpdateV2>>,
+    #[account(
+        constraint = collateral_mint.key() != loan_mint.key() @ ErrorCode::InvalidAssetPair,
+    )]
+    pub collateral_mint: Box<InterfaceAccount<'info, Mint>>,
+    pub loan_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        seeds = [collateral_mint.key().as_ref()],
+        bump,
+    )]
+    pub collateral_bank: Box<Account<'info, Bank>>,
+    pub collateral_rate_model: Option<Box<Account<'info, InterestRateModel>>>,
+    // Required while the collateral bank's deposit reward pool is emitting, user_rewards once the owner opted in
+    #[account(mut)]
+    pub reward_pool: Option<Box<Account<'info, RewardPool>>>,
+    #[account(mut)]
+    pub user_rewards: Option<Box<Account<'info, UserRewards>>>,
+    #[account(
+        mut,
+        seeds = [b"treasury", collateral_mint.key().as_ref()],
+        bump,
+    )]
+    pub collateral_bank_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [loan_mint.key().as_ref()],
+        bump,
+    )]
+    pub loan_bank: Box<Account<'info, Bank>>,
+    pub loan_rate_model: Option<Box<Account<'info, InterestRateModel>>>,
+    #[account(
+        mut,
+        seeds = [b"treasury", loan_mint.key().as_ref()],
+        bump,
+    )]
+    pub loan_bank_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [fixed_loan.owner.as_ref()],
+        bump,
+    )]
+    pub user_account: Box<Account<'info, User>>,
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"fixed_loan", fixed_loan.owner.as_ref(), loan_mint.key().as_ref(), fixed_loan.loan_id.to_le_bytes().as_ref()],
+        bump = fixed_loan.bump,
+    )]
+    pub fixed_loan: Box<Account<'info, FixedLoan>>,
+    // Receives the loan account's rent
+    #[account(
+        mut,
+        address = fixed_loan.owner,
+    )]
+    pub owner: SystemAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = liquidator,
+        associated_token::mint = collateral_mint,
+        associated_token::authority = liquidator,
+        associated_token::token_program = token_program,
+    )]
+    pub liquidator_collateral_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        associated_token::mint = loan_mint,
+        associated_token::authority = liquidator,
+        associated_token::token_program = token_program,
+    )]
+    pub liquidator_loan_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event]
+pub struct FixedLoanEvent {
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    pub loan_id: u64,
+    pub principal: u64,
+    pub interest: u64,
+    pub rate_bps: u64,
+    pub maturity_ts: i64,
+    pub liquidator: Option<Pubkey>,
+    pub timestamp: i64,
+}
+
+// Borrow at a rate fixed for the life of the loan: the bank's current variable rate plus a premium.
+// `max_rate_bps` protects the borrower from the curve moving before the transaction lands.
+pub fn process_borrow_fixed(
+    ctx: Context<BorrowFixed>,
+    loan_id: u64,
+    amount: u64,
+    term_seconds: i64,
+    max_rate_bps: u64,
+) -> Result<()> {
+    require!(
+        (MIN_FIXED_LOAN_TERM..=MAX_FIXED_LOAN_TERM).contains(&term_seconds),
+        ErrorCode::InvalidLoanTerm
+    );
+
+    accrue_interest(&mut ctx.accounts.bank, ctx.accounts.interest_rate_model.as_deref())?;
+    let rate_model = resolve_rate_model(&ctx.accounts.bank, ctx.accounts.interest_rate_model.as_deref())?;
+    let rate_bps = get_borrow_rate_bps(&ctx.accounts.bank, rate_model.as_ref())?
+        .checked_add(FIXED_RATE_PREMIUM_BPS)
+        .ok_or(MathError::Overflow)?;
+    require!(rate_bps <= max_rate_bps, ErrorCode::FixedRateTooHigh);
+
+    let bank = &mut ctx.accounts.bank;
+    let user = &mut ctx.accounts.user_account;
+    let mint_key = ctx.accounts.mint.key();
+    let is_usdc = pin_position_mint(user, mint_key)?;
+
+    let (sol_price, usdc_price) = get_sol_and_usdc_prices(&ctx.accounts.price_update, ctx.remaining_accounts, user, &[bank])?;
+    let mode = bank.borrow_valuation_mode;
+    let (total_collateral, total_borrowed) = calculate_position_values(user, sol_price, usdc_price, mode)?;
+    let borrowable_amount = calculate_borrow_capacity(total_collateral, total_borrowed, bank.max_ltv)?;
+    let borrow_price = if is_usdc { usdc_price.debt_price(mode)? } else { sol_price.debt_price(mode)? };
+    if borrowable_amount < token_value(borrow_price, amount, ctx.accounts.mint.decimals, Rounding::Ceil)? {
+        return Err(ErrorCode::OverBorrowableAmount.into());
+    }
+
+    let transfer_cpi_accounts = TransferChecked {
+        from: ctx.accounts.bank_token_account.to_account_info(),
+        mint: ctx.accounts.mint.to_account_info(),
+        to: ctx.accounts.user_token_account.to_account_info(),
+        authority: ctx.accounts.bank_token_account.to_account_info(),
+    };
+    let signer_seeds: &[&[&[u8]]] = &[
+        &[
+            b"treasury",
+            mint_key.as_ref(),
+            &[ctx.bumps.bank_token_account],
+        ],
+    ];
+    let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), transfer_cpi_accounts).with_signer(signer_seeds);
+    token_interface::transfer_checked(cpi_ctx, amount, ctx.accounts.mint.decimals)?;
+
+    bank.total_fixed_borrowed = bank.total_fixed_borrowed.saturating_add(amount);
+    if is_usdc {
+        user.fixed_borrowed_usdc = user.fixed_borrowed_usdc.saturating_add(amount);
+    } else {
+        user.fixed_borrowed_sol = user.fixed_borrowed_sol.saturating_add(amount);
+    }
+
+    let now = Clock::get()?.unix_timestamp;
+    let loan = &mut ctx.accounts.fixed_loan;
+    loan.owner = ctx.accounts.signer.key();
+    loan.mint_address = mint_key;
+    loan.loan_id = loan_id;
+    loan.principal = amount;
+    loan.rate_bps = rate_bps;
+    loan.start_ts = now;
+    loan.maturity_ts = now.saturating_add(term_seconds);
+    loan.bump = ctx.bumps.fixed_loan;
+
+    update_user_health_factor(user, &ctx.accounts.price_update, ctx.remaining_accounts, &[bank])?;
+
+    emit!(FixedLoanEvent {
+        owner: loan.owner,
+        mint: mint_key,
+        loan_id,
+        principal: amount,
+        interest: 0,
+        rate_bps,
+        maturity_ts: loan.maturity_ts,
+        liquidator: None,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+// Repay a fixed loan in full, with interest at the quoted rate up to now. Early repayment
+// only pays the interest accrued so far.
+pub fn process_repay_fixed(ctx: Context<RepayFixed>) -> Result<()> {
+    accrue_interest(&mut ctx.accounts.bank, ctx.accounts.interest_rate_model.as_deref())?;
+
+    let loan = &ctx.accounts.fixed_loan;
+    let now = Clock::get()?.unix_timestamp;
+    let interest = fixed_loan_interest(loan.principal, loan.rate_bps, now - loan.start_ts)?;
+    let amount_due = loan.principal.saturating_add(interest);
+
+    // The borrower bears any transfer fee, the full amount due must arrive in the treasury
+    let received = transfer_into_treasury(
+        ctx.accounts.token_program.to_account_info(),
+        ctx.accounts.user_token_account.to_account_info(),
+        ctx.accounts.signer.to_account_info(),
+        &ctx.accounts.mint,
+        &mut ctx.accounts.bank_token_account,
+        amount_before_transfer_fee(&ctx.accounts.mint, amount_due)?,
+    )?;
+    require!(received >= amount_due, ErrorCode::RepaymentShortfall);
+
+    let user = &mut ctx.accounts.user_account;
+    settle_fixed_loan(&mut ctx.accounts.bank, user, loan, interest)?;
+
+    emit!(FixedLoanEvent {
+        owner: loan.owner,
+        mint: loan.mint_address,
+        loan_id: loan.loan_id,
+        principal: loan.principal,
+        interest,
+        rate_bps: loan.rate_bps,
+        maturity_ts: loan.maturity_ts,
+        liquidator: None,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+// Permissionless once a loan is unpaid past maturity plus the grace period: the liquidator
+// repays it in full and receives the owner's collateral worth the amount due plus the
+// collateral bank's liquidation bonus.
+pub fn process_liquidate_fixed_loan(ctx: Context<LiquidateFixedLoan>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let loan = &ctx.accounts.fixed_loan;
+    require!(
+        now > loan.maturity_ts.saturating_add(FIXED_LOAN_GRACE_PERIOD),
+        ErrorCode::LoanNotOverdue
+    );
+
+    accrue_interest(&mut ctx.accounts.collateral_bank, ctx.accounts.collateral_rate_model.as_deref())?;
+    accrue_interest(&mut ctx.accounts.loan_bank, ctx.accounts.loan_rate_model.as_deref())?;
+
+    let interest = fixed_loan_interest(loan.principal, loan.rate_bps, now - loan.start_ts)?;
+    let amount_due = loan.principal.saturating_add(interest);
+
+    let user = &mut ctx.accounts.user_account;
+    let collateral_mint_key = ctx.accounts.collateral_mint.key();
+    let collateral_is_usdc = collateral_mint_key == user.usdc_address;
+    require!(collateral_is_usdc || collateral_mint_key == user.sol_address, ErrorCode::InvalidAssetPair);
+    let (sol_price, usdc_price) = get_sol_and_usdc_prices(
+        &ctx.accounts.price_update,
+        ctx.remaining_accounts,
+        user,
+        &[&ctx.accounts.collateral_bank, &ctx.accounts.loan_bank],
+    )?;
+    let (collateral_price, loan_price) = if collateral_is_usdc {
+        (usdc_price.price, sol_price.price)
+    } else {
+        (sol_price.price, usdc_price.price)
+    };
+
+    let collateral_bank = &mut ctx.accounts.collateral_bank;
+    // Value the amount due in whole tokens of each mint and round the seized collateral down,
+    // against the liquidator
+    let seize_value = Decimal::from_token_amount(amount_due, ctx.accounts.loan_mint.decimals)?
+        .try_mul(usd_price(loan_price)?, Rounding::Floor)?
+        .try_mul_bps(BPS_DENOMINATOR.saturating_add(collateral_bank.liquidation_bonus), Rounding::Floor)?;
+    let deposited = if collateral_is_usdc { user.deposited_usdc } else { user.deposited_sol };
+    let seized = seize_value
+        .try_div(usd_price(collateral_price)?, Rounding::Floor)?
+        .try_to_token_amount(ctx.accounts.collateral_mint.decimals, Rounding::Floor)?
+        .min(deposited);
+
+    // The liquidator bears any transfer fee, the full amount due must arrive in the treasury
+    let received = transfer_into_treasury(
+        ctx.accounts.token_program.to_account_info(),
+        ctx.accounts.liquidator_loan_token_account.to_account_info(),
+        ctx.accounts.liquidator.to_account_info(),
+        &ctx.accounts.loan_mint,
+        &mut ctx.accounts.loan_bank_token_account,
+        amount_before_transfer_fee(&ctx.accounts.loan_mint, amount_due)?,
+    )?;
+    require!(received >= amount_due, ErrorCode::RepaymentShortfall);
+
+    let transfer_to_liquidator = TransferChecked {
+        from: ctx.accounts.collateral_bank_token_account.to_account_info(),
+        mint: ctx.accounts.collateral_mint.to_account_info(),
+        to: ctx.accounts.liquidator_collateral_token_account.to_account_info(),
+        authority: ctx.accounts.collateral_bank_token_account.to_account_info(),
+    };
+    let signer_seeds: &[&[&[u8]]] = &[
+        &[
+            b"treasury",
+            collateral_mint_key.as_ref(),
+            &[ctx.bumps.collateral_bank_token_account],
+        ],
+    ];
+    let cpi_ctx_to_liquidator = CpiContext::new(ctx.accounts.token_program.to_account_info(), transfer_to_liquidator).with_signer(signer_seeds);
+    token_interface::transfer_checked(cpi_ctx_to_liquidator, seized, ctx.accounts.collateral_mint.decimals)?;
+
+    let seized_shares = shares_from_amount(
+        seized,
+        collateral_bank.total_deposits,
+        collateral_bank.total_deposit_shares,
+        Rounding::Ceil,
+    )?;
+    collateral_bank.total_deposits = collateral_bank.total_deposits.saturating_sub(seized);
+    collateral_bank.total_deposit_shares = collateral_bank.total_deposit_shares.saturating_sub(seized_shares);
+    if collateral_is_usdc {
+        user.deposited_usdc = user.deposited_usdc.saturating_sub(seized);
+        user.deposited_usdc_shares = user.deposited_usdc_shares.saturating_sub(seized_shares);
+    } else {
+        user.deposited_sol = user.deposited_sol.saturating_sub(seized);
+        user.deposited_sol_shares = user.deposited_sol_shares.saturating_sub(seized_shares);
+    }
+
+    settle_fixed_loan(&mut ctx.accounts.loan_bank, user, loan, interest)?;
+    checkpoint_rewards(
+        &ctx.accounts.collateral_bank,
+        &ctx.accounts.user_account,
+        RewardSide::Deposit,
+        ctx.accounts.reward_pool.as_deref_mut(),
+        ctx.accounts.user_rewards.as_deref_mut(),
+    )?;
+
+    emit!(FixedLoanEvent {
+        owner: loan.owner,
+        mint: loan.mint_address,
+        loan_id: loan.loan_id,
+        principal: loan.principal,
+        interest,
+        rate_bps: loan.rate_bps,
+        maturity_ts: loan.maturity_ts,
+        liquidator: Some(ctx.accounts.liquidator.key()),
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+// Remove a repaid loan from the bank and user, and pay its interest to depositors
+fn settle_fixed_loan(bank: &mut Bank, user: &mut User, loan: &FixedLoan, interest: u64) -> Result<()> {
+    bank.total_fixed_borrowed = bank.total_fixed_borrowed.saturating_sub(loan.principal);
+    distribute_interest(bank, interest)?;
+
+    if loan.mint_address == user.usdc_address {
+        user.fixed_borrowed_usdc = user.fixed_borrowed_usdc.saturating_sub(loan.principal);
+    } else {
+        user.fixed_borrowed_sol = user.fixed_borrowed_sol.saturating_sub(loan.principal);
+    }
+    Ok(())
+}
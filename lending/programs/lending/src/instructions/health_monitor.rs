@@ -1,10 +1,9 @@
 use anchor_lang::prelude::*;
-use anchor_spl::associated_token::AssociatedToken;
-use anchor_spl::token_interface::{ Mint, TokenAccount, TokenInterface };
-use pyth_solana_receiver_sdk::price_update::{get_feed_id_from_hex, PriceUpdateV2};
-use crate::constants::{MAXIMUM_AGE, SOL_USD_FEED_ID, USDC_USD_FEED_ID, BPS_DENOMINATOR};
+use pyth_solana_receiver_sdk::price_update::PriceUpdateV2;
+use crate::constants::{MIN_ALERT_THRESHOLD_BPS, MAX_ALERT_THRESHOLD_BPS};
 use crate::state::*;
 use crate::error::ErrorCode;
+use super::utils::{get_sol_and_usdc_prices, calculate_position_values, calculate_health_factor, value_to_u64};
 
 #[derive(Accounts)]
 pub struct EnableHealthMonitoring<'info> {
@@ -82,126 +81,79 @@ pub fn process_update_health_threshold(
     new_threshold: u64,
     alert_frequency_hours: u8
 ) -> Result<()> {
-    let user = &mut ctx.accounts.user_account;
-    
-    require!(new_threshold >= 110, ErrorCode::InvalidThreshold);
-    require!(new_threshold <= 300, ErrorCode::InvalidThreshold);
-    require!(alert_frequency_hours >= 1 && alert_frequency_hours <= 168, ErrorCode::InvalidAlertFrequency);
-    
-    user.alert_threshold = new_threshold;
-    user.alert_frequency_hours = alert_frequency_hours;
-    
-    msg!("Health threshold updated to: {} bps, alert frequency: {} hours", new_threshold, alert_frequency_hours);
-    Ok(())
+    process_update_alert_tier(ctx, AlertSeverity::Warning, new_threshold, alert_frequency_hours)
 }
 
-pub fn process_check_health_factor(ctx: Context<CheckHealthFactor>) -> Result<()> {
+// Set one alert tier. Critical and liquidation-imminent tiers can be disabled with a threshold of 0.
+pub fn process_update_alert_tier(
+    ctx: Context<UpdateHealthThreshold>,
+    severity: AlertSeverity,
+    new_threshold: u64,
+    alert_frequency_hours: u8
+) -> Result<()> {
     let user = &mut ctx.accounts.user_account;
-    let price_update = &ctx.accounts.price_update;
-
-    //current prices
-    let sol_feed_id = get_feed_id_from_hex(SOL_USD_FEED_ID)
-        .map_err(|_| error!(ErrorCode::OracleError))?;
-    let usdc_feed_id = get_feed_id_from_hex(USDC_USD_FEED_ID)
-        .map_err(|_| error!(ErrorCode::OracleError))?;
-
-    let sol_price = price_update
-        .get_price_no_older_than(&Clock::get()?, MAXIMUM_AGE, &sol_feed_id)
-        .map_err(|_| error!(ErrorCode::OracleError))?;
-    let usdc_price = price_update
-        .get_price_no_older_than(&Clock::get()?, MAXIMUM_AGE, &usdc_feed_id)
-        .map_err(|_| error!(ErrorCode::OracleError))?;
-
-    // total collateral and borrowed values
-    let total_collateral_value = (sol_price.price as u64)
-        .saturating_mul(user.deposited_sol)
-        .saturating_add((usdc_price.price as u64).saturating_mul(user.deposited_usdc));
-    
-    let total_borrowed_value = (sol_price.price as u64)
-        .saturating_mul(user.borrowed_sol)
-        .saturating_add((usdc_price.price as u64).saturating_mul(user.borrowed_usdc));
-
-    // health factor
-    let health_factor = if total_borrowed_value == 0 {
-        u64::MAX // Perfect health if no debt
-    } else {
-        (total_collateral_value as u128)
-            .saturating_mul(BPS_DENOMINATOR as u128)
-            .checked_div(total_borrowed_value as u128)
-            .unwrap_or(0) as u64
-    };
 
-    user.health_factor = health_factor;
-    user.last_health_check = Clock::get()?.unix_timestamp;
+    let can_disable = severity != AlertSeverity::Warning;
+    require!(
+        (can_disable && new_threshold == 0)
+            || (MIN_ALERT_THRESHOLD_BPS..=MAX_ALERT_THRESHOLD_BPS).contains(&new_threshold),
+        ErrorCode::InvalidThreshold
+    );
+    require!((1..=168).contains(&alert_frequency_hours), ErrorCode::InvalidAlertFrequency);
+
+    match severity {
+        AlertSeverity::Warning => {
+            user.alert_threshold = new_threshold;
+            user.alert_frequency_hours = alert_frequency_hours;
+        },
+        AlertSeverity::Critical => {
+            user.critical_alert_threshold = new_threshold;
+            user.critical_alert_frequency_hours = alert_frequency_hours;
+        },
+        AlertSeverity::LiquidationImminent => {
+            user.imminent_alert_threshold = new_threshold;
+            user.imminent_alert_frequency_hours = alert_frequency_hours;
+        }
+    }
 
-    if user.is_monitoring_enabled && health_factor < user.alert_threshold {
-        let now = Clock::get()?.unix_timestamp;
-        let hours_since_last_alert = (now - user.last_alert_sent) / 3600;
-        
-        if hours_since_last_alert >= user.alert_frequency_hours as i64 {
-            user.last_alert_sent = now;
-            
-            emit!(HealthAlertEvent {
-                user: user.owner,
-                health_factor,
-                total_collateral_value,
-                total_borrowed_value,
-                sol_price: sol_price.price as u64,
-                usdc_price: usdc_price.price as u64,
-                timestamp: now,
-            });
-            
-            msg!("HEALTH ALERT: User {} health factor {} below threshold {}", 
-                 user.owner, health_factor, user.alert_threshold);
+    // Enabled tiers must be strictly decreasing so each one is reachable
+    let mut previous = u64::MAX;
+    for threshold in [user.alert_threshold, user.critical_alert_threshold, user.imminent_alert_threshold] {
+        if threshold == 0 {
+            continue;
         }
+        require!(threshold < previous, ErrorCode::InvalidAlertTierOrder);
+        previous = threshold;
     }
 
-    msg!("Health factor updated: {} bps", health_factor);
+    msg!("{:?} alert threshold updated to: {} bps, alert frequency: {} hours", severity, new_threshold, alert_frequency_hours);
+    Ok(())
+}
+
+pub fn process_check_health_factor(ctx: Context<CheckHealthFactor>) -> Result<()> {
+    let user = &mut ctx.accounts.user_account;
+    update_user_health_factor(user, &ctx.accounts.price_update, ctx.remaining_accounts, &[])?;
+
+    msg!("Health factor updated: {} bps", user.health_factor);
     Ok(())
 }
 
 pub fn process_create_health_snapshot(ctx: Context<CreateHealthSnapshot>) -> Result<()> {
     let user = &mut ctx.accounts.user_account;
     let health_snapshot = &mut ctx.accounts.health_snapshot;
-    let price_update = &ctx.accounts.price_update;
-
-    let sol_feed_id = get_feed_id_from_hex(SOL_USD_FEED_ID)
-        .map_err(|_| error!(ErrorCode::OracleError))?;
-    let usdc_feed_id = get_feed_id_from_hex(USDC_USD_FEED_ID)
-        .map_err(|_| error!(ErrorCode::OracleError))?;
-
-    let sol_price = price_update
-        .get_price_no_older_than(&Clock::get()?, MAXIMUM_AGE, &sol_feed_id)
-        .map_err(|_| error!(ErrorCode::OracleError))?;
-    let usdc_price = price_update
-        .get_price_no_older_than(&Clock::get()?, MAXIMUM_AGE, &usdc_feed_id)
-        .map_err(|_| error!(ErrorCode::OracleError))?;
-
-    let total_collateral_value = (sol_price.price as u64)
-        .saturating_mul(user.deposited_sol)
-        .saturating_add((usdc_price.price as u64).saturating_mul(user.deposited_usdc));
-    
-    let total_borrowed_value = (sol_price.price as u64)
-        .saturating_mul(user.borrowed_sol)
-        .saturating_add((usdc_price.price as u64).saturating_mul(user.borrowed_usdc));
+    let (sol_price, usdc_price) = get_sol_and_usdc_prices(&ctx.accounts.price_update, ctx.remaining_accounts, user, &[])?;
 
-    let health_factor = if total_borrowed_value == 0 {
-        u64::MAX
-    } else {
-        (total_collateral_value as u128)
-            .saturating_mul(BPS_DENOMINATOR as u128)
-            .checked_div(total_borrowed_value as u128)
-            .unwrap_or(0) as u64
-    };
+    let (total_collateral_value, total_borrowed_value) = calculate_position_values(user, sol_price, usdc_price, ValuationMode::Spot)?;
+    let health_factor = calculate_health_factor(total_collateral_value, total_borrowed_value);
 
     let now = Clock::get()?.unix_timestamp;
     health_snapshot.user = user.owner;
     health_snapshot.health_factor = health_factor;
-    health_snapshot.total_collateral_value = total_collateral_value;
-    health_snapshot.total_borrowed_value = total_borrowed_value;
+    health_snapshot.total_collateral_value = value_to_u64(total_collateral_value);
+    health_snapshot.total_borrowed_value = value_to_u64(total_borrowed_value);
     health_snapshot.timestamp = now;
-    health_snapshot.sol_price = sol_price.price as u64;
-    health_snapshot.usdc_price = usdc_price.price as u64;
+    health_snapshot.sol_price = sol_price.price;
+    health_snapshot.usdc_price = usdc_price.price;
 
     user.health_history_count = user.health_history_count.saturating_add(1);
 
@@ -209,10 +161,91 @@ pub fn process_create_health_snapshot(ctx: Context<CreateHealthSnapshot>) -> Res
     Ok(())
 }
 
+// Recompute and store the user's health factor, emitting an alert if a tier is crossed
+pub fn update_user_health_factor(
+    user: &mut User,
+    price_update: &PriceUpdateV2,
+    fallback_sources: &[AccountInfo],
+    banks: &[&Bank],
+) -> Result<()> {
+    let (sol_price, usdc_price) = get_sol_and_usdc_prices(price_update, fallback_sources, user, banks)?;
+
+    let (total_collateral_value, total_borrowed_value) = calculate_position_values(user, sol_price, usdc_price, ValuationMode::Spot)?;
+    let health_factor = calculate_health_factor(total_collateral_value, total_borrowed_value);
+
+    user.health_factor = health_factor;
+    user.last_health_check = Clock::get()?.unix_timestamp;
+
+    emit_health_alert(
+        user,
+        health_factor,
+        value_to_u64(total_collateral_value),
+        value_to_u64(total_borrowed_value),
+        sol_price.price,
+        usdc_price.price,
+    )
+}
+
+// Emit an alert for the most severe tier the health factor has fallen below,
+// subject to that tier's own cooldown. Less severe tiers are not re-emitted.
+pub fn emit_health_alert(
+    user: &mut User,
+    health_factor: u64,
+    total_collateral_value: u64,
+    total_borrowed_value: u64,
+    sol_price: u64,
+    usdc_price: u64,
+) -> Result<()> {
+    if !user.is_monitoring_enabled {
+        return Ok(());
+    }
+
+    let breached = |threshold: u64| threshold != 0 && health_factor < threshold;
+    let (severity, threshold, frequency_hours, last_sent) = if breached(user.imminent_alert_threshold) {
+        (AlertSeverity::LiquidationImminent, user.imminent_alert_threshold, user.imminent_alert_frequency_hours, user.last_imminent_alert_sent)
+    } else if breached(user.critical_alert_threshold) {
+        (AlertSeverity::Critical, user.critical_alert_threshold, user.critical_alert_frequency_hours, user.last_critical_alert_sent)
+    } else if breached(user.alert_threshold) {
+        (AlertSeverity::Warning, user.alert_threshold, user.alert_frequency_hours, user.last_alert_sent)
+    } else {
+        return Ok(());
+    };
+
+    let now = Clock::get()?.unix_timestamp;
+    let hours_since_last_alert = (now - last_sent) / 3600;
+    if hours_since_last_alert < frequency_hours as i64 {
+        return Ok(());
+    }
+
+    match severity {
+        AlertSeverity::Warning => user.last_alert_sent = now,
+        AlertSeverity::Critical => user.last_critical_alert_sent = now,
+        AlertSeverity::LiquidationImminent => user.last_imminent_alert_sent = now,
+    }
+
+    emit!(HealthAlertEvent {
+        user: user.owner,
+        severity,
+        health_factor,
+        threshold,
+        total_collateral_value,
+        total_borrowed_value,
+        sol_price,
+        usdc_price,
+        timestamp: now,
+    });
+
+    msg!("HEALTH ALERT ({:?}): User {} health factor {} below threshold {}", 
+         severity, user.owner, health_factor, threshold);
+    Ok(())
+}
+
 #[event]
 pub struct HealthAlertEvent {
     pub user: Pubkey,
+    pub severity: AlertSeverity,
     pub health_factor: u64,
+    pub threshold: u64,
     pub total_collateral_value: u64,
     pub total_borrowed_value: u64,
     pub sol_price: u64,
@@ -1,50 +1,253 @@
 use anchor_lang::prelude::*;
-use crate::state::Bank;
-use crate::constants::{BPS_DENOMINATOR, SECONDS_PER_YEAR};
+use crate::state::{Bank, InterestRateModel, RateModel};
+use decimal::{mul_div, Decimal, MathError, Rounding};
+use crate::constants::{BPS_DENOMINATOR, SECONDS_PER_YEAR, ADAPTIVE_CURVE_STEEPNESS, ADAPTIVE_SPEED_SCALE};
+use crate::error::ErrorCode;
 
-// Compute utilization = total_borrowed / total_deposits (in bps), guarding zeros
+// `a * b / denominator` rounded down, for rates and utilization in bps
 #[inline(always)]
-fn compute_utilization_bps(bank: &Bank) -> u64 {
+fn mul_div_floor(a: u64, b: u64, denominator: u64) -> Result<u64> {
+    let value = mul_div(a as u128, b as u128, denominator as u128, Rounding::Floor)?;
+    Ok(u64::try_from(value).map_err(|_| MathError::Overflow)?)
+}
+
+// Compute utilization = (variable + fixed borrows) / total_deposits (in bps), guarding zeros
+#[inline(always)]
+fn compute_utilization_bps(bank: &Bank) -> Result<u64> {
+    let borrowed = bank.total_borrowed.checked_add(bank.total_fixed_borrowed).ok_or(MathError::Overflow)?;
     if bank.total_deposits == 0 { 
-        return 0; 
+        return Ok(0); 
+    }
+    if borrowed >= bank.total_deposits { 
+        return Ok(BPS_DENOMINATOR); 
     }
-    if bank.total_borrowed >= bank.total_deposits { 
-        return BPS_DENOMINATOR; 
+    mul_div_floor(borrowed, BPS_DENOMINATOR, bank.total_deposits)
+}
+
+// Borrow APR from the bank's rate model, or its built-in kinked curve when none is set
+#[inline(always)]
+fn current_borrow_rate_bps(bank: &Bank, rate_model: Option<&RateModel>) -> Result<u64> {
+    let u_bps = compute_utilization_bps(bank)?;
+    match rate_model {
+        Some(model) => model_borrow_rate_bps(model, bank, u_bps),
+        None => kinked_borrow_rate_bps(bank, u_bps),
     }
-    ((bank.total_borrowed as u128)
-        .saturating_mul(BPS_DENOMINATOR as u128)
-        .checked_div(bank.total_deposits as u128)
-        .unwrap_or(0)) as u64
 }
 
 // Kinked utilization model
 #[inline(always)]
-fn current_borrow_rate_bps(bank: &Bank) -> u64 {
-    let u_bps = compute_utilization_bps(bank);
-    if u_bps <= bank.optimal_utilization_bps {
+fn kinked_borrow_rate_bps(bank: &Bank, u_bps: u64) -> Result<u64> {
+    let rate = if u_bps <= bank.optimal_utilization_bps {
         // base + slope1 * (u / optimal)
-        let slope_contrib = (bank.slope1_bps as u128)
-            .saturating_mul(u_bps as u128)
-            .checked_div(bank.optimal_utilization_bps.max(1) as u128)
-            .unwrap_or(0) as u64;
-        bank.base_rate_bps.saturating_add(slope_contrib)
-        
+        let slope_contrib = mul_div_floor(bank.slope1_bps, u_bps, bank.optimal_utilization_bps.max(1))?;
+        bank.base_rate_bps.checked_add(slope_contrib)
     } else {
         // base + slope1 + slope2 * ((u - optimal)/(1 - optimal))
-        let over_bps = u_bps.saturating_sub(bank.optimal_utilization_bps);
+        let over_bps = u_bps - bank.optimal_utilization_bps;
         let denom = BPS_DENOMINATOR.saturating_sub(bank.optimal_utilization_bps).max(1);
-        let slope2_contrib = (bank.slope2_bps as u128)
-            .saturating_mul(over_bps as u128)
-            .checked_div(denom as u128)
-            .unwrap_or(0) as u64;
+        let slope2_contrib = mul_div_floor(bank.slope2_bps, over_bps, denom)?;
         bank.base_rate_bps
-            .saturating_add(bank.slope1_bps)
-            .saturating_add(slope2_contrib)
+            .checked_add(bank.slope1_bps)
+            .and_then(|rate| rate.checked_add(slope2_contrib))
+    };
+    Ok(rate.ok_or(MathError::Overflow)?)
+}
+
+// rate of `slope_bps` scaled by how far u is into [from, to]
+#[inline(always)]
+fn segment_contrib(slope_bps: u64, u_bps: u64, from_bps: u64, to_bps: u64) -> Result<u64> {
+    if u_bps <= from_bps {
+        return Ok(0);
+    }
+    let span = to_bps.saturating_sub(from_bps).max(1);
+    mul_div_floor(slope_bps, u_bps.min(to_bps) - from_bps, span)
+}
+
+// Sum of rate components in bps
+#[inline(always)]
+fn sum_rates(rates: &[u64]) -> Result<u64> {
+    Ok(rates
+        .iter()
+        .try_fold(0u64, |sum, rate| sum.checked_add(*rate))
+        .ok_or(MathError::Overflow)?)
+}
+
+fn model_borrow_rate_bps(model: &RateModel, bank: &Bank, u_bps: u64) -> Result<u64> {
+    match *model {
+        RateModel::Fixed { rate_bps } => Ok(rate_bps),
+        RateModel::Linear { base_rate_bps, slope_bps } => {
+            sum_rates(&[base_rate_bps, segment_contrib(slope_bps, u_bps, 0, BPS_DENOMINATOR)?])
+        }
+        RateModel::TwoKink {
+            base_rate_bps,
+            kink1_utilization_bps,
+            slope1_bps,
+            kink2_utilization_bps,
+            slope2_bps,
+            slope3_bps,
+        } => sum_rates(&[
+            base_rate_bps,
+            segment_contrib(slope1_bps, u_bps, 0, kink1_utilization_bps)?,
+            segment_contrib(slope2_bps, u_bps, kink1_utilization_bps, kink2_utilization_bps)?,
+            segment_contrib(slope3_bps, u_bps, kink2_utilization_bps, BPS_DENOMINATOR)?,
+        ]),
+        RateModel::Piecewise { points, len } => {
+            let points = &points[..len as usize];
+            let upper = points
+                .iter()
+                .position(|p| p.utilization_bps >= u_bps)
+                .unwrap_or(points.len() - 1);
+            if upper == 0 {
+                return Ok(points[0].rate_bps);
+            }
+            let (lo, hi) = (points[upper - 1], points[upper]);
+            // Rates may fall between points, so interpolate the distance from the lower point's rate
+            let span = hi.utilization_bps - lo.utilization_bps;
+            let offset = u_bps.min(hi.utilization_bps) - lo.utilization_bps;
+            if hi.rate_bps >= lo.rate_bps {
+                sum_rates(&[lo.rate_bps, mul_div_floor(hi.rate_bps - lo.rate_bps, offset, span)?])
+            } else {
+                Ok(lo.rate_bps - mul_div_floor(lo.rate_bps - hi.rate_bps, offset, span)?)
+            }
+        }
+        RateModel::Adaptive { initial_rate_at_target_bps, .. } => {
+            let rate_at_target = if bank.rate_at_target_bps == 0 {
+                initial_rate_at_target_bps
+            } else {
+                bank.rate_at_target_bps
+            };
+            adaptive_curve_bps(rate_at_target, utilization_error_bps(bank, u_bps))
+        }
+    }
+}
+
+// Distance of utilization from optimal, normalized to [-BPS, BPS]
+#[inline(always)]
+fn utilization_error_bps(bank: &Bank, u_bps: u64) -> i128 {
+    let optimal = bank.optimal_utilization_bps.clamp(1, BPS_DENOMINATOR - 1) as i128;
+    let u = u_bps as i128;
+    let bps = BPS_DENOMINATOR as i128;
+    if u > optimal {
+        (u - optimal) * bps / (bps - optimal)
+    } else {
+        (u - optimal) * bps / optimal
+    }
+}
+
+// Below target the rate falls linearly to rate_at_target / steepness at 0% utilization,
+// above it rises linearly to rate_at_target * steepness at 100%
+#[inline(always)]
+fn adaptive_curve_bps(rate_at_target_bps: u64, err_bps: i128) -> Result<u64> {
+    let bps = BPS_DENOMINATOR as i128;
+    let steepness = ADAPTIVE_CURVE_STEEPNESS as i128;
+    let coeff = if err_bps < 0 { bps - bps / steepness } else { (steepness - 1) * bps };
+    // Within [bps / steepness, bps * steepness] as err_bps is within [-bps, bps]
+    let multiplier_bps = (bps + coeff * err_bps / bps) as u64;
+    mul_div_floor(rate_at_target_bps, multiplier_bps, BPS_DENOMINATOR)
+}
+
+// Moves the rate at target for `elapsed` seconds spent at the current utilization.
+// Linear approximation of the exponential adjustment, bounded to [min, max].
+fn adapt_rate_at_target(bank: &mut Bank, rate_model: &RateModel, elapsed: i64) -> Result<()> {
+    let RateModel::Adaptive {
+        initial_rate_at_target_bps,
+        min_rate_at_target_bps,
+        max_rate_at_target_bps,
+        adjustment_speed,
+    } = *rate_model else {
+        return Ok(());
+    };
+
+    let current = if bank.rate_at_target_bps == 0 { initial_rate_at_target_bps } else { bank.rate_at_target_bps };
+    let err_bps = utilization_error_bps(bank, compute_utilization_bps(bank)?);
+    // relative change = speed * err * elapsed, truncated towards no change
+    let change = mul_div(
+        (current as u128).checked_mul(adjustment_speed as u128).ok_or(MathError::Overflow)?,
+        err_bps.unsigned_abs() * elapsed as u128,
+        ADAPTIVE_SPEED_SCALE as u128 * BPS_DENOMINATOR as u128,
+        Rounding::Floor,
+    )?;
+    // A drop beyond the current rate lands on min, like any drop below it
+    let rate_at_target = if err_bps >= 0 {
+        (current as u128).checked_add(change).ok_or(MathError::Overflow)?.min(max_rate_at_target_bps as u128)
+    } else {
+        (current as u128).saturating_sub(change).max(min_rate_at_target_bps as u128)
+    };
+    bank.rate_at_target_bps = u64::try_from(rate_at_target).map_err(|_| MathError::Overflow)?;
+    Ok(())
+}
+
+// Checks a model is well formed before it is stored
+pub fn validate_rate_model(model: &RateModel) -> Result<()> {
+    match *model {
+        RateModel::Fixed { .. } | RateModel::Linear { .. } => {}
+        RateModel::Adaptive {
+            initial_rate_at_target_bps,
+            min_rate_at_target_bps,
+            max_rate_at_target_bps,
+            adjustment_speed,
+        } => {
+            require!(
+                min_rate_at_target_bps > 0
+                    && min_rate_at_target_bps <= initial_rate_at_target_bps
+                    && initial_rate_at_target_bps <= max_rate_at_target_bps
+                    && adjustment_speed > 0,
+                ErrorCode::InvalidRateModel
+            );
+        }
+        RateModel::TwoKink { kink1_utilization_bps, kink2_utilization_bps, .. } => {
+            require!(
+                kink1_utilization_bps > 0
+                    && kink1_utilization_bps < kink2_utilization_bps
+                    && kink2_utilization_bps < BPS_DENOMINATOR,
+                ErrorCode::InvalidRateModel
+            );
+        }
+        RateModel::Piecewise { points, len } => {
+            require!(len >= 2 && len as usize <= points.len(), ErrorCode::InvalidRateModel);
+            let points = &points[..len as usize];
+            require!(
+                points[0].utilization_bps == 0 && points[points.len() - 1].utilization_bps == BPS_DENOMINATOR,
+                ErrorCode::InvalidRateModel
+            );
+            require!(
+                points.windows(2).all(|w| w[0].utilization_bps < w[1].utilization_bps),
+                ErrorCode::InvalidRateModel
+            );
+        }
+    }
+    Ok(())
+}
+
+// Rate model the bank accrues with. Banks referencing a model account must be passed it.
+pub fn resolve_rate_model(
+    bank: &Bank,
+    rate_model: Option<&Account<InterestRateModel>>,
+) -> Result<Option<RateModel>> {
+    if bank.interest_rate_model == Pubkey::default() {
+        return Ok(None);
     }
+    let rate_model = rate_model.ok_or(ErrorCode::RateModelMismatch)?;
+    require_keys_eq!(rate_model.key(), bank.interest_rate_model, ErrorCode::RateModelMismatch);
+    Ok(Some(rate_model.model))
 }
 
-// Accrue interest on total_borrowed based on elapsed time and current borrow APR.
-pub fn accrue_interest(bank: &mut Bank) -> Result<()> {
+// Growth factor minus one for an APR compounded every second over `elapsed`.
+// e^x - 1 with x = apr * elapsed / year, using the first three Taylor terms.
+#[inline(always)]
+fn compounded_growth(apr_bps: u64, elapsed: i64) -> Result<Decimal> {
+    let x = Decimal::from_bps(apr_bps)
+        .try_mul_int(elapsed as u64)?
+        .try_div_int(SECONDS_PER_YEAR as u64, Rounding::Floor)?;
+    let x2 = x.try_mul(x, Rounding::Floor)?;
+    let x3 = x2.try_mul(x, Rounding::Floor)?;
+    Ok(x.try_add(x2.try_div_int(2, Rounding::Floor)?)?
+        .try_add(x3.try_div_int(6, Rounding::Floor)?)?)
+}
+
+// Accrue compounded interest on total_borrowed based on elapsed time and current borrow APR.
+pub fn accrue_interest(bank: &mut Bank, rate_model: Option<&Account<InterestRateModel>>) -> Result<()> {
+    let rate_model = resolve_rate_model(bank, rate_model)?;
     let now = Clock::get()?.unix_timestamp;
     if bank.last_accrual_ts == 0 { 
         bank.last_accrual_ts = now; 
@@ -58,35 +261,60 @@ pub fn accrue_interest(bank: &mut Bank) -> Result<()> {
     if elapsed <= 0 { 
         return Ok(()); 
     }
+
+    // Interest for the period uses the curve as it stood at its start
+    let apr_bps = current_borrow_rate_bps(bank, rate_model.as_ref())?;
+    if let Some(model) = rate_model.as_ref() {
+        adapt_rate_at_target(bank, model, elapsed)?;
+    }
     if bank.total_borrowed == 0 { 
         bank.last_accrual_ts = now; 
         return Ok(()); 
     }
 
-    let apr_bps = current_borrow_rate_bps(bank);
-
-    // interest = total_borrowed * apr_bps/10_000 * elapsed/seconds_per_year
-    let interest = ((bank.total_borrowed as u128)
-        .saturating_mul(apr_bps as u128)
-        .checked_div(BPS_DENOMINATOR as u128)
-        .unwrap_or(0))
-        .saturating_mul(elapsed as u128)
-        .checked_div(SECONDS_PER_YEAR as u128)
-        .unwrap_or(0) as u64;
+    // interest = total_borrowed * (e^(apr * elapsed/seconds_per_year) - 1), rounded down so
+    // frequent accruals cannot charge dust
+    let interest = compounded_growth(apr_bps, elapsed)?
+        .try_mul_int(bank.total_borrowed)?
+        .try_to_u64(Rounding::Floor)?;
 
     if interest > 0 {
-        bank.total_borrowed = bank.total_borrowed.saturating_add(interest);
+        bank.total_borrowed = bank.total_borrowed.checked_add(interest).ok_or(MathError::Overflow)?;
         // Keep shares constant; value per share increases implicitly.
+        distribute_interest(bank, interest)?;
     }
 
     bank.last_accrual_ts = now;
     Ok(())
 }
 
-// Expose helpers for testing/inspection
-#[inline(always)]
-pub fn get_utilization_bps(bank: &Bank) -> u64 { compute_utilization_bps(bank) }
+// All interest paid by borrowers goes to depositors
+pub fn distribute_interest(bank: &mut Bank, interest: u64) -> Result<()> {
+    bank.total_deposits = bank.total_deposits.checked_add(interest).ok_or(MathError::Overflow)?;
+    Ok(())
+}
+
+// Simple interest owed on a fixed-rate loan after `elapsed` seconds, rounded up against the borrower
 #[inline(always)]
-pub fn get_borrow_rate_bps(bank: &Bank) -> u64 { current_borrow_rate_bps(bank) }
+pub fn fixed_loan_interest(principal: u64, rate_bps: u64, elapsed: i64) -> Result<u64> {
+    Ok(Decimal::from_bps(rate_bps)
+        .try_mul_int(elapsed.max(0) as u64)?
+        .try_mul_int(principal)?
+        .try_div_int(SECONDS_PER_YEAR as u64, Rounding::Ceil)?
+        .try_to_u64(Rounding::Ceil)?)
+}
 
+// Simple (not compounded) supply APR earned by depositors: borrow rate * utilization
+#[inline(always)]
+fn current_supply_rate_bps(bank: &Bank, rate_model: Option<&RateModel>) -> Result<u64> {
+    let u_bps = compute_utilization_bps(bank)?;
+    mul_div_floor(current_borrow_rate_bps(bank, rate_model)?, u_bps, BPS_DENOMINATOR)
+}
 
+// Expose helpers for testing/inspection
+#[inline(always)]
+pub fn get_utilization_bps(bank: &Bank) -> Result<u64> { compute_utilization_bps(bank) }
+#[inline(always)]
+pub fn get_borrow_rate_bps(bank: &Bank, rate_model: Option<&RateModel>) -> Result<u64> { current_borrow_rate_bps(bank, rate_model) }
+#[inline(always)]
+pub fn get_supply_rate_bps(bank: &Bank, rate_model: Option<&RateModel>) -> Result<u64> { current_supply_rate_bps(bank, rate_model) }
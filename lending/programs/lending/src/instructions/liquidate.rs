@@ -1,11 +1,13 @@
 use anchor_lang::prelude::*;
 use anchor_spl::associated_token::AssociatedToken;
 use anchor_spl::token_interface::{ self, Mint, TokenAccount, TokenInterface, TransferChecked };
-use pyth_solana_receiver_sdk::price_update::{get_feed_id_from_hex, PriceUpdateV2};
-use crate::constants::{MAXIMUM_AGE, SOL_USD_FEED_ID, USDC_USD_FEED_ID};
+use pyth_solana_receiver_sdk::price_update::PriceUpdateV2;
+use crate::constants::BPS_DENOMINATOR;
+use decimal::Rounding;
 use crate::state::*;
 use crate::error::ErrorCode;
 use super::interest::accrue_interest;
+use super::utils::{get_sol_and_usdc_prices, calculate_position_values, calculate_bank_liquidation_health_factor, usd_price};
 
 #[derive(Accounts)]
 pub struct Liquidate<'info> {
@@ -20,6 +22,7 @@ pub struct Liquidate<'info> {
         bump,
     )]  
     pub collateral_bank: Account<'info, Bank>,
+    pub collateral_rate_model: Option<Account<'info, InterestRateModel>>,
     #[account(
         mut, 
         seeds = [b"treasury", collateral_mint.key().as_ref()],
@@ -66,43 +69,35 @@ pub struct Liquidate<'info> {
 }
 
 pub fn process_liquidate(ctx: Context<Liquidate>) -> Result<()> { 
-    accrue_interest(&mut ctx.accounts.collateral_bank)?;
+    accrue_interest(&mut ctx.accounts.collateral_bank, ctx.accounts.collateral_rate_model.as_ref())?;
     let collateral_bank = &ctx.accounts.collateral_bank;
     let user = &mut ctx.accounts.user_account;
 
-    let price_update = &ctx.accounts.price_update;
+    let (sol_price, usdc_price) = get_sol_and_usdc_prices(
+        &ctx.accounts.price_update,
+        ctx.remaining_accounts,
+        user,
+        &[collateral_bank, &ctx.accounts.borrowed_bank],
+    )?;
+    let health_factor = calculate_bank_liquidation_health_factor(user, collateral_bank, sol_price, usdc_price)?;
 
-    let sol_feed_id = get_feed_id_from_hex(SOL_USD_FEED_ID)
-        .map_err(|_| error!(ErrorCode::OracleError))?; 
-    let usdc_feed_id = get_feed_id_from_hex(USDC_USD_FEED_ID)
-        .map_err(|_| error!(ErrorCode::OracleError))?;
-
-    let sol_price = price_update
-        .get_price_no_older_than(&Clock::get()?, MAXIMUM_AGE, &sol_feed_id)
-        .map_err(|_| error!(ErrorCode::OracleError))?;
-    let usdc_price = price_update
-        .get_price_no_older_than(&Clock::get()?, MAXIMUM_AGE, &usdc_feed_id)
-        .map_err(|_| error!(ErrorCode::OracleError))?;
-
-
-    let total_collateral = (sol_price.price as u64)
-        .saturating_mul(user.deposited_sol)
-        .saturating_add((usdc_price.price as u64).saturating_mul(user.deposited_usdc));
-    let total_borrowed = (sol_price.price as u64)
-        .saturating_mul(user.borrowed_sol)
-        .saturating_add((usdc_price.price as u64).saturating_mul(user.borrowed_usdc));    
-
-    let health_factor = total_collateral
-        .saturating_mul(collateral_bank.liquidation_threshold)
-        .checked_div(total_borrowed)
-        .unwrap_or(0);
-
-    if health_factor >= 1 {
+    if health_factor >= BPS_DENOMINATOR {
         return Err(ErrorCode::NotUndercollateralized.into());
     }
 
-    let liquidation_amount = total_borrowed
-        .saturating_mul(collateral_bank.liquidation_close_factor);
+    // Close factor and bonus are in bps. The repaid value is converted to whole tokens of each mint
+    // at spot prices, and both amounts round down, against the liquidator.
+    let (_, total_borrowed) =
+        calculate_position_values(user, sol_price, usdc_price, collateral_bank.liquidation_valuation_mode)?;
+    let spot_price = |mint: Pubkey| usd_price(if mint == user.usdc_address { usdc_price.price } else { sol_price.price });
+    let repay_value = total_borrowed.try_mul_bps(collateral_bank.liquidation_close_factor, Rounding::Floor)?;
+    let liquidation_amount = repay_value
+        .try_div(spot_price(ctx.accounts.borrowed_mint.key())?, Rounding::Floor)?
+        .try_to_token_amount(ctx.accounts.borrowed_mint.decimals, Rounding::Floor)?;
+    let liquidation_bonus = repay_value
+        .try_mul_bps(BPS_DENOMINATOR.saturating_add(collateral_bank.liquidation_bonus), Rounding::Floor)?
+        .try_div(spot_price(ctx.accounts.collateral_mint.key())?, Rounding::Floor)?
+        .try_to_token_amount(ctx.accounts.collateral_mint.decimals, Rounding::Floor)?;
 
 
     let transfer_to_bank = TransferChecked {
@@ -117,10 +112,6 @@ pub fn process_liquidate(ctx: Context<Liquidate>) -> Result<()> {
 
     token_interface::transfer_checked(cpi_ctx_to_bank, liquidation_amount, decimals)?;
 
-    let liquidation_bonus = liquidation_amount
-        .saturating_mul(collateral_bank.liquidation_bonus)
-        .saturating_add(liquidation_amount);
-    
     let transfer_to_liquidator = TransferChecked {
         from: ctx.accounts.collateral_bank_token_account.to_account_info(),
         mint: ctx.accounts.collateral_mint.to_account_info(),
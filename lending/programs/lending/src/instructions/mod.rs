@@ -12,4 +12,27 @@ pub use liquidate::*;
 pub mod liquidate;
 pub use health_monitor::*;
 pub mod health_monitor;
-pub mod interest;
\ No newline at end of file
+pub use auto_deleverage::*;
+pub mod auto_deleverage;
+pub use position::*;
+pub mod position;
+pub use bank_state::*;
+pub mod bank_state;
+pub use rate_model::*;
+pub mod rate_model;
+pub use fixed_loan::*;
+pub mod fixed_loan;
+pub use referral::*;
+pub mod referral;
+pub use rewards::*;
+pub mod rewards;
+pub use receipt::*;
+pub mod receipt;
+pub use native::*;
+pub mod native;
+pub use reconcile::*;
+pub mod reconcile;
+pub use oracle::*;
+pub mod oracle;
+pub mod interest;
+pub mod utils;
\ No newline at end of file
@@ -0,0 +1,267 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{ self, Transfer };
+use anchor_spl::token::spl_token::native_mint;
+use anchor_spl::token_interface::{ self, Mint, TokenAccount, TokenInterface, TransferChecked, SyncNative, CloseAccount };
+use pyth_solana_receiver_sdk::price_update::PriceUpdateV2;
+use crate::state::*;
+use crate::error::ErrorCode;
+use super::deposit::{record_deposit, DepositPosition};
+use super::withdraw::{record_withdraw, WithdrawPosition};
+use super::borrow::{record_borrow, BorrowPosition};
+
+// Native SOL variants of deposit/withdraw/borrow for the wSOL bank. Lamports are wrapped straight
+// into the treasury on the way in, and paid out through a temporary wSOL account that is closed
+// back to the signer on the way out, so the signer never needs a wSOL token account of their own.
+
+#[derive(Accounts)]
+pub struct DepositNative<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    #[account(address = native_mint::ID @ ErrorCode::NotNativeMint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        seeds = [mint.key().as_ref()],
+        bump,
+    )]
+    pub bank: Account<'info, Bank>,
+    // Required when the bank references an interest rate model
+    pub interest_rate_model: Option<Account<'info, InterestRateModel>>,
+    // Required while the bank's deposit reward pool is emitting, user_rewards once the user opted in
+    #[account(mut)]
+    pub reward_pool: Option<Box<Account<'info, RewardPool>>>,
+    #[account(mut)]
+    pub user_rewards: Option<Box<Account<'info, UserRewards>>>,
+    #[account(
+        mut,
+        seeds = [b"treasury", mint.key().as_ref()],
+        bump,
+    )]
+    pub bank_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [signer.key().as_ref()],
+        bump,
+    )]
+    pub user_account: Account<'info, User>,
+    pub price_update: Account<'info, PriceUpdateV2>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawNative<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    #[account(address = native_mint::ID @ ErrorCode::NotNativeMint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        seeds = [mint.key().as_ref()],
+        bump,
+    )]
+    pub bank: Account<'info, Bank>,
+    // Required when the bank references an interest rate model
+    pub interest_rate_model: Option<Account<'info, InterestRateModel>>,
+    // Required while the bank's deposit reward pool is emitting, user_rewards once the user opted in
+    #[account(mut)]
+    pub reward_pool: Option<Box<Account<'info, RewardPool>>>,
+    #[account(mut)]
+    pub user_rewards: Option<Box<Account<'info, UserRewards>>>,
+    #[account(
+        mut,
+        seeds = [b"treasury", mint.key().as_ref()],
+        bump,
+    )]
+    pub bank_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [signer.key().as_ref()],
+        bump,
+    )]
+    pub user_account: Account<'info, User>,
+    #[account(
+        init,
+        payer = signer,
+        seeds = [b"unwrap", signer.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = unwrap_token_account,
+        token::token_program = token_program,
+    )]
+    pub unwrap_token_account: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct BorrowNative<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    #[account(address = native_mint::ID @ ErrorCode::NotNativeMint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        seeds = [mint.key().as_ref()],
+        bump,
+    )]
+    pub bank: Account<'info, Bank>,
+    // Required when the bank references an interest rate model
+    pub interest_rate_model: Option<Account<'info, InterestRateModel>>,
+    // Required while the bank's borrow reward pool is emitting, user_rewards once the user opted in
+    #[account(mut)]
+    pub reward_pool: Option<Box<Account<'info, RewardPool>>>,
+    #[account(mut)]
+    pub user_rewards: Option<Box<Account<'info, UserRewards>>>,
+    #[account(
+        mut,
+        seeds = [b"treasury", mint.key().as_ref()],
+        bump,
+    )]
+    pub bank_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [signer.key().as_ref()],
+        bump,
+    )]
+    pub user_account: Account<'info, User>,
+    #[account(
+        init,
+        payer = signer,
+        seeds = [b"unwrap", signer.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = unwrap_token_account,
+        token::token_program = token_program,
+    )]
+    pub unwrap_token_account: InterfaceAccount<'info, TokenAccount>,
+    // Optional referrer credited with a share of the origination fee
+    #[account(
+        mut,
+        constraint = referrer_fees.mint_address == mint.key()
+            && referrer_fees.referrer != signer.key() @ ErrorCode::InvalidReferrer,
+    )]
+    pub referrer_fees: Option<Account<'info, ReferrerFees>>,
+    pub price_update: Account<'info, PriceUpdateV2>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn process_deposit_native(ctx: Context<DepositNative>, amount: u64) -> Result<()> {
+    let accounts = ctx.accounts;
+
+    let transfer_accounts = Transfer {
+        from: accounts.signer.to_account_info(),
+        to: accounts.bank_token_account.to_account_info(),
+    };
+    system_program::transfer(CpiContext::new(accounts.system_program.to_account_info(), transfer_accounts), amount)?;
+
+    let sync_accounts = SyncNative {
+        account: accounts.bank_token_account.to_account_info(),
+    };
+    token_interface::sync_native(CpiContext::new(accounts.token_program.to_account_info(), sync_accounts))?;
+
+    record_deposit(&mut DepositPosition {
+        mint: accounts.mint.key(),
+        bank: &mut accounts.bank,
+        interest_rate_model: accounts.interest_rate_model.as_ref(),
+        reward_pool: accounts.reward_pool.as_deref_mut(),
+        user_rewards: accounts.user_rewards.as_deref_mut(),
+        user_account: &mut accounts.user_account,
+        price_update: &accounts.price_update,
+    }, ctx.remaining_accounts, amount)
+}
+
+pub fn process_withdraw_native(ctx: Context<WithdrawNative>, amount: u64) -> Result<()> {
+    let accounts = ctx.accounts;
+    record_withdraw(&mut WithdrawPosition {
+        mint: accounts.mint.key(),
+        bank: &mut accounts.bank,
+        interest_rate_model: accounts.interest_rate_model.as_ref(),
+        reward_pool: accounts.reward_pool.as_deref_mut(),
+        user_rewards: accounts.user_rewards.as_deref_mut(),
+        user_account: &mut accounts.user_account,
+    }, amount)?;
+
+    unwrap_to_signer(
+        &accounts.bank_token_account,
+        &accounts.unwrap_token_account,
+        accounts.signer.to_account_info(),
+        &accounts.mint,
+        accounts.token_program.to_account_info(),
+        amount,
+        ctx.bumps.bank_token_account,
+        ctx.bumps.unwrap_token_account,
+    )
+}
+
+pub fn process_borrow_native(ctx: Context<BorrowNative>, amount: u64) -> Result<()> {
+    let accounts = ctx.accounts;
+    record_borrow(&mut BorrowPosition {
+        mint: &accounts.mint,
+        bank: &mut accounts.bank,
+        interest_rate_model: accounts.interest_rate_model.as_ref(),
+        reward_pool: accounts.reward_pool.as_deref_mut(),
+        user_rewards: accounts.user_rewards.as_deref_mut(),
+        user_account: &mut accounts.user_account,
+        referrer_fees: accounts.referrer_fees.as_mut(),
+        price_update: &accounts.price_update,
+    }, ctx.remaining_accounts, amount)?;
+
+    unwrap_to_signer(
+        &accounts.bank_token_account,
+        &accounts.unwrap_token_account,
+        accounts.signer.to_account_info(),
+        &accounts.mint,
+        accounts.token_program.to_account_info(),
+        amount,
+        ctx.bumps.bank_token_account,
+        ctx.bumps.unwrap_token_account,
+    )
+}
+
+// Move `amount` wSOL from the treasury into the temporary account, then close it so the
+// signer receives the lamports along with the account's rent
+#[allow(clippy::too_many_arguments)]
+fn unwrap_to_signer<'info>(
+    bank_token_account: &InterfaceAccount<'info, TokenAccount>,
+    unwrap_token_account: &InterfaceAccount<'info, TokenAccount>,
+    signer: AccountInfo<'info>,
+    mint: &InterfaceAccount<'info, Mint>,
+    token_program: AccountInfo<'info>,
+    amount: u64,
+    treasury_bump: u8,
+    unwrap_bump: u8,
+) -> Result<()> {
+    let mint_key = mint.key();
+    let treasury_seeds: &[&[&[u8]]] = &[
+        &[
+            b"treasury",
+            mint_key.as_ref(),
+            &[treasury_bump],
+        ],
+    ];
+    let transfer_cpi_accounts = TransferChecked {
+        from: bank_token_account.to_account_info(),
+        mint: mint.to_account_info(),
+        to: unwrap_token_account.to_account_info(),
+        authority: bank_token_account.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new(token_program.clone(), transfer_cpi_accounts).with_signer(treasury_seeds);
+    token_interface::transfer_checked(cpi_ctx, amount, mint.decimals)?;
+
+    let signer_key = signer.key();
+    let unwrap_seeds: &[&[&[u8]]] = &[
+        &[
+            b"unwrap",
+            signer_key.as_ref(),
+            &[unwrap_bump],
+        ],
+    ];
+    let close_accounts = CloseAccount {
+        account: unwrap_token_account.to_account_info(),
+        destination: signer,
+        authority: unwrap_token_account.to_account_info(),
+    };
+    token_interface::close_account(CpiContext::new(token_program, close_accounts).with_signer(unwrap_seeds))
+}
@@ -0,0 +1,193 @@
+use anchor_lang::prelude::*;
+use pyth_solana_receiver_sdk::price_update::{FeedId, PriceUpdateV2};
+use price_oracle::{OracleError, OraclePolicy, OraclePrice, cross_check, parse_feed_id, pyth_price, secondary_price, select_price};
+use crate::constants::MAX_MANUAL_PRICE_VALIDITY;
+use crate::state::*;
+use crate::error::ErrorCode;
+
+#[derive(Accounts)]
+pub struct InitOracleConfig<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::LendingProtocol>,
+    #[account(constraint = program_data.upgrade_authority_address == Some(signer.key()) @ ErrorCode::Unauthorized)]
+    pub program_data: Account<'info, ProgramData>,
+    #[account(
+        init,
+        payer = signer,
+        space = 8 + OracleConfig::INIT_SPACE,
+        seeds = [b"oracle_config"],
+        bump,
+    )]
+    pub oracle_config: Account<'info, OracleConfig>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateOracleConfig<'info> {
+    pub signer: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"oracle_config"],
+        bump = oracle_config.bump,
+        constraint = oracle_config.authority == signer.key() @ ErrorCode::Unauthorized,
+    )]
+    pub oracle_config: Account<'info, OracleConfig>,
+}
+
+#[derive(Accounts)]
+#[instruction(feed_id: [u8; 32])]
+pub struct SetManualPrice<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    #[account(
+        seeds = [b"oracle_config"],
+        bump = oracle_config.bump,
+        constraint = oracle_config.guardian == signer.key() @ ErrorCode::Unauthorized,
+    )]
+    pub oracle_config: Account<'info, OracleConfig>,
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 8 + ManualPrice::INIT_SPACE,
+        seeds = [b"manual_price", feed_id.as_ref()],
+        bump,
+    )]
+    pub manual_price: Account<'info, ManualPrice>,
+    pub system_program: Program<'info, System>,
+}
+
+// The program's upgrade authority becomes the authority and initial guardian of the fallback price
+// sources
+pub fn process_init_oracle_config(ctx: Context<InitOracleConfig>) -> Result<()> {
+    let oracle_config = &mut ctx.accounts.oracle_config;
+    oracle_config.authority = ctx.accounts.signer.key();
+    oracle_config.guardian = ctx.accounts.signer.key();
+    oracle_config.bump = ctx.bumps.oracle_config;
+    Ok(())
+}
+
+pub fn process_update_oracle_config(
+    ctx: Context<UpdateOracleConfig>,
+    guardian: Pubkey,
+    secondary_oracle_program: Pubkey,
+) -> Result<()> {
+    let oracle_config = &mut ctx.accounts.oracle_config;
+    oracle_config.guardian = guardian;
+    oracle_config.secondary_oracle_program = secondary_oracle_program;
+    msg!("Oracle guardian: {}, secondary oracle program: {}", guardian, secondary_oracle_program);
+    Ok(())
+}
+
+// Last-resort price for a feed, only used while no other source is fresh or the others agree with
+// it. Expires after `valid_for` seconds so a stale manual price cannot outlive an outage.
+pub fn process_set_manual_price(
+    ctx: Context<SetManualPrice>,
+    feed_id: [u8; 32],
+    price: i64,
+    conf: u64,
+    exponent: i32,
+    valid_for: i64,
+) -> Result<()> {
+    require!(price > 0, ErrorCode::InvalidManualPrice);
+    require!(valid_for > 0 && valid_for <= MAX_MANUAL_PRICE_VALIDITY, ErrorCode::InvalidManualPrice);
+
+    let now = Clock::get()?.unix_timestamp;
+    let manual_price = &mut ctx.accounts.manual_price;
+    manual_price.feed_id = feed_id;
+    manual_price.price = price;
+    manual_price.conf = conf;
+    manual_price.exponent = exponent;
+    manual_price.publish_time = now;
+    manual_price.expires_at = now.saturating_add(valid_for);
+    manual_price.bump = ctx.bumps.manual_price;
+
+    msg!("Manual price {} (expo {}) set until {}", price, exponent, manual_price.expires_at);
+    Ok(())
+}
+
+// Price of a feed from Pyth while it is fresh, failing if a fresh secondary oracle or manual price
+// disagrees with it by more than ORACLE_SOURCE_TOLERANCE_BPS. Once Pyth is stale the first of the
+// secondary oracle and the manual price that agrees with the other is used instead.
+// Fallback sources are looked up in `fallback_sources`, normally the instruction's remaining accounts;
+// secondary prices are only read when the OracleConfig naming their program is passed as well.
+// The Pyth update for the feed must be passed, either as `price_update` or among the fallback sources.
+pub fn get_asset_price(
+    price_update: &PriceUpdateV2,
+    fallback_sources: &[AccountInfo],
+    feed_id: &str,
+    policy: &OraclePolicy,
+) -> Result<OraclePrice> {
+    let feed_id = parse_feed_id(feed_id).map_err(ErrorCode::from)?;
+    let clock = Clock::get()?;
+
+    let fallback_update;
+    let pyth_update = if price_update.price_message.feed_id == feed_id {
+        price_update
+    } else {
+        fallback_update = find_pyth_update(fallback_sources, &feed_id).ok_or(ErrorCode::InvalidPriceUpdate)?;
+        &fallback_update
+    };
+
+    let pyth = match pyth_price(pyth_update, &feed_id, policy, &clock) {
+        Ok(price) => Some(price),
+        Err(OracleError::StalePrice) => None,
+        Err(err) => return Err(ErrorCode::from(err).into()),
+    };
+
+    let mut fallbacks: Vec<OraclePrice> = Vec::with_capacity(2);
+    if let Some(oracle_config) = find_program_account::<OracleConfig>(fallback_sources) {
+        if let Some(price) = fallback_sources.iter().find_map(|source| {
+            secondary_price(source, &oracle_config.secondary_oracle_program, &feed_id, policy, &clock)
+        }) {
+            fallbacks.push(price);
+        }
+    }
+    if let Some(price) = find_manual_price(fallback_sources, &feed_id, &clock) {
+        fallbacks.push(price);
+    }
+
+    let price = match pyth {
+        Some(price) => cross_check(price, &fallbacks),
+        None if fallbacks.is_empty() => Err(OracleError::StalePrice),
+        None => select_price(&fallbacks),
+    };
+    price.map_err(|err| ErrorCode::from(err).into())
+}
+
+// Pyth update for the feed among the fallback sources, for instructions whose `price_update` account
+// carries another feed
+fn find_pyth_update(fallback_sources: &[AccountInfo], feed_id: &FeedId) -> Option<PriceUpdateV2> {
+    fallback_sources
+        .iter()
+        .filter(|source| *source.owner == pyth_solana_receiver_sdk::ID)
+        .filter_map(|source| {
+            let data = source.try_borrow_data().ok()?;
+            PriceUpdateV2::try_deserialize(&mut &data[..]).ok()
+        })
+        .find(|update| update.price_message.feed_id == *feed_id)
+}
+
+// Unexpired guardian price for the feed, if its ManualPrice account was passed
+fn find_manual_price(fallback_sources: &[AccountInfo], feed_id: &FeedId, clock: &Clock) -> Option<OraclePrice> {
+    fallback_sources
+        .iter()
+        .filter_map(|source| read_program_account::<ManualPrice>(source))
+        .find(|manual| manual.feed_id == *feed_id && clock.unix_timestamp <= manual.expires_at)
+        .and_then(|manual| OraclePrice::from_parts(manual.price, manual.conf, manual.exponent, manual.publish_time).ok())
+}
+
+// Accounts of this program can only be created through its own instructions, so an owner and
+// discriminator check is enough to trust them
+fn read_program_account<T: AccountDeserialize>(source: &AccountInfo) -> Option<T> {
+    if *source.owner != crate::ID {
+        return None;
+    }
+    let data = source.try_borrow_data().ok()?;
+    T::try_deserialize(&mut &data[..]).ok()
+}
+
+fn find_program_account<T: AccountDeserialize>(fallback_sources: &[AccountInfo]) -> Option<T> {
+    fallback_sources.iter().find_map(|source| read_program_account::<T>(source))
+}
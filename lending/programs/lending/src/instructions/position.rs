@@ -0,0 +1,133 @@
+use anchor_lang::prelude::*;
+use pyth_solana_receiver_sdk::price_update::PriceUpdateV2;
+use decimal::Rounding;
+use crate::constants::{SOL_DECIMALS, USDC_DECIMALS};
+use crate::state::*;
+use crate::error::ErrorCode;
+use super::utils::{
+    get_sol_and_usdc_prices, calculate_position_values, calculate_health_factor,
+    calculate_bank_liquidation_health_factor, calculate_borrow_capacity, calculate_liquidation_sol_price,
+    amount_from_shares, value_to_u64, ValuationPrice,
+};
+use super::interest::accrue_interest;
+
+#[derive(Accounts)]
+pub struct GetUserPosition<'info> {
+    #[account(
+        seeds = [user_account.owner.as_ref()],
+        bump,
+    )]
+    pub user_account: Account<'info, User>,
+    #[account(
+        constraint = sol_bank.mint_address != user_account.usdc_address
+            && (user_account.sol_address == Pubkey::default()
+                || sol_bank.mint_address == user_account.sol_address) @ ErrorCode::InvalidAssetPair,
+    )]
+    pub sol_bank: Account<'info, Bank>,
+    #[account(
+        constraint = usdc_bank.mint_address == user_account.usdc_address @ ErrorCode::InvalidAssetPair,
+    )]
+    pub usdc_bank: Account<'info, Bank>,
+    // Required when the matching bank references an interest rate model
+    pub sol_rate_model: Option<Account<'info, InterestRateModel>>,
+    pub usdc_rate_model: Option<Account<'info, InterestRateModel>>,
+    pub price_update: Account<'info, PriceUpdateV2>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct UserPosition {
+    pub owner: Pubkey,
+    // Principal balances in token base units, as borrow and liquidate value them
+    pub deposited_sol: u64,
+    pub deposited_usdc: u64,
+    pub borrowed_sol: u64,
+    pub borrowed_usdc: u64,
+    // The same balances redeemed from shares at the banks' exchange rates with interest accrued to now
+    pub deposited_sol_with_interest: u64,
+    pub deposited_usdc_with_interest: u64,
+    pub borrowed_sol_with_interest: u64,
+    pub borrowed_usdc_with_interest: u64,
+    pub fixed_borrowed_sol: u64,        // fixed-rate loan principal
+    pub fixed_borrowed_usdc: u64,
+    pub sol_price: u64,
+    pub usdc_price: u64,
+    pub total_collateral_value: u64,    // USD at PRICE_EXPONENT, rounded down
+    pub total_borrowed_value: u64,
+    pub health_factor: u64,             // bps, same measure the health alerts use
+    pub liquidation_health_factor: u64, // bps, the lower of the checks liquidate makes against either bank
+    pub sol_borrow_capacity: u64,       // additional SOL debt in base units, origination fee included, at max LTV
+    pub usdc_borrow_capacity: u64,      // additional USDC debt in base units, origination fee included, at max LTV
+    pub liquidation_sol_price: u64,     // 0 if no SOL price makes the position liquidatable
+    pub timestamp: i64,
+}
+
+// Read-only: values the position with the same helpers and balances as borrow and liquidate and
+// returns it as return data
+pub fn process_get_user_position(ctx: Context<GetUserPosition>) -> Result<UserPosition> {
+    let position = &ctx.accounts.user_account;
+    let sol_bank = &ctx.accounts.sol_bank;
+    let usdc_bank = &ctx.accounts.usdc_bank;
+
+    // Accrue copies of the banks so share balances include interest up to now without writing state
+    let mut accrued_sol_bank: Bank = (**sol_bank).clone();
+    accrue_interest(&mut accrued_sol_bank, ctx.accounts.sol_rate_model.as_ref())?;
+    let mut accrued_usdc_bank: Bank = (**usdc_bank).clone();
+    accrue_interest(&mut accrued_usdc_bank, ctx.accounts.usdc_rate_model.as_ref())?;
+
+    let (sol_price, usdc_price) = get_sol_and_usdc_prices(&ctx.accounts.price_update, ctx.remaining_accounts, position, &[sol_bank, usdc_bank])?;
+    let (total_collateral_value, total_borrowed_value) =
+        calculate_position_values(position, sol_price, usdc_price, ValuationMode::Spot)?;
+
+    // A liquidator picks the collateral bank, so the binding check is the lower of the two
+    let sol_liquidation_health_factor = calculate_bank_liquidation_health_factor(position, sol_bank, sol_price, usdc_price)?;
+    let usdc_liquidation_health_factor = calculate_bank_liquidation_health_factor(position, usdc_bank, sol_price, usdc_price)?;
+    let binding_bank = if sol_liquidation_health_factor <= usdc_liquidation_health_factor { sol_bank } else { usdc_bank };
+
+    // Each bank's borrow capacity is valued under that bank's own borrow mode
+    let sol_mode = sol_bank.borrow_valuation_mode;
+    let (sol_collateral_value, sol_borrowed_value) = calculate_position_values(position, sol_price, usdc_price, sol_mode)?;
+    let sol_borrow_capacity = calculate_borrow_capacity(sol_collateral_value, sol_borrowed_value, sol_bank.max_ltv)?
+        .try_div(sol_price.debt_price(sol_mode)?, Rounding::Floor)?
+        .try_to_token_amount(SOL_DECIMALS, Rounding::Floor)
+        .unwrap_or(u64::MAX);
+    let usdc_mode = usdc_bank.borrow_valuation_mode;
+    let (usdc_collateral_value, usdc_borrowed_value) = calculate_position_values(position, sol_price, usdc_price, usdc_mode)?;
+    let usdc_borrow_capacity = calculate_borrow_capacity(usdc_collateral_value, usdc_borrowed_value, usdc_bank.max_ltv)?
+        .try_div(usdc_price.debt_price(usdc_mode)?, Rounding::Floor)?
+        .try_to_token_amount(USDC_DECIMALS, Rounding::Floor)
+        .unwrap_or(u64::MAX);
+
+    Ok(UserPosition {
+        owner: position.owner,
+        deposited_sol: position.deposited_sol,
+        deposited_usdc: position.deposited_usdc,
+        borrowed_sol: position.borrowed_sol,
+        borrowed_usdc: position.borrowed_usdc,
+        deposited_sol_with_interest: deposit_with_interest(position.deposited_sol_shares, &accrued_sol_bank)?,
+        deposited_usdc_with_interest: deposit_with_interest(position.deposited_usdc_shares, &accrued_usdc_bank)?,
+        borrowed_sol_with_interest: debt_with_interest(position.borrowed_sol_shares, &accrued_sol_bank)?,
+        borrowed_usdc_with_interest: debt_with_interest(position.borrowed_usdc_shares, &accrued_usdc_bank)?,
+        fixed_borrowed_sol: position.fixed_borrowed_sol,
+        fixed_borrowed_usdc: position.fixed_borrowed_usdc,
+        sol_price: sol_price.price,
+        usdc_price: usdc_price.price,
+        total_collateral_value: value_to_u64(total_collateral_value),
+        total_borrowed_value: value_to_u64(total_borrowed_value),
+        health_factor: calculate_health_factor(total_collateral_value, total_borrowed_value),
+        liquidation_health_factor: sol_liquidation_health_factor.min(usdc_liquidation_health_factor),
+        sol_borrow_capacity,
+        usdc_borrow_capacity,
+        liquidation_sol_price: calculate_liquidation_sol_price(position, sol_price, usdc_price, binding_bank)?,
+        timestamp: Clock::get()?.unix_timestamp,
+    })
+}
+
+// Deposit shares redeemed at the bank's exchange rate, rounded down as withdrawals pay out
+fn deposit_with_interest(shares: u64, bank: &Bank) -> Result<u64> {
+    amount_from_shares(shares, bank.total_deposits, bank.total_deposit_shares, Rounding::Floor)
+}
+
+// Borrow shares redeemed at the bank's exchange rate, rounded up as debt is charged
+fn debt_with_interest(shares: u64, bank: &Bank) -> Result<u64> {
+    amount_from_shares(shares, bank.total_borrowed, bank.total_borrowed_shares, Rounding::Ceil)
+}
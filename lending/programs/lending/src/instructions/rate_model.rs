@@ -0,0 +1,60 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::error::ErrorCode;
+use super::interest::{accrue_interest, validate_rate_model};
+
+#[derive(Accounts)]
+pub struct InitInterestRateModel<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    #[account(
+        init,
+        payer = signer,
+        space = 8 + InterestRateModel::INIT_SPACE,
+    )]
+    pub interest_rate_model: Account<'info, InterestRateModel>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetBankRateModel<'info> {
+    pub signer: Signer<'info>,
+    #[account(
+        mut,
+        constraint = bank.authority == signer.key() @ ErrorCode::Unauthorized,
+    )]
+    pub bank: Account<'info, Bank>,
+    // Model the bank accrues with today, required if it has one
+    pub current_rate_model: Option<Account<'info, InterestRateModel>>,
+    // Omit to fall back to the bank's built-in kinked curve
+    pub new_rate_model: Option<Account<'info, InterestRateModel>>,
+}
+
+// Models are immutable once created, so banks sharing one never see a retroactive change.
+// Governance switches a bank by pointing it at a new model.
+pub fn process_init_interest_rate_model(ctx: Context<InitInterestRateModel>, model: RateModel) -> Result<()> {
+    validate_rate_model(&model)?;
+
+    let rate_model = &mut ctx.accounts.interest_rate_model;
+    rate_model.authority = ctx.accounts.signer.key();
+    rate_model.model = model;
+
+    msg!("Interest rate model {} created: {:?}", rate_model.key(), model);
+    Ok(())
+}
+
+pub fn process_set_bank_rate_model(ctx: Context<SetBankRateModel>) -> Result<()> {
+    // Settle interest up to now under the old model before switching
+    accrue_interest(&mut ctx.accounts.bank, ctx.accounts.current_rate_model.as_ref())?;
+
+    let new_rate_model = ctx.accounts.new_rate_model
+        .as_ref()
+        .map(|rate_model| rate_model.key())
+        .unwrap_or_default();
+    ctx.accounts.bank.interest_rate_model = new_rate_model;
+    // An adaptive model starts from its own initial rate at target
+    ctx.accounts.bank.rate_at_target_bps = 0;
+
+    msg!("Bank {} interest rate model set to: {}", ctx.accounts.bank.mint_address, new_rate_model);
+    Ok(())
+}
@@ -0,0 +1,294 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_2022::{ self, Token2022, MintTo, Burn };
+use anchor_spl::token_interface::{ self, Mint, TokenAccount, TokenInterface, TransferChecked };
+use pyth_solana_receiver_sdk::price_update::PriceUpdateV2;
+use decimal::Rounding;
+use crate::state::*;
+use crate::error::ErrorCode;
+use super::interest::accrue_interest;
+use super::rewards::checkpoint_rewards;
+use super::health_monitor::update_user_health_factor;
+use super::utils::{amount_from_shares, pin_position_mint, shares_from_amount, transfer_into_treasury};
+
+#[derive(Accounts)]
+pub struct InitReceiptMint<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        seeds = [mint.key().as_ref()],
+        bump,
+        constraint = bank.authority == signer.key() @ ErrorCode::Unauthorized,
+    )]
+    pub bank: Box<Account<'info, Bank>>,
+    #[account(
+        init,
+        payer = signer,
+        seeds = [b"receipt", mint.key().as_ref()],
+        bump,
+        mint::decimals = mint.decimals,
+        mint::authority = receipt_mint,
+        mint::token_program = receipt_token_program,
+    )]
+    pub receipt_mint: Box<InterfaceAccount<'info, Mint>>,
+    pub receipt_token_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositForReceipt<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        seeds = [mint.key().as_ref()],
+        bump,
+    )]
+    pub bank: Box<Account<'info, Bank>>,
+    // Required when the bank references an interest rate model
+    pub interest_rate_model: Option<Box<Account<'info, InterestRateModel>>>,
+    #[account(
+        mut,
+        seeds = [b"treasury", mint.key().as_ref()],
+        bump,
+    )]
+    pub bank_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [b"receipt", mint.key().as_ref()],
+        bump,
+    )]
+    pub receipt_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = signer,
+        associated_token::token_program = token_program,
+    )]
+    pub user_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = signer,
+        associated_token::mint = receipt_mint,
+        associated_token::authority = signer,
+        associated_token::token_program = receipt_token_program,
+    )]
+    pub user_receipt_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub receipt_token_program: Program<'info, Token2022>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawReceipt<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        seeds = [mint.key().as_ref()],
+        bump,
+    )]
+    pub bank: Box<Account<'info, Bank>>,
+    // Required when the bank references an interest rate model
+    pub interest_rate_model: Option<Box<Account<'info, InterestRateModel>>>,
+    #[account(
+        mut,
+        seeds = [b"treasury", mint.key().as_ref()],
+        bump,
+    )]
+    pub bank_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [b"receipt", mint.key().as_ref()],
+        bump,
+    )]
+    pub receipt_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        associated_token::mint = receipt_mint,
+        associated_token::authority = signer,
+        associated_token::token_program = receipt_token_program,
+    )]
+    pub user_receipt_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = signer,
+        associated_token::mint = mint,
+        associated_token::authority = signer,
+        associated_token::token_program = token_program,
+    )]
+    pub user_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub receipt_token_program: Program<'info, Token2022>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositReceiptAsCollateral<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        seeds = [mint.key().as_ref()],
+        bump,
+    )]
+    pub bank: Box<Account<'info, Bank>>,
+    // Required when the bank references an interest rate model
+    pub interest_rate_model: Option<Box<Account<'info, InterestRateModel>>>,
+    // Required while the bank's deposit reward pool is emitting, user_rewards once the user opted in
+    #[account(mut)]
+    pub reward_pool: Option<Box<Account<'info, RewardPool>>>,
+    #[account(mut)]
+    pub user_rewards: Option<Box<Account<'info, UserRewards>>>,
+    #[account(
+        mut,
+        seeds = [b"receipt", mint.key().as_ref()],
+        bump,
+    )]
+    pub receipt_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        associated_token::mint = receipt_mint,
+        associated_token::authority = signer,
+        associated_token::token_program = receipt_token_program,
+    )]
+    pub user_receipt_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [signer.key().as_ref()],
+        bump,
+    )]
+    pub user_account: Box<Account<'info, User>>,
+    pub price_update: Box<Account<'info, PriceUpdateV2>>,
+    pub receipt_token_program: Program<'info, Token2022>,
+}
+
+// Let a bank issue transferable deposit receipts
+pub fn process_init_receipt_mint(ctx: Context<InitReceiptMint>) -> Result<()> {
+    ctx.accounts.bank.receipt_mint = ctx.accounts.receipt_mint.key();
+    msg!("Bank {} receipt mint: {}", ctx.accounts.bank.mint_address, ctx.accounts.receipt_mint.key());
+    Ok(())
+}
+
+// Deposit into the bank and receive one receipt per deposit share instead of a position on User.
+// Receipts earn the bank's supply rate but are not collateral until locked into a position.
+pub fn process_deposit_for_receipt(ctx: Context<DepositForReceipt>, amount: u64) -> Result<()> {
+    accrue_interest(&mut ctx.accounts.bank, ctx.accounts.interest_rate_model.as_deref())?;
+    let amount = transfer_into_treasury(
+        ctx.accounts.token_program.to_account_info(),
+        ctx.accounts.user_token_account.to_account_info(),
+        ctx.accounts.signer.to_account_info(),
+        &ctx.accounts.mint,
+        &mut ctx.accounts.bank_token_account,
+        amount,
+    )?;
+
+    let bank = &mut ctx.accounts.bank;
+    let shares = shares_from_amount(amount, bank.total_deposits, bank.total_deposit_shares, Rounding::Floor)?;
+    bank.total_deposits += amount;
+    bank.total_deposit_shares += shares;
+    bank.total_receipt_shares += shares;
+
+    let mint_key = ctx.accounts.mint.key();
+    let signer_seeds: &[&[&[u8]]] = &[
+        &[
+            b"receipt",
+            mint_key.as_ref(),
+            &[ctx.bumps.receipt_mint],
+        ],
+    ];
+    let mint_to_accounts = MintTo {
+        mint: ctx.accounts.receipt_mint.to_account_info(),
+        to: ctx.accounts.user_receipt_account.to_account_info(),
+        authority: ctx.accounts.receipt_mint.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new(ctx.accounts.receipt_token_program.to_account_info(), mint_to_accounts).with_signer(signer_seeds);
+    token_2022::mint_to(cpi_ctx, shares)?;
+
+    msg!("Deposited {} for {} receipts", amount, shares);
+    Ok(())
+}
+
+// Burn receipts and withdraw the underlying at the current share exchange rate
+pub fn process_withdraw_receipt(ctx: Context<WithdrawReceipt>, receipt_amount: u64) -> Result<()> {
+    accrue_interest(&mut ctx.accounts.bank, ctx.accounts.interest_rate_model.as_deref())?;
+    let bank = &mut ctx.accounts.bank;
+    let amount = amount_from_shares(receipt_amount, bank.total_deposits, bank.total_deposit_shares, Rounding::Floor)?;
+
+    let burn_accounts = Burn {
+        mint: ctx.accounts.receipt_mint.to_account_info(),
+        from: ctx.accounts.user_receipt_account.to_account_info(),
+        authority: ctx.accounts.signer.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new(ctx.accounts.receipt_token_program.to_account_info(), burn_accounts);
+    token_2022::burn(cpi_ctx, receipt_amount)?;
+
+    let transfer_cpi_accounts = TransferChecked {
+        from: ctx.accounts.bank_token_account.to_account_info(),
+        mint: ctx.accounts.mint.to_account_info(),
+        to: ctx.accounts.user_token_account.to_account_info(),
+        authority: ctx.accounts.bank_token_account.to_account_info(),
+    };
+    let mint_key = ctx.accounts.mint.key();
+    let signer_seeds: &[&[&[u8]]] = &[
+        &[
+            b"treasury",
+            mint_key.as_ref(),
+            &[ctx.bumps.bank_token_account],
+        ],
+    ];
+    let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), transfer_cpi_accounts).with_signer(signer_seeds);
+    token_interface::transfer_checked(cpi_ctx, amount, ctx.accounts.mint.decimals)?;
+
+    bank.total_deposits = bank.total_deposits.saturating_sub(amount);
+    bank.total_deposit_shares = bank.total_deposit_shares.saturating_sub(receipt_amount);
+    bank.total_receipt_shares = bank.total_receipt_shares.saturating_sub(receipt_amount);
+
+    msg!("Redeemed {} receipts for {}", receipt_amount, amount);
+    Ok(())
+}
+
+// Burn receipts and credit the shares they represent to the signer's position as collateral.
+// The bank's totals are unchanged, the shares only move from the receipt holder to the position,
+// where they start earning liquidity mining rewards.
+pub fn process_deposit_receipt_as_collateral(ctx: Context<DepositReceiptAsCollateral>, receipt_amount: u64) -> Result<()> {
+    accrue_interest(&mut ctx.accounts.bank, ctx.accounts.interest_rate_model.as_deref())?;
+
+    let burn_accounts = Burn {
+        mint: ctx.accounts.receipt_mint.to_account_info(),
+        from: ctx.accounts.user_receipt_account.to_account_info(),
+        authority: ctx.accounts.signer.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new(ctx.accounts.receipt_token_program.to_account_info(), burn_accounts);
+    token_2022::burn(cpi_ctx, receipt_amount)?;
+
+    let bank = &mut ctx.accounts.bank;
+    let amount = amount_from_shares(receipt_amount, bank.total_deposits, bank.total_deposit_shares, Rounding::Floor)?;
+    bank.total_receipt_shares = bank.total_receipt_shares.saturating_sub(receipt_amount);
+    let user = &mut ctx.accounts.user_account;
+    if pin_position_mint(user, ctx.accounts.mint.key())? {
+        user.deposited_usdc += amount;
+        user.deposited_usdc_shares += receipt_amount;
+    } else {
+        user.deposited_sol += amount;
+        user.deposited_sol_shares += receipt_amount;
+    }
+    user.last_updated = Clock::get()?.unix_timestamp;
+
+    update_user_health_factor(user, &ctx.accounts.price_update, ctx.remaining_accounts, &[&ctx.accounts.bank])?;
+    checkpoint_rewards(
+        &ctx.accounts.bank,
+        &ctx.accounts.user_account,
+        RewardSide::Deposit,
+        ctx.accounts.reward_pool.as_deref_mut(),
+        ctx.accounts.user_rewards.as_deref_mut(),
+    )?;
+    Ok(())
+}
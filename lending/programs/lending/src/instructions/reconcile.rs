@@ -0,0 +1,133 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{ self, Mint, TokenAccount, TokenInterface, TransferChecked };
+use crate::state::*;
+use crate::error::ErrorCode;
+
+#[derive(Accounts)]
+pub struct VerifyBank<'info> {
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        seeds = [mint.key().as_ref()],
+        bump,
+    )]
+    pub bank: Account<'info, Bank>,
+    #[account(
+        seeds = [b"treasury", mint.key().as_ref()],
+        bump,
+    )]
+    pub bank_token_account: InterfaceAccount<'info, TokenAccount>,
+}
+
+#[derive(Accounts)]
+pub struct SkimExcess<'info> {
+    pub signer: Signer<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        seeds = [mint.key().as_ref()],
+        bump,
+        constraint = bank.authority == signer.key() @ ErrorCode::Unauthorized,
+    )]
+    pub bank: Account<'info, Bank>,
+    #[account(
+        mut,
+        seeds = [b"treasury", mint.key().as_ref()],
+        bump,
+    )]
+    pub bank_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        token::mint = mint,
+        token::token_program = token_program,
+    )]
+    pub fee_receiver: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[event]
+pub struct BankVerified {
+    pub mint: Pubkey,
+    pub treasury_balance: u64,
+    pub expected_balance: i128,
+    pub excess: u64,
+    pub shortfall: u64,
+    pub deposit_shares_consistent: bool,
+    pub borrow_shares_consistent: bool,
+    pub liquidity_non_negative: bool,
+    pub healthy: bool,
+    pub timestamp: i64,
+}
+
+// Tokens the bank's books say the treasury should hold: deposits not lent out, plus reserves and
+// unclaimed referral fees. Interest accrual moves deposits and borrows together, so it does not
+// change this figure and the bank does not need to be refreshed first.
+fn expected_treasury_balance(bank: &Bank) -> i128 {
+    bank.total_deposits as i128
+        - bank.total_borrowed as i128
+        - bank.total_fixed_borrowed as i128
+        + bank.total_reserves as i128
+        + bank.total_referral_fees as i128
+}
+
+// Permissionless check of the bank's books against its treasury, reported as an event
+pub fn process_verify_bank(ctx: Context<VerifyBank>) -> Result<()> {
+    let bank = &ctx.accounts.bank;
+    let treasury_balance = ctx.accounts.bank_token_account.amount;
+    let expected_balance = expected_treasury_balance(bank);
+
+    let difference = treasury_balance as i128 - expected_balance;
+    let excess = difference.clamp(0, u64::MAX as i128) as u64;
+    let shortfall = (-difference).clamp(0, u64::MAX as i128) as u64;
+
+    // Outstanding amounts and shares must be zero together
+    let deposit_shares_consistent = (bank.total_deposits == 0) == (bank.total_deposit_shares == 0);
+    let borrow_shares_consistent = (bank.total_borrowed == 0) == (bank.total_borrowed_shares == 0);
+    let liquidity_non_negative = expected_balance >= 0;
+    let healthy = shortfall == 0 && deposit_shares_consistent && borrow_shares_consistent && liquidity_non_negative;
+
+    if !healthy {
+        msg!("Bank {} failed verification: shortfall {}, deposit shares ok {}, borrow shares ok {}, liquidity ok {}",
+             bank.mint_address, shortfall, deposit_shares_consistent, borrow_shares_consistent, liquidity_non_negative);
+    }
+
+    emit!(BankVerified {
+        mint: bank.mint_address,
+        treasury_balance,
+        expected_balance,
+        excess,
+        shortfall,
+        deposit_shares_consistent,
+        borrow_shares_consistent,
+        liquidity_non_negative,
+        healthy,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+// Sweep tokens the books do not account for, such as direct donations to the treasury
+pub fn process_skim_excess(ctx: Context<SkimExcess>) -> Result<()> {
+    let expected_balance = expected_treasury_balance(&ctx.accounts.bank).max(0);
+    let excess = (ctx.accounts.bank_token_account.amount as i128 - expected_balance).max(0) as u64;
+    require!(excess > 0, ErrorCode::NoExcessToSkim);
+
+    let mint_key = ctx.accounts.mint.key();
+    let signer_seeds: &[&[&[u8]]] = &[
+        &[
+            b"treasury",
+            mint_key.as_ref(),
+            &[ctx.bumps.bank_token_account],
+        ],
+    ];
+    let transfer_cpi_accounts = TransferChecked {
+        from: ctx.accounts.bank_token_account.to_account_info(),
+        mint: ctx.accounts.mint.to_account_info(),
+        to: ctx.accounts.fee_receiver.to_account_info(),
+        authority: ctx.accounts.bank_token_account.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), transfer_cpi_accounts).with_signer(signer_seeds);
+    token_interface::transfer_checked(cpi_ctx, excess, ctx.accounts.mint.decimals)?;
+
+    msg!("Skimmed {} excess from bank {} to {}", excess, mint_key, ctx.accounts.fee_receiver.key());
+    Ok(())
+}
@@ -0,0 +1,151 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_interface::{ self, Mint, TokenAccount, TokenInterface, TransferChecked };
+use decimal::{mul_div, MathError, Rounding};
+use crate::state::*;
+use crate::error::ErrorCode;
+
+#[derive(Accounts)]
+pub struct InitReferrerFees<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        init,
+        payer = signer,
+        space = 8 + ReferrerFees::INIT_SPACE,
+        seeds = [b"referrer_fees", signer.key().as_ref(), mint.key().as_ref()],
+        bump,
+    )]
+    pub referrer_fees: Account<'info, ReferrerFees>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimReferrerFees<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        seeds = [mint.key().as_ref()],
+        bump,
+    )]
+    pub bank: Account<'info, Bank>,
+    #[account(
+        mut,
+        seeds = [b"treasury", mint.key().as_ref()],
+        bump,
+    )]
+    pub bank_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"referrer_fees", signer.key().as_ref(), mint.key().as_ref()],
+        bump = referrer_fees.bump,
+    )]
+    pub referrer_fees: Account<'info, ReferrerFees>,
+    #[account(
+        init_if_needed,
+        payer = signer,
+        associated_token::mint = mint,
+        associated_token::authority = signer,
+        associated_token::token_program = token_program,
+    )]
+    pub referrer_token_account: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+// Referrers register once per mint, then pass this account to borrows they refer
+pub fn process_init_referrer_fees(ctx: Context<InitReferrerFees>) -> Result<()> {
+    let referrer_fees = &mut ctx.accounts.referrer_fees;
+    referrer_fees.referrer = ctx.accounts.signer.key();
+    referrer_fees.mint_address = ctx.accounts.mint.key();
+    referrer_fees.bump = ctx.bumps.referrer_fees;
+    Ok(())
+}
+
+// Claims pay out only fees realized by the referred borrowers' repayments, pending fees are still
+// owed to the treasury as part of their debt
+pub fn process_claim_referrer_fees(ctx: Context<ClaimReferrerFees>) -> Result<()> {
+    let amount = ctx.accounts.referrer_fees.accrued;
+    require!(amount > 0, ErrorCode::NothingToClaim);
+
+    let transfer_cpi_accounts = TransferChecked {
+        from: ctx.accounts.bank_token_account.to_account_info(),
+        mint: ctx.accounts.mint.to_account_info(),
+        to: ctx.accounts.referrer_token_account.to_account_info(),
+        authority: ctx.accounts.bank_token_account.to_account_info(),
+    };
+    let mint_key = ctx.accounts.mint.key();
+    let signer_seeds: &[&[&[u8]]] = &[
+        &[
+            b"treasury",
+            mint_key.as_ref(),
+            &[ctx.bumps.bank_token_account],
+        ],
+    ];
+    let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), transfer_cpi_accounts).with_signer(signer_seeds);
+    token_interface::transfer_checked(cpi_ctx, amount, ctx.accounts.mint.decimals)?;
+
+    let bank = &mut ctx.accounts.bank;
+    bank.total_referral_fees = bank.total_referral_fees.saturating_sub(amount);
+
+    let referrer_fees = &mut ctx.accounts.referrer_fees;
+    referrer_fees.accrued = 0;
+    referrer_fees.total_claimed = referrer_fees.total_claimed.saturating_add(amount);
+
+    msg!("Referrer {} claimed {} of mint {}", referrer_fees.referrer, amount, mint_key);
+    Ok(())
+}
+
+// Referrer account and pending referral fee of one side of the user's debt
+fn pending_referral(user: &mut User, is_usdc: bool) -> (&mut Pubkey, &mut u64) {
+    if is_usdc {
+        (&mut user.referrer_fees_usdc, &mut user.pending_referral_fee_usdc)
+    } else {
+        (&mut user.referrer_fees_sol, &mut user.pending_referral_fee_sol)
+    }
+}
+
+// Hold a borrow's referral fee as pending on the user's debt and the referrer's account. A side
+// with pending fees keeps its referrer until they are repaid.
+pub fn record_referral_fee(user: &mut User, is_usdc: bool, referrer_fees: &mut Account<ReferrerFees>, fee: u64) -> Result<()> {
+    let (referrer, pending) = pending_referral(user, is_usdc);
+    if *pending > 0 {
+        require_keys_eq!(referrer_fees.key(), *referrer, ErrorCode::InvalidReferrer);
+    }
+    *referrer = referrer_fees.key();
+    *pending = pending.saturating_add(fee);
+    referrer_fees.pending = referrer_fees.pending.saturating_add(fee);
+    Ok(())
+}
+
+// Realize the share of the user's pending referral fee that repaying `repaid` of `borrowed` settles,
+// making it claimable by the referrer. Call before the user's borrowed balance is reduced.
+pub fn realize_referral_fee(
+    user: &mut User,
+    is_usdc: bool,
+    referrer_fees: Option<&mut Account<ReferrerFees>>,
+    repaid: u64,
+    borrowed: u64,
+) -> Result<()> {
+    let (referrer, pending) = pending_referral(user, is_usdc);
+    if *pending == 0 || repaid == 0 {
+        return Ok(());
+    }
+    let referrer_fees = referrer_fees.ok_or(ErrorCode::InvalidReferrer)?;
+    require_keys_eq!(referrer_fees.key(), *referrer, ErrorCode::InvalidReferrer);
+
+    let realized = if repaid >= borrowed {
+        *pending
+    } else {
+        let realized = mul_div(*pending as u128, repaid as u128, borrowed as u128, Rounding::Floor)?;
+        u64::try_from(realized).map_err(|_| MathError::Overflow)?
+    };
+    *pending -= realized;
+    referrer_fees.pending = referrer_fees.pending.saturating_sub(realized);
+    referrer_fees.accrued = referrer_fees.accrued.saturating_add(realized);
+    Ok(())
+}
@@ -1,9 +1,13 @@
 use anchor_lang::prelude::*;
 use anchor_spl::associated_token::AssociatedToken;
-use anchor_spl::token_interface::{ self, Mint, TokenAccount, TokenInterface, TransferChecked };
+use anchor_spl::token_interface::{ Mint, TokenAccount, TokenInterface };
+use decimal::Rounding;
 use crate::state::*;
 use crate::error::ErrorCode;
 use super::interest::accrue_interest;
+use super::rewards::checkpoint_rewards;
+use super::referral::realize_referral_fee;
+use super::utils::{position_is_usdc, shares_from_amount, transfer_into_treasury};
 
 #[derive(Accounts)]
 pub struct Repay<'info> {
@@ -16,6 +20,16 @@ pub struct Repay<'info> {
         bump,
     )]  
     pub bank: Account<'info, Bank>,
+    // Required when the bank references an interest rate model
+    pub interest_rate_model: Option<Account<'info, InterestRateModel>>,
+    // Required while the bank's borrow reward pool is emitting, user_rewards once the user opted in
+    #[account(mut)]
+    pub reward_pool: Option<Box<Account<'info, RewardPool>>>,
+    #[account(mut)]
+    pub user_rewards: Option<Box<Account<'info, UserRewards>>>,
+    // Required while the user's debt in this mint carries a pending referral fee
+    #[account(mut)]
+    pub referrer_fees: Option<Account<'info, ReferrerFees>>,
     #[account(
         mut, 
         seeds = [b"treasury", mint.key().as_ref()],
@@ -42,61 +56,54 @@ pub struct Repay<'info> {
 }
 
 pub fn process_repay(ctx: Context<Repay>, amount: u64) -> Result<()> {
-    accrue_interest(&mut ctx.accounts.bank)?;
+    accrue_interest(&mut ctx.accounts.bank, ctx.accounts.interest_rate_model.as_ref())?;
     let user = &mut ctx.accounts.user_account;
 
-    let borrowed_asset; 
-    let mint_key = ctx.accounts.mint.key();
-    let user_usdc = user.usdc_address;
-
+    let is_usdc = position_is_usdc(user, ctx.accounts.mint.key())?;
 
-    match mint_key {
-        key if key == user_usdc => {
-            borrowed_asset = user.borrowed_usdc;
-        },
-        _ => {
-            borrowed_asset = user.borrowed_sol;
-        }
-    }
+    let borrowed_asset = if is_usdc { user.borrowed_usdc } else { user.borrowed_sol };
 
     if amount > borrowed_asset {
         return Err(ErrorCode::OverRepay.into());
     }
 
-    let transfer_cpi_accounts = TransferChecked {
-        from: ctx.accounts.user_token_account.to_account_info(),
-        mint: ctx.accounts.mint.to_account_info(),
-        to: ctx.accounts.bank_token_account.to_account_info(),
-        authority: ctx.accounts.signer.to_account_info(),
-    };
-
-    let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), transfer_cpi_accounts);
-    let decimals = ctx.accounts.mint.decimals;
-
-    token_interface::transfer_checked(cpi_ctx, amount, decimals)?;
+    // Only what arrived in the treasury is repaid, transfer fees are borne by the borrower
+    let amount = transfer_into_treasury(
+        ctx.accounts.token_program.to_account_info(),
+        ctx.accounts.user_token_account.to_account_info(),
+        ctx.accounts.signer.to_account_info(),
+        &ctx.accounts.mint,
+        &mut ctx.accounts.bank_token_account,
+        amount,
+    )?;
 
 
     let bank = &mut ctx.accounts.bank;
 
-    let borrowed_ratio = amount.checked_div(bank.total_borrowed).ok_or(ErrorCode::OverRepay)?;
-    let users_shares = bank.total_borrowed_shares.checked_mul(borrowed_ratio).ok_or(ErrorCode::OverRepay)?;
+    let users_shares = shares_from_amount(amount, bank.total_borrowed, bank.total_borrowed_shares, Rounding::Floor)?;
     
     let user = &mut ctx.accounts.user_account;
+    realize_referral_fee(user, is_usdc, ctx.accounts.referrer_fees.as_mut(), amount, borrowed_asset)?;
     
-    match mint_key {
-        key if key == user_usdc => {
-            user.borrowed_usdc -= amount;
-            user.borrowed_usdc_shares -= users_shares;
-        },
-        _ => {
-            user.borrowed_sol -= amount;
-            user.borrowed_sol_shares -= users_shares; 
-        }
+    if is_usdc {
+        user.borrowed_usdc -= amount;
+        user.borrowed_usdc_shares = user.borrowed_usdc_shares.saturating_sub(users_shares);
+    } else {
+        user.borrowed_sol -= amount;
+        user.borrowed_sol_shares = user.borrowed_sol_shares.saturating_sub(users_shares); 
     }
 
 
-    bank.total_borrowed -= amount;
-    bank.total_borrowed_shares -= users_shares;
+    bank.total_borrowed = bank.total_borrowed.saturating_sub(amount);
+    bank.total_borrowed_shares = bank.total_borrowed_shares.saturating_sub(users_shares);
+
+    checkpoint_rewards(
+        &ctx.accounts.bank,
+        &ctx.accounts.user_account,
+        RewardSide::Borrow,
+        ctx.accounts.reward_pool.as_deref_mut(),
+        ctx.accounts.user_rewards.as_deref_mut(),
+    )?;
 
     Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,344 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_interface::{ self, Mint, TokenAccount, TokenInterface, TransferChecked };
+use decimal::{Decimal, MathError, Rounding};
+use crate::state::*;
+use crate::error::ErrorCode;
+
+#[derive(Accounts)]
+#[instruction(side: RewardSide, emissions_per_second: u64, start_ts: i64)]
+pub struct CreateRewardPool<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    #[account(
+        mut,
+        constraint = bank.authority == signer.key() @ ErrorCode::Unauthorized,
+    )]
+    pub bank: Box<Account<'info, Bank>>,
+    pub reward_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        init,
+        payer = signer,
+        space = 8 + RewardPool::INIT_SPACE,
+        seeds = [b"reward_pool", bank.key().as_ref(), reward_mint.key().as_ref(), start_ts.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub reward_pool: Box<Account<'info, RewardPool>>,
+    #[account(
+        init,
+        payer = signer,
+        token::mint = reward_mint,
+        token::authority = reward_vault,
+        seeds = [b"reward_vault", reward_pool.key().as_ref()],
+        bump,
+    )]
+    pub reward_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    // Funds the full emission schedule up front
+    #[account(
+        mut,
+        token::mint = reward_mint,
+        token::authority = signer,
+    )]
+    pub funder_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    // Pool currently active on this side, required if there is one
+    #[account(mut)]
+    pub previous_reward_pool: Option<Box<Account<'info, RewardPool>>>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitUserRewards<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    #[account(address = reward_pool.bank)]
+    pub bank: Box<Account<'info, Bank>>,
+    #[account(
+        mut,
+        seeds = [signer.key().as_ref()],
+        bump,
+    )]
+    pub user_account: Box<Account<'info, User>>,
+    #[account(mut)]
+    pub reward_pool: Box<Account<'info, RewardPool>>,
+    #[account(
+        init,
+        payer = signer,
+        space = 8 + UserRewards::INIT_SPACE,
+        seeds = [b"user_rewards", reward_pool.key().as_ref(), signer.key().as_ref()],
+        bump,
+    )]
+    pub user_rewards: Box<Account<'info, UserRewards>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRewards<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    #[account(address = reward_pool.bank)]
+    pub bank: Box<Account<'info, Bank>>,
+    #[account(
+        seeds = [signer.key().as_ref()],
+        bump,
+    )]
+    pub user_account: Box<Account<'info, User>>,
+    #[account(mut)]
+    pub reward_pool: Box<Account<'info, RewardPool>>,
+    #[account(
+        mut,
+        seeds = [b"user_rewards", reward_pool.key().as_ref(), signer.key().as_ref()],
+        bump = user_rewards.bump,
+    )]
+    pub user_rewards: Box<Account<'info, UserRewards>>,
+    #[account(address = reward_pool.reward_mint)]
+    pub reward_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        seeds = [b"reward_vault", reward_pool.key().as_ref()],
+        bump = reward_pool.vault_bump,
+    )]
+    pub reward_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = signer,
+        associated_token::mint = reward_mint,
+        associated_token::authority = signer,
+        associated_token::token_program = token_program,
+    )]
+    pub user_reward_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+// Start emitting `reward_mint` to a bank's depositors or borrowers between start_ts and end_ts.
+// A side has one active pool at a time, and it can only be replaced once it has ended.
+pub fn process_create_reward_pool(
+    ctx: Context<CreateRewardPool>,
+    side: RewardSide,
+    emissions_per_second: u64,
+    start_ts: i64,
+    end_ts: i64,
+) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        emissions_per_second > 0 && start_ts >= now && end_ts > start_ts,
+        ErrorCode::InvalidRewardPool
+    );
+
+    let bank = &mut ctx.accounts.bank;
+    let active_pool = active_reward_pool(bank, side);
+    if active_pool != Pubkey::default() {
+        let previous = ctx.accounts.previous_reward_pool.as_deref_mut().ok_or(ErrorCode::RewardPoolMismatch)?;
+        require_keys_eq!(previous.key(), active_pool, ErrorCode::RewardPoolMismatch);
+        require!(now >= previous.end_ts, ErrorCode::RewardPoolActive);
+        // Final index of the outgoing pool, no checkpoints will update it after this
+        accrue_reward_pool(previous, now)?;
+    }
+
+    let total_rewards = emissions_per_second
+        .checked_mul((end_ts - start_ts) as u64)
+        .ok_or(ErrorCode::InvalidRewardPool)?;
+    let transfer_cpi_accounts = TransferChecked {
+        from: ctx.accounts.funder_token_account.to_account_info(),
+        mint: ctx.accounts.reward_mint.to_account_info(),
+        to: ctx.accounts.reward_vault.to_account_info(),
+        authority: ctx.accounts.signer.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), transfer_cpi_accounts);
+    token_interface::transfer_checked(cpi_ctx, total_rewards, ctx.accounts.reward_mint.decimals)?;
+
+    let pool = &mut ctx.accounts.reward_pool;
+    pool.bank = bank.key();
+    pool.reward_mint = ctx.accounts.reward_mint.key();
+    pool.side = side;
+    pool.emissions_per_second = emissions_per_second;
+    pool.start_ts = start_ts;
+    pool.end_ts = end_ts;
+    pool.last_update_ts = start_ts;
+    pool.reward_index = 0;
+    pool.total_shares = eligible_shares(bank, side);
+    pool.bump = ctx.bumps.reward_pool;
+    pool.vault_bump = ctx.bumps.reward_vault;
+
+    match side {
+        RewardSide::Deposit => {
+            bank.deposit_reward_pool = pool.key();
+            bank.deposit_reward_end_ts = end_ts;
+        }
+        RewardSide::Borrow => {
+            bank.borrow_reward_pool = pool.key();
+            bank.borrow_reward_end_ts = end_ts;
+        }
+    }
+
+    msg!("Reward pool {} for bank {}: {} per second from {} to {}",
+         pool.key(), bank.mint_address, emissions_per_second, start_ts, end_ts);
+    Ok(())
+}
+
+// Users opt into the bank's active pool once, earning on their current shares from this point on.
+// From then on their share changes on that side must checkpoint it until it stops emitting.
+pub fn process_init_user_rewards(ctx: Context<InitUserRewards>) -> Result<()> {
+    let bank = &ctx.accounts.bank;
+    let pool = &mut ctx.accounts.reward_pool;
+    require_keys_eq!(pool.key(), active_reward_pool(bank, pool.side), ErrorCode::RewardPoolMismatch);
+    accrue_reward_pool(pool, Clock::get()?.unix_timestamp)?;
+
+    let user = &mut ctx.accounts.user_account;
+    let slot = reward_slot(bank, user, pool.side);
+    user.reward_pools[slot] = pool.key();
+
+    let user_rewards = &mut ctx.accounts.user_rewards;
+    user_rewards.owner = ctx.accounts.signer.key();
+    user_rewards.reward_pool = pool.key();
+    user_rewards.reward_index = pool.reward_index;
+    user_rewards.shares = user_shares(bank, user, pool.side);
+    user_rewards.bump = ctx.bumps.user_rewards;
+    Ok(())
+}
+
+// Claims are credited at the shares recorded by the user's last checkpoint, so shares gained after
+// a pool was replaced earn nothing from it
+pub fn process_claim_rewards(ctx: Context<ClaimRewards>) -> Result<()> {
+    let pool = &mut ctx.accounts.reward_pool;
+    accrue_reward_pool(pool, Clock::get()?.unix_timestamp)?;
+
+    let user_rewards = &mut ctx.accounts.user_rewards;
+    let shares = user_rewards.shares;
+    checkpoint_user(user_rewards, pool, shares)?;
+
+    // Bounded by the vault in case rounding left it marginally short
+    let amount = user_rewards.accrued.min(ctx.accounts.reward_vault.amount);
+    require!(amount > 0, ErrorCode::NothingToClaim);
+
+    let transfer_cpi_accounts = TransferChecked {
+        from: ctx.accounts.reward_vault.to_account_info(),
+        mint: ctx.accounts.reward_mint.to_account_info(),
+        to: ctx.accounts.user_reward_token_account.to_account_info(),
+        authority: ctx.accounts.reward_vault.to_account_info(),
+    };
+    let pool_key = pool.key();
+    let signer_seeds: &[&[&[u8]]] = &[
+        &[
+            b"reward_vault",
+            pool_key.as_ref(),
+            &[pool.vault_bump],
+        ],
+    ];
+    let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), transfer_cpi_accounts).with_signer(signer_seeds);
+    token_interface::transfer_checked(cpi_ctx, amount, ctx.accounts.reward_mint.decimals)?;
+
+    user_rewards.accrued -= amount;
+    user_rewards.total_claimed = user_rewards.total_claimed.saturating_add(amount);
+
+    msg!("User {} claimed {} rewards from pool {}", user_rewards.owner, amount, pool_key);
+    Ok(())
+}
+
+// Credit the user's rewards since their last checkpoint at the shares recorded then, and record the
+// shares deposit/withdraw/borrow/repay just changed. Called after the change. Until the bank's pool
+// on that side stops emitting it must be passed, and so must the user's UserRewards if they opted
+// into it; users who did not opt in act without earning.
+pub fn checkpoint_rewards(
+    bank: &Bank,
+    user: &User,
+    side: RewardSide,
+    reward_pool: Option<&mut Account<RewardPool>>,
+    user_rewards: Option<&mut Account<UserRewards>>,
+) -> Result<()> {
+    let active_pool = active_reward_pool(bank, side);
+    let now = Clock::get()?.unix_timestamp;
+    // The shares recorded at the last checkpoints keep earning up to the end of the emissions
+    if active_pool == Pubkey::default() || now >= reward_end_ts(bank, side) {
+        return Ok(());
+    }
+    let pool = reward_pool.ok_or(ErrorCode::RewardPoolMismatch)?;
+    require_keys_eq!(pool.key(), active_pool, ErrorCode::RewardPoolMismatch);
+    accrue_reward_pool(pool, now)?;
+    pool.total_shares = eligible_shares(bank, side);
+
+    if user.reward_pools[reward_slot(bank, user, side)] == active_pool {
+        let user_rewards = user_rewards.ok_or(ErrorCode::RewardPoolMismatch)?;
+        require_keys_eq!(user_rewards.reward_pool, active_pool, ErrorCode::RewardPoolMismatch);
+        require_keys_eq!(user_rewards.owner, user.owner, ErrorCode::RewardPoolMismatch);
+        checkpoint_user(user_rewards, pool, user_shares(bank, user, side))?;
+    }
+    Ok(())
+}
+
+#[inline(always)]
+fn active_reward_pool(bank: &Bank, side: RewardSide) -> Pubkey {
+    match side {
+        RewardSide::Deposit => bank.deposit_reward_pool,
+        RewardSide::Borrow => bank.borrow_reward_pool,
+    }
+}
+
+#[inline(always)]
+fn reward_end_ts(bank: &Bank, side: RewardSide) -> i64 {
+    match side {
+        RewardSide::Deposit => bank.deposit_reward_end_ts,
+        RewardSide::Borrow => bank.borrow_reward_end_ts,
+    }
+}
+
+// Shares rewards are split between. Receipt holders have no position to checkpoint, so receipt
+// shares are left out rather than earning emissions nobody can claim.
+#[inline(always)]
+fn eligible_shares(bank: &Bank, side: RewardSide) -> u64 {
+    match side {
+        RewardSide::Deposit => bank.total_deposit_shares.saturating_sub(bank.total_receipt_shares),
+        RewardSide::Borrow => bank.total_borrowed_shares,
+    }
+}
+
+// Index of the position and side in User::reward_pools
+#[inline(always)]
+fn reward_slot(bank: &Bank, user: &User, side: RewardSide) -> usize {
+    let position = if bank.mint_address == user.usdc_address { 2 } else { 0 };
+    match side {
+        RewardSide::Deposit => position,
+        RewardSide::Borrow => position + 1,
+    }
+}
+
+#[inline(always)]
+fn user_shares(bank: &Bank, user: &User, side: RewardSide) -> u64 {
+    let is_usdc = bank.mint_address == user.usdc_address;
+    match (side, is_usdc) {
+        (RewardSide::Deposit, true) => user.deposited_usdc_shares,
+        (RewardSide::Deposit, false) => user.deposited_sol_shares,
+        (RewardSide::Borrow, true) => user.borrowed_usdc_shares,
+        (RewardSide::Borrow, false) => user.borrowed_sol_shares,
+    }
+}
+
+// Advance the pool's reward index to `now`, clamped to its emission window, over the shares
+// recorded at its last checkpoint
+fn accrue_reward_pool(pool: &mut RewardPool, now: i64) -> Result<()> {
+    let from = pool.last_update_ts.max(pool.start_ts);
+    let to = now.min(pool.end_ts);
+    if to > from && pool.total_shares > 0 {
+        // Round the index down so the pool never owes more than it emitted
+        let emitted = Decimal::from_integer(pool.emissions_per_second).try_mul_int((to - from) as u64)?;
+        let per_share = emitted.try_div_int(pool.total_shares, Rounding::Floor)?;
+        pool.reward_index = Decimal::from_wad(pool.reward_index).try_add(per_share)?.to_wad();
+    }
+    pool.last_update_ts = pool.last_update_ts.max(to);
+    Ok(())
+}
+
+// Credit the index growth since the user's last checkpoint at the shares recorded then, which they
+// held throughout, and record `shares` for the next one
+fn checkpoint_user(user_rewards: &mut UserRewards, pool: &RewardPool, shares: u64) -> Result<()> {
+    let earned = Decimal::from_wad(pool.reward_index)
+        .saturating_sub(Decimal::from_wad(user_rewards.reward_index))
+        .try_mul_int(user_rewards.shares)?
+        .try_to_u64(Rounding::Floor)?;
+    user_rewards.accrued = user_rewards.accrued.checked_add(earned).ok_or(MathError::Overflow)?;
+    user_rewards.reward_index = pool.reward_index;
+    user_rewards.shares = shares;
+    Ok(())
+}
@@ -0,0 +1,277 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{ self, Mint, TokenAccount, TransferChecked };
+use anchor_spl::token_interface::spl_token_2022::{
+    self,
+    extension::{ BaseStateWithExtensions, StateWithExtensions, transfer_fee::TransferFeeConfig },
+};
+use pyth_solana_receiver_sdk::price_update::PriceUpdateV2;
+use price_oracle::{OraclePolicy, OraclePrice, PRICE_EXPONENT};
+use decimal::{mul_div, Decimal, MathError, Rounding};
+use crate::constants::{
+    SOL_USD_FEED_ID, USDC_USD_FEED_ID, SOL_USD_POLICY, USDC_USD_POLICY, SOL_DECIMALS, USDC_DECIMALS,
+};
+use crate::state::*;
+use crate::error::ErrorCode;
+use super::oracle::get_asset_price;
+
+// Price used to value collateral (low end of the confidence interval) and debt (high end) under a
+// bank's valuation mode, in USD per whole token
+pub trait ValuationPrice {
+    fn collateral_price(&self, mode: ValuationMode) -> Result<Decimal>;
+    fn debt_price(&self, mode: ValuationMode) -> Result<Decimal>;
+}
+
+impl ValuationPrice for OraclePrice {
+    fn collateral_price(&self, mode: ValuationMode) -> Result<Decimal> {
+        let price = match mode {
+            ValuationMode::Spot => self.lower_bound(),
+            ValuationMode::Ema => self.ema_lower_bound(),
+            ValuationMode::Conservative => self.lower_bound().min(self.ema_lower_bound()),
+        };
+        usd_price(price)
+    }
+
+    fn debt_price(&self, mode: ValuationMode) -> Result<Decimal> {
+        let price = match mode {
+            ValuationMode::Spot => self.upper_bound(),
+            ValuationMode::Ema => self.ema_upper_bound(),
+            ValuationMode::Conservative => self.upper_bound().max(self.ema_upper_bound()),
+        };
+        usd_price(price)
+    }
+}
+
+// Oracle price, quoted at PRICE_EXPONENT, as a decimal in USD per whole token
+#[inline(always)]
+pub fn usd_price(price: u64) -> Result<Decimal> {
+    Ok(Decimal::from_price(price, PRICE_EXPONENT)?)
+}
+
+// USD value of `amount` base units of a mint with `decimals` at `price` (USD per whole token)
+#[inline(always)]
+pub fn token_value(price: Decimal, amount: u64, decimals: u8, rounding: Rounding) -> Result<Decimal> {
+    Ok(Decimal::from_token_amount(amount, decimals)?.try_mul(price, rounding)?)
+}
+
+// Position value as reported in account fields and events, in USD at PRICE_EXPONENT, rounded down
+#[inline(always)]
+pub fn value_to_u64(value: Decimal) -> u64 {
+    value.try_to_price(PRICE_EXPONENT, Rounding::Floor).unwrap_or(u64::MAX)
+}
+
+// Fetch current SOL/USD and USDC/USD prices, falling back to the secondary oracle or a manual
+// price passed in `fallback_sources` when Pyth is unavailable. Each side of the user's position is
+// checked against the oracle policy of its bank when that bank is among `banks`.
+pub fn get_sol_and_usdc_prices(
+    price_update: &PriceUpdateV2,
+    fallback_sources: &[AccountInfo],
+    user: &User,
+    banks: &[&Bank],
+) -> Result<(OraclePrice, OraclePrice)> {
+    let sol_bank = banks.iter().find(|bank| bank.mint_address != user.usdc_address);
+    let usdc_bank = banks.iter().find(|bank| bank.mint_address == user.usdc_address);
+    let sol_policy = bank_oracle_policy(sol_bank.copied(), SOL_USD_POLICY);
+    let usdc_policy = bank_oracle_policy(usdc_bank.copied(), USDC_USD_POLICY);
+
+    let sol_price = get_asset_price(price_update, fallback_sources, SOL_USD_FEED_ID, &sol_policy)?;
+    let usdc_price = get_asset_price(price_update, fallback_sources, USDC_USD_FEED_ID, &usdc_policy)?;
+    Ok((sol_price, usdc_price))
+}
+
+// Policy a bank set for its asset, or the feed's default when the bank has none or is not part of
+// the instruction
+fn bank_oracle_policy(bank: Option<&Bank>, default: OraclePolicy) -> OraclePolicy {
+    match bank {
+        Some(bank) if bank.oracle_max_price_age > 0 => OraclePolicy {
+            maximum_age: bank.oracle_max_price_age,
+            max_confidence_bps: bank.oracle_max_confidence_bps,
+            max_ema_deviation_bps: bank.oracle_max_ema_deviation_bps,
+        },
+        _ => default,
+    }
+}
+
+// Transfer `amount` from a user into the treasury and return what the treasury actually received,
+// which is less than `amount` for Token-2022 mints charging a transfer fee
+pub fn transfer_into_treasury<'info>(
+    token_program: AccountInfo<'info>,
+    from: AccountInfo<'info>,
+    authority: AccountInfo<'info>,
+    mint: &InterfaceAccount<'info, Mint>,
+    treasury: &mut InterfaceAccount<'info, TokenAccount>,
+    amount: u64,
+) -> Result<u64> {
+    let balance_before = treasury.amount;
+
+    let transfer_cpi_accounts = TransferChecked {
+        from,
+        mint: mint.to_account_info(),
+        to: treasury.to_account_info(),
+        authority,
+    };
+    let cpi_ctx = CpiContext::new(token_program, transfer_cpi_accounts);
+    token_interface::transfer_checked(cpi_ctx, amount, mint.decimals)?;
+
+    treasury.reload()?;
+    Ok(treasury.amount.saturating_sub(balance_before))
+}
+
+// Amount to send for `amount` to reach the treasury after the mint's Token-2022 transfer fee, if any
+pub fn amount_before_transfer_fee(mint: &InterfaceAccount<Mint>, amount: u64) -> Result<u64> {
+    let mint_info = mint.to_account_info();
+    if *mint_info.owner != spl_token_2022::ID {
+        return Ok(amount);
+    }
+
+    let mint_data = mint_info.try_borrow_data()?;
+    let mint_state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_data)?;
+    let Ok(fee_config) = mint_state.get_extension::<TransferFeeConfig>() else {
+        return Ok(amount);
+    };
+    let fee = fee_config
+        .calculate_inverse_epoch_fee(Clock::get()?.epoch, amount)
+        .ok_or(MathError::Overflow)?;
+    Ok(amount.checked_add(fee).ok_or(MathError::Overflow)?)
+}
+
+// Total collateral and borrowed USD values of a position under the given valuation mode, valuing
+// collateral at the low end of the oracle confidence interval and debt at the high end. Balances are
+// converted to whole tokens at the SOL and USDC mint decimals, collateral rounded down and debt up.
+pub fn calculate_position_values(
+    user: &User,
+    sol_price: OraclePrice,
+    usdc_price: OraclePrice,
+    mode: ValuationMode,
+) -> Result<(Decimal, Decimal)> {
+    let total_collateral_value = token_value(sol_price.collateral_price(mode)?, user.deposited_sol, SOL_DECIMALS, Rounding::Floor)?
+        .try_add(token_value(usdc_price.collateral_price(mode)?, user.deposited_usdc, USDC_DECIMALS, Rounding::Floor)?)?;
+
+    let borrowed_sol = user.borrowed_sol.checked_add(user.fixed_borrowed_sol).ok_or(MathError::Overflow)?;
+    let borrowed_usdc = user.borrowed_usdc.checked_add(user.fixed_borrowed_usdc).ok_or(MathError::Overflow)?;
+    let total_borrowed_value = token_value(sol_price.debt_price(mode)?, borrowed_sol, SOL_DECIMALS, Rounding::Ceil)?
+        .try_add(token_value(usdc_price.debt_price(mode)?, borrowed_usdc, USDC_DECIMALS, Rounding::Ceil)?)?;
+
+    Ok((total_collateral_value, total_borrowed_value))
+}
+
+// Health factor in bps (10_000 = 1.0), rounded down. u64::MAX when there is no debt or the ratio
+// is too large to represent.
+pub fn calculate_health_factor(total_collateral_value: Decimal, total_borrowed_value: Decimal) -> u64 {
+    if total_borrowed_value.is_zero() {
+        return u64::MAX;
+    }
+    total_collateral_value
+        .try_div(total_borrowed_value, Rounding::Floor)
+        .and_then(|ratio| ratio.try_to_bps(Rounding::Floor))
+        .unwrap_or(u64::MAX)
+}
+
+// Health factor with collateral weighted by the liquidation threshold (bps).
+// The position is liquidatable once this drops below BPS_DENOMINATOR.
+pub fn calculate_liquidation_health_factor(
+    total_collateral_value: Decimal,
+    total_borrowed_value: Decimal,
+    liquidation_threshold: u64,
+) -> Result<u64> {
+    let weighted_collateral = total_collateral_value.try_mul_bps(liquidation_threshold, Rounding::Floor)?;
+    Ok(calculate_health_factor(weighted_collateral, total_borrowed_value))
+}
+
+// Liquidation health factor liquidate enforces when the position is liquidated against
+// `collateral_bank`: valued under that bank's liquidation mode and weighted by its threshold
+pub fn calculate_bank_liquidation_health_factor(
+    user: &User,
+    collateral_bank: &Bank,
+    sol_price: OraclePrice,
+    usdc_price: OraclePrice,
+) -> Result<u64> {
+    let (total_collateral_value, total_borrowed_value) =
+        calculate_position_values(user, sol_price, usdc_price, collateral_bank.liquidation_valuation_mode)?;
+    calculate_liquidation_health_factor(total_collateral_value, total_borrowed_value, collateral_bank.liquidation_threshold)
+}
+
+// Additional value that can be borrowed before hitting the max LTV (bps)
+pub fn calculate_borrow_capacity(
+    total_collateral_value: Decimal,
+    total_borrowed_value: Decimal,
+    max_ltv: u64,
+) -> Result<Decimal> {
+    let max_borrow_value = total_collateral_value.try_mul_bps(max_ltv, Rounding::Floor)?;
+    Ok(max_borrow_value.saturating_sub(total_borrowed_value))
+}
+
+// SOL spot price (at PRICE_EXPONENT) at which the liquidation health factor liquidate enforces
+// against `collateral_bank` reaches 1.0, holding the USDC price fixed. Both sides are valued at
+// the bank's liquidation mode bounds, which are assumed to move with the SOL price. Returns 0 when
+// no SOL price makes the position liquidatable.
+pub fn calculate_liquidation_sol_price(
+    user: &User,
+    sol_price: OraclePrice,
+    usdc_price: OraclePrice,
+    collateral_bank: &Bank,
+) -> Result<u64> {
+    let mode = collateral_bank.liquidation_valuation_mode;
+    let lt = collateral_bank.liquidation_threshold;
+    let borrowed_sol = user.borrowed_sol.checked_add(user.fixed_borrowed_sol).ok_or(MathError::Overflow)?;
+    let borrowed_usdc = user.borrowed_usdc.checked_add(user.fixed_borrowed_usdc).ok_or(MathError::Overflow)?;
+
+    // Threshold-weighted collateral and debt value per USD of SOL spot price, and in USD on the USDC side
+    let spot = usd_price(sol_price.price)?;
+    let sol_collateral = token_value(sol_price.collateral_price(mode)?.try_div(spot, Rounding::Floor)?, user.deposited_sol, SOL_DECIMALS, Rounding::Floor)?
+        .try_mul_bps(lt, Rounding::Floor)?;
+    let sol_debt = token_value(sol_price.debt_price(mode)?.try_div(spot, Rounding::Ceil)?, borrowed_sol, SOL_DECIMALS, Rounding::Ceil)?;
+    let usdc_collateral = token_value(usdc_price.collateral_price(mode)?, user.deposited_usdc, USDC_DECIMALS, Rounding::Floor)?
+        .try_mul_bps(lt, Rounding::Floor)?;
+    let usdc_debt = token_value(usdc_price.debt_price(mode)?, borrowed_usdc, USDC_DECIMALS, Rounding::Ceil)?;
+
+    // Liquidatable once price * sol_collateral + usdc_collateral < price * sol_debt + usdc_debt: a
+    // net SOL long falls below the price, a net SOL short rises above it
+    let liquidation_price = if sol_collateral > sol_debt && usdc_debt > usdc_collateral {
+        usdc_debt.try_sub(usdc_collateral)?.try_div(sol_collateral.try_sub(sol_debt)?, Rounding::Floor)?
+    } else if sol_debt > sol_collateral && usdc_collateral > usdc_debt {
+        usdc_collateral.try_sub(usdc_debt)?.try_div(sol_debt.try_sub(sol_collateral)?, Rounding::Floor)?
+    } else {
+        return Ok(0);
+    };
+    Ok(liquidation_price.try_to_price(PRICE_EXPONENT, Rounding::Floor)?)
+}
+
+// Whether `mint` is the USDC side of the user's position, rejecting any mint that is neither side
+pub fn position_is_usdc(user: &User, mint: Pubkey) -> Result<bool> {
+    if mint == user.usdc_address {
+        return Ok(true);
+    }
+    require_keys_eq!(mint, user.sol_address, ErrorCode::UnsupportedPositionMint);
+    Ok(false)
+}
+
+// Like position_is_usdc, but the first non-USDC mint a position is opened in becomes its SOL side.
+// Every later deposit, borrow and liquidation on that side uses the same bank.
+pub fn pin_position_mint(user: &mut User, mint: Pubkey) -> Result<bool> {
+    if mint != user.usdc_address && user.sol_address == Pubkey::default() {
+        user.sol_address = mint;
+    }
+    position_is_usdc(user, mint)
+}
+
+// Token amount represented by `shares` at the bank's current exchange rate. Round down when
+// paying out and up when charging, so rounding always favours the bank.
+#[inline(always)]
+pub fn amount_from_shares(shares: u64, total: u64, total_shares: u64, rounding: Rounding) -> Result<u64> {
+    if total_shares == 0 {
+        return Ok(shares);
+    }
+    let amount = mul_div(shares as u128, total as u128, total_shares as u128, rounding)?;
+    Ok(u64::try_from(amount).map_err(|_| MathError::Overflow)?)
+}
+
+// Shares equivalent to `amount` at the bank's current exchange rate. Round down when crediting
+// shares and up when removing them, so rounding always favours the bank.
+#[inline(always)]
+pub fn shares_from_amount(amount: u64, total: u64, total_shares: u64, rounding: Rounding) -> Result<u64> {
+    if total == 0 || total_shares == 0 {
+        return Ok(amount);
+    }
+    let shares = mul_div(amount as u128, total_shares as u128, total as u128, rounding)?;
+    Ok(u64::try_from(shares).map_err(|_| MathError::Overflow)?)
+}
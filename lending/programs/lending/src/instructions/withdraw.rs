@@ -1,9 +1,12 @@
 use anchor_lang::prelude::*;
 use anchor_spl::associated_token::AssociatedToken;
 use anchor_spl::token_interface::{ self, Mint, TokenAccount, TokenInterface, TransferChecked };
+use decimal::Rounding;
 use crate::state::*;
 use crate::error::ErrorCode;
 use super::interest::accrue_interest;
+use super::rewards::checkpoint_rewards;
+use super::utils::{position_is_usdc, shares_from_amount};
 
 #[derive(Accounts)]
 pub struct Withdraw<'info> {
@@ -16,6 +19,13 @@ pub struct Withdraw<'info> {
         bump,
     )]  
     pub bank: Account<'info, Bank>,
+    // Required when the bank references an interest rate model
+    pub interest_rate_model: Option<Account<'info, InterestRateModel>>,
+    // Required while the bank's deposit reward pool is emitting, user_rewards once the user opted in
+    #[account(mut)]
+    pub reward_pool: Option<Box<Account<'info, RewardPool>>>,
+    #[account(mut)]
+    pub user_rewards: Option<Box<Account<'info, UserRewards>>>,
     #[account(
         mut, 
         seeds = [b"treasury", mint.key().as_ref()],
@@ -42,24 +52,17 @@ pub struct Withdraw<'info> {
 }
 
 pub fn process_withdraw(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
-    accrue_interest(&mut ctx.accounts.bank)?;
-    let user = &mut ctx.accounts.user_account;
+    let accounts = &mut *ctx.accounts;
+    record_withdraw(&mut WithdrawPosition {
+        mint: accounts.mint.key(),
+        bank: &mut accounts.bank,
+        interest_rate_model: accounts.interest_rate_model.as_ref(),
+        reward_pool: accounts.reward_pool.as_deref_mut(),
+        user_rewards: accounts.user_rewards.as_deref_mut(),
+        user_account: &mut accounts.user_account,
+    }, amount)?;
 
     let mint_key = ctx.accounts.mint.key();
-    let user_usdc = user.usdc_address;
-
-    let deposited_value; 
-
-    if mint_key == user_usdc {
-        deposited_value = user.deposited_usdc;
-    } else {
-        deposited_value = user.deposited_sol;
-    }
-
-    if amount > deposited_value {
-        return Err(ErrorCode::InsufficientFunds.into());
-    }
-
     let transfer_cpi_accounts = TransferChecked {
         from: ctx.accounts.bank_token_account.to_account_info(),
         mint: ctx.accounts.mint.to_account_info(),
@@ -67,7 +70,6 @@ pub fn process_withdraw(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
         authority: ctx.accounts.bank_token_account.to_account_info(),
     };
 
-    let mint_key = mint_key;
     let signer_seeds: &[&[&[u8]]] = &[
         &[
             b"treasury",
@@ -81,20 +83,57 @@ pub fn process_withdraw(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
 
     token_interface::transfer_checked(cpi_ctx, amount, decimals)?;
 
-    let bank = &mut ctx.accounts.bank;
-    let shares_to_remove = (amount as u128)
-        .saturating_mul(bank.total_deposit_shares as u128)
-        .checked_div(bank.total_deposits as u128)
-        .unwrap_or(0) as u64;
+    Ok(())    
+}
+
+// The accounts a withdrawal updates, shared by the token and native SOL withdrawals
+pub struct WithdrawPosition<'a, 'info> {
+    pub mint: Pubkey,
+    pub bank: &'a mut Account<'info, Bank>,
+    pub interest_rate_model: Option<&'a Account<'info, InterestRateModel>>,
+    pub reward_pool: Option<&'a mut Account<'info, RewardPool>>,
+    pub user_rewards: Option<&'a mut Account<'info, UserRewards>>,
+    pub user_account: &'a mut Account<'info, User>,
+}
+
+// Debit `amount` from the bank and the signer's position, the caller pays it out of the treasury
+pub fn record_withdraw(accounts: &mut WithdrawPosition, amount: u64) -> Result<()> {
+    accrue_interest(accounts.bank, accounts.interest_rate_model)?;
+    let user = &mut accounts.user_account;
+
+    let is_usdc = position_is_usdc(user, accounts.mint)?;
+
+    let deposited_value = if is_usdc {
+        user.deposited_usdc
+    } else {
+        user.deposited_sol
+    };
+
+    if amount > deposited_value {
+        return Err(ErrorCode::InsufficientFunds.into());
+    }
+
+    let bank = &mut accounts.bank;
+    let shares_to_remove = shares_from_amount(amount, bank.total_deposits, bank.total_deposit_shares, Rounding::Ceil)?;
 
-    if mint_key == user_usdc {
-        user.deposited_usdc = user.deposited_usdc.saturating_sub(shares_to_remove);
+    if is_usdc {
+        user.deposited_usdc -= amount;
+        user.deposited_usdc_shares = user.deposited_usdc_shares.saturating_sub(shares_to_remove);
     } else {
-        user.deposited_sol = user.deposited_sol.saturating_sub(shares_to_remove);
+        user.deposited_sol -= amount;
+        user.deposited_sol_shares = user.deposited_sol_shares.saturating_sub(shares_to_remove);
     }
 
     bank.total_deposits = bank.total_deposits.saturating_sub(amount);
     bank.total_deposit_shares = bank.total_deposit_shares.saturating_sub(shares_to_remove);
+
+    checkpoint_rewards(
+        accounts.bank,
+        accounts.user_account,
+        RewardSide::Deposit,
+        accounts.reward_pool.as_deref_mut(),
+        accounts.user_rewards.as_deref_mut(),
+    )?;
     
-    Ok(())    
-}
\ No newline at end of file
+    Ok(())
+}
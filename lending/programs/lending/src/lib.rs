@@ -1,5 +1,6 @@
 use anchor_lang::prelude::*;
 use instructions::*;
+use state::{AlertSeverity, RateModel, RewardSide, ValuationMode};
 
 mod state;
 mod instructions;
@@ -17,6 +18,44 @@ pub mod lending_protocol {
         process_init_bank(ctx, liquidation_threshold, max_ltv)
     }
 
+    pub fn update_bank_risk_params(ctx: Context<UpdateBank>, liquidation_threshold: u64, max_ltv: u64) -> Result<()> {
+        process_update_bank_risk_params(ctx, liquidation_threshold, max_ltv)
+    }
+
+    pub fn update_bank_swap_program(ctx: Context<UpdateBank>, swap_program: Pubkey) -> Result<()> {
+        process_update_bank_swap_program(ctx, swap_program)
+    }
+
+    // Interest rate models
+    pub fn init_interest_rate_model(ctx: Context<InitInterestRateModel>, model: RateModel) -> Result<()> {
+        process_init_interest_rate_model(ctx, model)
+    }
+
+    pub fn set_bank_rate_model(ctx: Context<SetBankRateModel>) -> Result<()> {
+        process_set_bank_rate_model(ctx)
+    }
+
+    pub fn update_bank_fees(ctx: Context<UpdateBank>, borrow_fee_bps: u64, referral_fee_bps: u64) -> Result<()> {
+        process_update_bank_fees(ctx, borrow_fee_bps, referral_fee_bps)
+    }
+
+    pub fn update_bank_valuation_modes(
+        ctx: Context<UpdateBank>,
+        borrow_valuation_mode: ValuationMode,
+        liquidation_valuation_mode: ValuationMode,
+    ) -> Result<()> {
+        process_update_bank_valuation_modes(ctx, borrow_valuation_mode, liquidation_valuation_mode)
+    }
+
+    pub fn update_bank_oracle_policy(
+        ctx: Context<UpdateBank>,
+        max_price_age: u64,
+        max_confidence_bps: u64,
+        max_ema_deviation_bps: u64,
+    ) -> Result<()> {
+        process_update_bank_oracle_policy(ctx, max_price_age, max_confidence_bps, max_ema_deviation_bps)
+    }
+
     pub fn init_user(ctx: Context<InitUser>, usdc_address: Pubkey) -> Result<()> {
         process_init_user(ctx, usdc_address)
     }
@@ -29,10 +68,40 @@ pub mod lending_protocol {
         process_withdraw(ctx, amount)
     }
 
+    // Deposit receipts
+    pub fn init_receipt_mint(ctx: Context<InitReceiptMint>) -> Result<()> {
+        process_init_receipt_mint(ctx)
+    }
+
+    pub fn deposit_for_receipt(ctx: Context<DepositForReceipt>, amount: u64) -> Result<()> {
+        process_deposit_for_receipt(ctx, amount)
+    }
+
+    pub fn withdraw_receipt(ctx: Context<WithdrawReceipt>, receipt_amount: u64) -> Result<()> {
+        process_withdraw_receipt(ctx, receipt_amount)
+    }
+
+    pub fn deposit_receipt_as_collateral(ctx: Context<DepositReceiptAsCollateral>, receipt_amount: u64) -> Result<()> {
+        process_deposit_receipt_as_collateral(ctx, receipt_amount)
+    }
+
     pub fn borrow(ctx: Context<Borrow>, amount: u64) -> Result<()> {
         process_borrow(ctx, amount)
     }
 
+    // Native SOL variants for the wSOL bank
+    pub fn deposit_native(ctx: Context<DepositNative>, amount: u64) -> Result<()> {
+        process_deposit_native(ctx, amount)
+    }
+
+    pub fn withdraw_native(ctx: Context<WithdrawNative>, amount: u64) -> Result<()> {
+        process_withdraw_native(ctx, amount)
+    }
+
+    pub fn borrow_native(ctx: Context<BorrowNative>, amount: u64) -> Result<()> {
+        process_borrow_native(ctx, amount)
+    }
+
     pub fn repay(ctx: Context<Repay>, amount: u64) -> Result<()> {
         process_repay(ctx, amount)
     }
@@ -41,6 +110,98 @@ pub mod lending_protocol {
         process_liquidate(ctx)
     }
 
+    // Referral fees
+    pub fn init_referrer_fees(ctx: Context<InitReferrerFees>) -> Result<()> {
+        process_init_referrer_fees(ctx)
+    }
+
+    pub fn claim_referrer_fees(ctx: Context<ClaimReferrerFees>) -> Result<()> {
+        process_claim_referrer_fees(ctx)
+    }
+
+    // Liquidity mining
+    pub fn create_reward_pool(
+        ctx: Context<CreateRewardPool>,
+        side: RewardSide,
+        emissions_per_second: u64,
+        start_ts: i64,
+        end_ts: i64
+    ) -> Result<()> {
+        process_create_reward_pool(ctx, side, emissions_per_second, start_ts, end_ts)
+    }
+
+    pub fn init_user_rewards(ctx: Context<InitUserRewards>) -> Result<()> {
+        process_init_user_rewards(ctx)
+    }
+
+    pub fn claim_rewards(ctx: Context<ClaimRewards>) -> Result<()> {
+        process_claim_rewards(ctx)
+    }
+
+    // Fixed-rate, fixed-term loans
+    pub fn borrow_fixed(
+        ctx: Context<BorrowFixed>,
+        loan_id: u64,
+        amount: u64,
+        term_seconds: i64,
+        max_rate_bps: u64
+    ) -> Result<()> {
+        process_borrow_fixed(ctx, loan_id, amount, term_seconds, max_rate_bps)
+    }
+
+    pub fn repay_fixed(ctx: Context<RepayFixed>) -> Result<()> {
+        process_repay_fixed(ctx)
+    }
+
+    pub fn liquidate_fixed_loan(ctx: Context<LiquidateFixedLoan>) -> Result<()> {
+        process_liquidate_fixed_loan(ctx)
+    }
+
+    pub fn get_user_position(ctx: Context<GetUserPosition>) -> Result<UserPosition> {
+        process_get_user_position(ctx)
+    }
+
+    pub fn get_bank_state(ctx: Context<GetBankState>) -> Result<BankState> {
+        process_get_bank_state(ctx)
+    }
+
+    pub fn refresh_bank(ctx: Context<RefreshBank>) -> Result<()> {
+        process_refresh_bank(ctx)
+    }
+
+    // Fallback oracle sources
+    pub fn init_oracle_config(ctx: Context<InitOracleConfig>) -> Result<()> {
+        process_init_oracle_config(ctx)
+    }
+
+    pub fn update_oracle_config(
+        ctx: Context<UpdateOracleConfig>,
+        guardian: Pubkey,
+        secondary_oracle_program: Pubkey,
+    ) -> Result<()> {
+        process_update_oracle_config(ctx, guardian, secondary_oracle_program)
+    }
+
+    pub fn set_manual_price(
+        ctx: Context<SetManualPrice>,
+        feed_id: [u8; 32],
+        price: i64,
+        conf: u64,
+        exponent: i32,
+        valid_for: i64,
+    ) -> Result<()> {
+        process_set_manual_price(ctx, feed_id, price, conf, exponent, valid_for)
+    }
+
+    // Treasury reconciliation
+    pub fn verify_bank(ctx: Context<VerifyBank>) -> Result<()> {
+        process_verify_bank(ctx)
+    }
+
+    pub fn skim_excess(ctx: Context<SkimExcess>) -> Result<()> {
+        process_skim_excess(ctx)
+    }
+
     // Health monitoring
     pub fn enable_health_monitoring(ctx: Context<EnableHealthMonitoring>) -> Result<()> {
         process_enable_health_monitoring(ctx)
@@ -54,6 +215,15 @@ pub mod lending_protocol {
         process_update_health_threshold(ctx, new_threshold, alert_frequency_hours)
     }
 
+    pub fn update_alert_tier(
+        ctx: Context<UpdateHealthThreshold>,
+        severity: AlertSeverity,
+        new_threshold: u64,
+        alert_frequency_hours: u8
+    ) -> Result<()> {
+        process_update_alert_tier(ctx, severity, new_threshold, alert_frequency_hours)
+    }
+
     pub fn check_health_factor(ctx: Context<CheckHealthFactor>) -> Result<()> {
         process_check_health_factor(ctx)
     }
@@ -61,4 +231,28 @@ pub mod lending_protocol {
     pub fn create_health_snapshot(ctx: Context<CreateHealthSnapshot>) -> Result<()> {
         process_create_health_snapshot(ctx)
     }
+
+    // Automatic deleveraging
+    pub fn set_deleverage_order(
+        ctx: Context<SetDeleverageOrder>,
+        trigger_health_factor: u64,
+        target_health_factor: u64,
+        max_slippage_bps: u64,
+        keeper_fee_bps: u64
+    ) -> Result<()> {
+        process_set_deleverage_order(ctx, trigger_health_factor, target_health_factor, max_slippage_bps, keeper_fee_bps)
+    }
+
+    pub fn cancel_deleverage_order(ctx: Context<CancelDeleverageOrder>) -> Result<()> {
+        process_cancel_deleverage_order(ctx)
+    }
+
+    pub fn auto_deleverage<'info>(
+        ctx: Context<'_, '_, 'info, 'info, AutoDeleverage<'info>>,
+        collateral_amount: u64,
+        fallback_source_count: u8,
+        swap_data: Vec<u8>
+    ) -> Result<()> {
+        process_auto_deleverage(ctx, collateral_amount, fallback_source_count, swap_data)
+    }
 }
\ No newline at end of file
@@ -9,10 +9,10 @@ pub struct Bank {
     pub total_deposit_shares: u64,
     pub total_borrowed: u64,
     pub total_borrowed_shares: u64,
-    pub liquidation_threshold: u64,
+    pub liquidation_threshold: u64,     // bps of collateral value, liquidatable above it
     pub liquidation_bonus: u64,
     pub liquidation_close_factor: u64,
-    pub max_ltv: u64,
+    pub max_ltv: u64,                   // bps of collateral value, below the liquidation threshold
     pub last_updated: i64,
     pub interest_rate: u64,
     
@@ -22,6 +22,58 @@ pub struct Bank {
     pub slope2_bps: u64,
     pub optimal_utilization_bps: u64,
     pub last_accrual_ts: i64,
+
+    // Swap program keepers may route this bank's collateral through when deleveraging
+    pub swap_program: Pubkey,
+
+    // Rate model account used for accrual (default = kinked curve from the fields above)
+    pub interest_rate_model: Pubkey,
+
+    // Adaptive rate model state: borrow APR at optimal utilization (0 = use the model's initial value)
+    pub rate_at_target_bps: u64,
+
+    // Principal lent out through fixed-rate loans, which do not accrue variable interest
+    pub total_fixed_borrowed: u64,
+
+    // Origination fee added to debt on borrow, and the share of it (bps of the fee) paid to referrers
+    pub borrow_fee_bps: u64,
+    pub referral_fee_bps: u64,
+    // Protocol's share of borrow fees, held in the treasury
+    pub total_reserves: u64,
+    // Referral fees accrued but not yet claimed, held in the treasury
+    pub total_referral_fees: u64,
+
+    // Active liquidity mining pools for depositors and borrowers (default = none), and when their
+    // emissions end, after which share changes no longer need to checkpoint them
+    pub deposit_reward_pool: Pubkey,
+    pub borrow_reward_pool: Pubkey,
+    pub deposit_reward_end_ts: i64,
+    pub borrow_reward_end_ts: i64,
+
+    // Token-2022 mint of deposit receipts, one token per deposit share (default = not enabled)
+    pub receipt_mint: Pubkey,
+    // Deposit shares held as receipts, which earn no liquidity mining rewards
+    pub total_receipt_shares: u64,
+
+    // Oracle price used to value positions for borrow limits and for liquidation eligibility
+    pub borrow_valuation_mode: ValuationMode,
+    pub liquidation_valuation_mode: ValuationMode,
+
+    // Oracle acceptance rules for this bank's asset: max price age (seconds), widest confidence
+    // interval and largest spot deviation from the EMA (bps). A max age of 0 uses the feed's default.
+    pub oracle_max_price_age: u64,
+    pub oracle_max_confidence_bps: u64,
+    pub oracle_max_ema_deviation_bps: u64,
+}
+
+// Which Pyth price values positions: spot, EMA, or the more conservative of the two
+// (lower for collateral, higher for debt). All modes apply the confidence interval.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, Default, InitSpace)]
+pub enum ValuationMode {
+    #[default]
+    Spot,
+    Ema,
+    Conservative,
 }
 
 #[account]
@@ -47,6 +99,39 @@ pub struct User {
     pub is_monitoring_enabled: bool,
     pub last_alert_sent: i64,       
     pub alert_frequency_hours: u8, 
+
+    // Additional alert tiers (bps, 0 = disabled), each with its own cooldown
+    pub critical_alert_threshold: u64,
+    pub last_critical_alert_sent: i64,
+    pub critical_alert_frequency_hours: u8,
+    pub imminent_alert_threshold: u64,
+    pub last_imminent_alert_sent: i64,
+    pub imminent_alert_frequency_hours: u8,
+
+    // Outstanding fixed-rate loan principal, counted as debt alongside variable borrows
+    pub fixed_borrowed_sol: u64,
+    pub fixed_borrowed_usdc: u64,
+
+    // Mint of the SOL side of the position, pinned by the first non-USDC deposit or borrow. Other
+    // non-USDC mints are rejected, so liquidations always find the collateral in this bank.
+    pub sol_address: Pubkey,
+
+    // Reward pools the user opted into for each position and side (see rewards::reward_slot)
+    pub reward_pools: [Pubkey; 4],
+
+    // Referral fees on the SOL and USDC debt that repayments have not realized yet, and the
+    // referrer fee account they are owed to
+    pub referrer_fees_sol: Pubkey,
+    pub pending_referral_fee_sol: u64,
+    pub referrer_fees_usdc: Pubkey,
+    pub pending_referral_fee_usdc: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, InitSpace)]
+pub enum AlertSeverity {
+    Warning,
+    Critical,
+    LiquidationImminent,
 }
 
 #[account]
@@ -60,3 +145,144 @@ pub struct HealthSnapshot {
     pub sol_price: u64,
     pub usdc_price: u64,
 }
+
+#[account]
+#[derive(InitSpace)]
+pub struct DeleverageOrder {
+    pub owner: Pubkey,
+    pub trigger_health_factor: u64, // bps, keepers may deleverage once health falls below this
+    pub target_health_factor: u64,  // bps, health the position must be restored to
+    pub max_slippage_bps: u64,      // max swap shortfall against the oracle price
+    pub keeper_fee_bps: u64,        // paid to the keeper out of the collateral sold
+    pub last_executed: i64,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct InterestRateModel {
+    pub authority: Pubkey,
+    pub model: RateModel,
+}
+
+// Borrow APR as a function of utilization, all values in bps
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, InitSpace)]
+pub enum RateModel {
+    Fixed {
+        rate_bps: u64,
+    },
+    // base + slope * u
+    Linear {
+        base_rate_bps: u64,
+        slope_bps: u64,
+    },
+    // base, then slope1 up to kink1, slope2 up to kink2 and slope3 above it
+    TwoKink {
+        base_rate_bps: u64,
+        kink1_utilization_bps: u64,
+        slope1_bps: u64,
+        kink2_utilization_bps: u64,
+        slope2_bps: u64,
+        slope3_bps: u64,
+    },
+    // Linear interpolation between the first `len` points, from 0 to 100% utilization
+    Piecewise {
+        points: [RatePoint; 8],
+        len: u8,
+    },
+    // Curve around the bank's stored rate at target, which drifts towards the rate that keeps
+    // utilization at optimal_utilization_bps. Speed is the relative change per second at 100%
+    // error, scaled by ADAPTIVE_SPEED_SCALE.
+    Adaptive {
+        initial_rate_at_target_bps: u64,
+        min_rate_at_target_bps: u64,
+        max_rate_at_target_bps: u64,
+        adjustment_speed: u64,
+    },
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, Default, InitSpace)]
+pub struct RatePoint {
+    pub utilization_bps: u64,
+    pub rate_bps: u64,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct OracleConfig {
+    pub authority: Pubkey,
+    pub guardian: Pubkey,                 // may push short-lived manual prices
+    pub secondary_oracle_program: Pubkey, // owner of secondary price accounts (default = none)
+    pub bump: u8,
+}
+
+// Guardian-pushed price for a feed, the last fallback after Pyth and the secondary oracle
+#[account]
+#[derive(InitSpace)]
+pub struct ManualPrice {
+    pub feed_id: [u8; 32],
+    pub price: i64,
+    pub conf: u64,
+    pub exponent: i32,
+    pub publish_time: i64,
+    pub expires_at: i64,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct FixedLoan {
+    pub owner: Pubkey,
+    pub mint_address: Pubkey,
+    pub loan_id: u64,
+    pub principal: u64,
+    pub rate_bps: u64,       // APR quoted at origination, simple interest
+    pub start_ts: i64,
+    pub maturity_ts: i64,    // liquidatable once FIXED_LOAN_GRACE_PERIOD past this
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct ReferrerFees {
+    pub referrer: Pubkey,
+    pub mint_address: Pubkey,
+    pub accrued: u64,   // realized by borrowers' repayments, claimable
+    pub pending: u64,   // on debt the referred borrowers have not repaid yet
+    pub total_claimed: u64,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, InitSpace)]
+pub enum RewardSide {
+    Deposit,
+    Borrow,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct RewardPool {
+    pub bank: Pubkey,
+    pub reward_mint: Pubkey,
+    pub side: RewardSide,
+    pub emissions_per_second: u64,
+    pub start_ts: i64,
+    pub end_ts: i64,
+    pub last_update_ts: i64,
+    pub reward_index: u128, // rewards per bank share, as a WAD-scaled decimal
+    pub total_shares: u64,  // bank shares on this side at the last checkpoint, earning until the next
+    pub bump: u8,
+    pub vault_bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct UserRewards {
+    pub owner: Pubkey,
+    pub reward_pool: Pubkey,
+    pub reward_index: u128, // pool index at the last checkpoint
+    pub shares: u64,        // user's shares at the last checkpoint, earning until the next
+    pub accrued: u64,
+    pub total_claimed: u64,
+    pub bump: u8,
+}
@@ -0,0 +1,182 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{ self, Approve, Mint, TokenAccount, TokenInterface, TransferChecked };
+
+declare_id!("7e8gthCbjmQzScyAhwmd44MYei2Wo2B5hLUCUwsGPPcd");
+
+// Minimal constant-rate AMM standing in for a real swap program in lending tests.
+// amount_out = amount_in * rate_numerator / rate_denominator (in base units).
+#[program]
+pub mod mock_amm {
+    use super::*;
+
+    pub fn init_pool(ctx: Context<InitPool>, rate_numerator: u64, rate_denominator: u64) -> Result<()> {
+        require!(rate_denominator > 0, MockAmmError::InvalidRate);
+        let pool = &mut ctx.accounts.pool;
+        pool.mint_in = ctx.accounts.mint_in.key();
+        pool.mint_out = ctx.accounts.mint_out.key();
+        pool.rate_numerator = rate_numerator;
+        pool.rate_denominator = rate_denominator;
+        pool.bump = ctx.bumps.pool;
+        Ok(())
+    }
+
+    pub fn set_rate(ctx: Context<SetRate>, rate_numerator: u64, rate_denominator: u64) -> Result<()> {
+        require!(rate_denominator > 0, MockAmmError::InvalidRate);
+        let pool = &mut ctx.accounts.pool;
+        pool.rate_numerator = rate_numerator;
+        pool.rate_denominator = rate_denominator;
+        Ok(())
+    }
+
+    pub fn swap(ctx: Context<Swap>, amount_in: u64, min_amount_out: u64) -> Result<()> {
+        let pool = &ctx.accounts.pool;
+        let amount_out = ((amount_in as u128)
+            .saturating_mul(pool.rate_numerator as u128)
+            / pool.rate_denominator as u128) as u64;
+        require!(amount_out >= min_amount_out, MockAmmError::SlippageExceeded);
+
+        token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.source.to_account_info(),
+                    mint: ctx.accounts.mint_in.to_account_info(),
+                    to: ctx.accounts.vault_in.to_account_info(),
+                    authority: ctx.accounts.authority.to_account_info(),
+                },
+            ),
+            amount_in,
+            ctx.accounts.mint_in.decimals,
+        )?;
+
+        let mint_in = pool.mint_in;
+        let mint_out = pool.mint_out;
+        let signer_seeds: &[&[&[u8]]] = &[&[b"pool", mint_in.as_ref(), mint_out.as_ref(), &[pool.bump]]];
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.vault_out.to_account_info(),
+                    mint: ctx.accounts.mint_out.to_account_info(),
+                    to: ctx.accounts.destination.to_account_info(),
+                    authority: ctx.accounts.pool.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            amount_out,
+            ctx.accounts.mint_out.decimals,
+        )?;
+
+        Ok(())
+    }
+
+    // Misbehaving route: swaps, but first approves the pool as a delegate over the source account
+    pub fn swap_and_approve(ctx: Context<Swap>, amount_in: u64, min_amount_out: u64) -> Result<()> {
+        token_interface::approve(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Approve {
+                    to: ctx.accounts.source.to_account_info(),
+                    delegate: ctx.accounts.pool.to_account_info(),
+                    authority: ctx.accounts.authority.to_account_info(),
+                },
+            ),
+            amount_in,
+        )?;
+        swap(ctx, amount_in, min_amount_out)
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Pool {
+    pub mint_in: Pubkey,
+    pub mint_out: Pubkey,
+    pub rate_numerator: u64,
+    pub rate_denominator: u64,
+    pub bump: u8,
+}
+
+#[derive(Accounts)]
+pub struct InitPool<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub mint_in: InterfaceAccount<'info, Mint>,
+    pub mint_out: InterfaceAccount<'info, Mint>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Pool::INIT_SPACE,
+        seeds = [b"pool", mint_in.key().as_ref(), mint_out.key().as_ref()],
+        bump,
+    )]
+    pub pool: Account<'info, Pool>,
+    #[account(
+        init,
+        payer = payer,
+        token::mint = mint_in,
+        token::authority = pool,
+        token::token_program = token_program,
+        seeds = [b"vault", pool.key().as_ref(), mint_in.key().as_ref()],
+        bump,
+    )]
+    pub vault_in: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = payer,
+        token::mint = mint_out,
+        token::authority = pool,
+        token::token_program = token_program,
+        seeds = [b"vault", pool.key().as_ref(), mint_out.key().as_ref()],
+        bump,
+    )]
+    pub vault_out: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetRate<'info> {
+    pub signer: Signer<'info>,
+    #[account(mut)]
+    pub pool: Account<'info, Pool>,
+}
+
+#[derive(Accounts)]
+pub struct Swap<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [b"pool", pool.mint_in.as_ref(), pool.mint_out.as_ref()],
+        bump = pool.bump,
+        has_one = mint_in,
+        has_one = mint_out,
+    )]
+    pub pool: Account<'info, Pool>,
+    pub mint_in: InterfaceAccount<'info, Mint>,
+    pub mint_out: InterfaceAccount<'info, Mint>,
+    #[account(mut, token::mint = mint_in)]
+    pub source: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, token::mint = mint_out)]
+    pub destination: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"vault", pool.key().as_ref(), mint_in.key().as_ref()],
+        bump,
+    )]
+    pub vault_in: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"vault", pool.key().as_ref(), mint_out.key().as_ref()],
+        bump,
+    )]
+    pub vault_out: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[error_code]
+pub enum MockAmmError {
+    #[msg("Rate denominator must be non-zero.")]
+    InvalidRate,
+    #[msg("Swap output is below the minimum amount out.")]
+    SlippageExceeded,
+}
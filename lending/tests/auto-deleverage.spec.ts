@@ -0,0 +1,231 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { LendingProtocol } from "../target/types/lending_protocol";
+import { MockAmm } from "../target/types/mock_amm";
+import { expect } from "chai";
+import { PublicKey, Keypair, SystemProgram } from "@solana/web3.js";
+import {
+  createMint,
+  mintTo,
+  TOKEN_PROGRAM_ID,
+  getAssociatedTokenAddress,
+  ASSOCIATED_TOKEN_PROGRAM_ID
+} from "@solana/spl-token";
+import { BankrunConnection } from "./bankrun-utils/bankrunConnection";
+
+describe("Automatic Deleveraging", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.LendingProtocol as Program<LendingProtocol>;
+  const amm = anchor.workspace.MockAmm as Program<MockAmm>;
+  const connection = new BankrunConnection();
+
+  let solMint: PublicKey;
+  let usdcMint: PublicKey;
+  let solBank: PublicKey;
+  let usdcBank: PublicKey;
+  let solBankTokenAccount: PublicKey;
+  let usdcBankTokenAccount: PublicKey;
+  let userAccount: PublicKey;
+  let deleverageOrder: PublicKey;
+  let pool: PublicKey;
+  let vaultIn: PublicKey;
+  let vaultOut: PublicKey;
+  let priceUpdate: PublicKey;
+
+  const user = Keypair.generate();
+  const keeper = Keypair.generate();
+  const authority = Keypair.generate();
+
+  before(async () => {
+    // Same decimals on both mints so oracle conversion and health math agree
+    solMint = await createMint(connection, authority, authority.publicKey, null, 9);
+    usdcMint = await createMint(connection, authority, authority.publicKey, null, 9);
+    priceUpdate = Keypair.generate().publicKey;
+
+    [solBank] = PublicKey.findProgramAddressSync([solMint.toBuffer()], program.programId);
+    [usdcBank] = PublicKey.findProgramAddressSync([usdcMint.toBuffer()], program.programId);
+    [solBankTokenAccount] = PublicKey.findProgramAddressSync([Buffer.from("treasury"), solMint.toBuffer()], program.programId);
+    [usdcBankTokenAccount] = PublicKey.findProgramAddressSync([Buffer.from("treasury"), usdcMint.toBuffer()], program.programId);
+    [userAccount] = PublicKey.findProgramAddressSync([user.publicKey.toBuffer()], program.programId);
+    [deleverageOrder] = PublicKey.findProgramAddressSync([Buffer.from("deleverage"), user.publicKey.toBuffer()], program.programId);
+    [pool] = PublicKey.findProgramAddressSync([Buffer.from("pool"), solMint.toBuffer(), usdcMint.toBuffer()], amm.programId);
+    [vaultIn] = PublicKey.findProgramAddressSync([Buffer.from("vault"), pool.toBuffer(), solMint.toBuffer()], amm.programId);
+    [vaultOut] = PublicKey.findProgramAddressSync([Buffer.from("vault"), pool.toBuffer(), usdcMint.toBuffer()], amm.programId);
+
+    for (const mint of [solMint, usdcMint]) {
+      await program.methods.initBank(new anchor.BN(8000), new anchor.BN(7500))
+        .accounts({ signer: authority.publicKey, mint, tokenProgram: TOKEN_PROGRAM_ID, systemProgram: SystemProgram.programId })
+        .signers([authority]).rpc();
+    }
+
+    // Whitelist the mock AMM for selling SOL collateral
+    await program.methods.updateBankSwapProgram(amm.programId)
+      .accounts({ signer: authority.publicKey, bank: solBank })
+      .signers([authority]).rpc();
+
+    // Each SOL base unit swaps for 150 USDC base units
+    await amm.methods.initPool(new anchor.BN(150), new anchor.BN(1))
+      .accounts({ payer: authority.publicKey, mintIn: solMint, mintOut: usdcMint, tokenProgram: TOKEN_PROGRAM_ID })
+      .signers([authority]).rpc();
+    await mintTo(connection, authority, usdcMint, vaultOut, authority, 1_000_000 * 1e9);
+
+    await program.methods.initUser(usdcMint)
+      .accounts({ signer: user.publicKey, userAccount, systemProgram: SystemProgram.programId })
+      .signers([user]).rpc();
+
+    await connection.requestAirdrop(user.publicKey, 10 * anchor.web3.LAMPORTS_PER_SOL);
+    await connection.requestAirdrop(keeper.publicKey, 10 * anchor.web3.LAMPORTS_PER_SOL);
+    await mintTo(connection, authority, solMint, await getAssociatedTokenAddress(solMint, user.publicKey), authority, 1_000 * 1e9);
+    await mintTo(connection, authority, usdcMint, usdcBankTokenAccount, authority, 100_000 * 1e9);
+  });
+
+  it("Should require health monitoring before accepting an order", async () => {
+    try {
+      await program.methods.setDeleverageOrder(new anchor.BN(12000), new anchor.BN(14000), new anchor.BN(100), new anchor.BN(50))
+        .accounts({ signer: user.publicKey, systemProgram: SystemProgram.programId })
+        .signers([user]).rpc();
+      expect.fail("Should have rejected order without monitoring");
+    } catch (error) {
+      expect(error.message).to.include("MonitoringDisabled");
+    }
+
+    await program.methods.enableHealthMonitoring()
+      .accounts({ signer: user.publicKey, userAccount, systemProgram: SystemProgram.programId })
+      .signers([user]).rpc();
+  });
+
+  it("Should reject orders whose trigger is above the warning alert", async () => {
+    try {
+      await program.methods.setDeleverageOrder(new anchor.BN(16000), new anchor.BN(18000), new anchor.BN(100), new anchor.BN(50))
+        .accounts({ signer: user.publicKey, systemProgram: SystemProgram.programId })
+        .signers([user]).rpc();
+      expect.fail("Should have rejected trigger above alert threshold");
+    } catch (error) {
+      expect(error.message).to.include("InvalidDeleverageOrder");
+    }
+  });
+
+  it("Should set a deleverage order", async () => {
+    await program.methods.setDeleverageOrder(new anchor.BN(12000), new anchor.BN(14000), new anchor.BN(100), new anchor.BN(50))
+      .accounts({ signer: user.publicKey, systemProgram: SystemProgram.programId })
+      .signers([user]).rpc();
+
+    const order = await program.account.deleverageOrder.fetch(deleverageOrder);
+    expect(order.owner.toString()).to.equal(user.publicKey.toString());
+    expect(order.triggerHealthFactor.toNumber()).to.equal(12000);
+    expect(order.targetHealthFactor.toNumber()).to.equal(14000);
+  });
+
+  it("Should reject a swap route that leaves a delegate on the treasury", async () => {
+    const accounts = {
+      signer: user.publicKey, priceUpdate,
+      tokenProgram: TOKEN_PROGRAM_ID, associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID, systemProgram: SystemProgram.programId,
+    };
+    await program.methods.deposit(new anchor.BN(10 * 1e9))
+      .accounts({ ...accounts, mint: solMint, userTokenAccount: await getAssociatedTokenAddress(solMint, user.publicKey) })
+      .signers([user]).rpc();
+    await program.methods.borrow(new anchor.BN(1_300 * 1e9))
+      .accounts({ ...accounts, mint: usdcMint, userTokenAccount: await getAssociatedTokenAddress(usdcMint, user.publicKey) })
+      .signers([user]).rpc();
+
+    const sellAmount = new anchor.BN(2 * 1e9);
+    const swapIx = await amm.methods.swapAndApprove(sellAmount, new anchor.BN(0))
+      .accounts({
+        authority: solBankTokenAccount, pool, mintIn: solMint, mintOut: usdcMint,
+        source: solBankTokenAccount, destination: usdcBankTokenAccount, vaultIn, vaultOut, tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .instruction();
+
+    try {
+      await program.methods.autoDeleverage(sellAmount, 0, swapIx.data)
+        .accounts({
+          keeper: keeper.publicKey, deleverageOrder, priceUpdate,
+          collateralMint: solMint, debtMint: usdcMint, swapProgram: amm.programId, tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts(swapIx.keys.map((key) => ({ ...key, isSigner: false })))
+        .signers([keeper]).rpc();
+      expect.fail("Should have rejected a route that approves a delegate");
+    } catch (error) {
+      expect(error.message).to.include("SwapAlteredTreasury");
+    }
+  });
+
+  it("Should let a keeper deleverage an unhealthy position through the swap program", async () => {
+    const before = await program.account.user.fetch(userAccount);
+    expect(before.healthFactor.toNumber()).to.be.lessThan(12000);
+
+    const sellAmount = new anchor.BN(2 * 1e9);
+    const swapIx = await amm.methods.swap(sellAmount, new anchor.BN(0))
+      .accounts({
+        authority: solBankTokenAccount, pool, mintIn: solMint, mintOut: usdcMint,
+        source: solBankTokenAccount, destination: usdcBankTokenAccount, vaultIn, vaultOut, tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .instruction();
+
+    await program.methods.autoDeleverage(sellAmount, 0, swapIx.data)
+      .accounts({
+        keeper: keeper.publicKey,
+        deleverageOrder,
+        priceUpdate,
+        collateralMint: solMint,
+        debtMint: usdcMint,
+        swapProgram: amm.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .remainingAccounts(swapIx.keys.map((key) => ({ ...key, isSigner: false })))
+      .signers([keeper]).rpc();
+
+    const after = await program.account.user.fetch(userAccount);
+    expect(after.borrowedUsdc.toNumber()).to.be.lessThan(before.borrowedUsdc.toNumber());
+    expect(after.depositedSol.toNumber()).to.be.lessThan(before.depositedSol.toNumber());
+    expect(after.healthFactor.toNumber()).to.be.at.least(14000);
+  });
+
+  it("Should refuse to deleverage a healthy position", async () => {
+    try {
+      await program.methods.autoDeleverage(new anchor.BN(1e9), 0, Buffer.from([]))
+        .accounts({
+          keeper: keeper.publicKey, deleverageOrder, priceUpdate,
+          collateralMint: solMint, debtMint: usdcMint, swapProgram: amm.programId, tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([keeper]).rpc();
+      expect.fail("Should have rejected healthy position");
+    } catch (error) {
+      expect(error.message).to.include("DeleverageNotTriggered");
+    }
+  });
+
+  it("Should reject more fallback sources than remaining accounts", async () => {
+    try {
+      await program.methods.autoDeleverage(new anchor.BN(1e9), 1, Buffer.from([]))
+        .accounts({
+          keeper: keeper.publicKey, deleverageOrder, priceUpdate,
+          collateralMint: solMint, debtMint: usdcMint, swapProgram: amm.programId, tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([keeper]).rpc();
+      expect.fail("Should have rejected the fallback source count");
+    } catch (error) {
+      expect(error.message).to.include("InvalidFallbackSourceCount");
+    }
+  });
+
+  it("Should refuse to deleverage collateral whose bank has no swap program", async () => {
+    // The default key is the (executable) system program, which must not pass as a whitelisted route
+    await program.methods.updateBankSwapProgram(PublicKey.default)
+      .accounts({ signer: authority.publicKey, bank: solBank })
+      .signers([authority]).rpc();
+    try {
+      await program.methods.autoDeleverage(new anchor.BN(1e9), 0, Buffer.from([]))
+        .accounts({
+          keeper: keeper.publicKey, deleverageOrder, priceUpdate,
+          collateralMint: solMint, debtMint: usdcMint, swapProgram: SystemProgram.programId, tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([keeper]).rpc();
+      expect.fail("Should have rejected the unset swap program");
+    } catch (error) {
+      expect(error.message).to.include("SwapProgramNotAllowed");
+    }
+  });
+});
@@ -162,7 +162,7 @@ describe("Lending Smart Contract Tests - Enhanced Version", async () => {
 
   it("Test Init and Fund USDC Bank", async () => {
     const initUSDCBankTx = await program.methods
-      .initBank(new BN(80), new BN(75)) // 80% liquidation threshold, 75% max LTV
+      .initBank(new BN(8000), new BN(7500)) // 80% liquidation threshold, 75% max LTV
       .accounts({
         signer: signer.publicKey,
         mint: mintUSDC,
@@ -209,7 +209,7 @@ describe("Lending Smart Contract Tests - Enhanced Version", async () => {
 
   it("Test Init and Fund SOL Bank", async () => {
     const initSOLBankTx = await program.methods
-      .initBank(new BN(80), new BN(75)) // 80% liquidation threshold, 75% max LTV
+      .initBank(new BN(8000), new BN(7500)) // 80% liquidation threshold, 75% max LTV
       .accounts({
         signer: signer.publicKey,
         mint: mintSOL,
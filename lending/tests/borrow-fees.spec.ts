@@ -0,0 +1,115 @@
+import { describe, it } from "node:test";
+import { BN } from "@coral-xyz/anchor";
+import { TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { PublicKey, Keypair } from "@solana/web3.js";
+import { setupSolMarket } from "../bankrun-utils/solMarket";
+
+describe("Lending - Borrow and Referral Fees", async () => {
+  const {
+    context,
+    program,
+    signer,
+    mintSOL,
+    solBankAccount,
+    userAccount,
+    solUsdPriceFeedAccount,
+  } = await setupSolMarket();
+
+  it("borrow fee is added to debt and shared with the referrer once the borrower repays", async () => {
+    const referrer = Keypair.generate();
+    context.setAccount(referrer.publicKey, {
+      lamports: 1_000_000_000,
+      data: Buffer.alloc(0),
+      owner: PublicKey.default,
+      executable: false,
+    });
+    const [referrerFees] = PublicKey.findProgramAddressSync(
+      [Buffer.from("referrer_fees"), referrer.publicKey.toBuffer(), mintSOL.toBuffer()],
+      program.programId
+    );
+
+    // 1% origination fee, half of it to referrers
+    await program.methods
+      .updateBankFees(new BN(100), new BN(5_000))
+      .accounts({ signer: signer.publicKey, bank: solBankAccount })
+      .rpc({ commitment: "confirmed" });
+    await program.methods
+      .initReferrerFees()
+      .accounts({ signer: referrer.publicKey, mint: mintSOL })
+      .signers([referrer])
+      .rpc({ commitment: "confirmed" });
+
+    const userBefore = await program.account.user.fetch(userAccount);
+    const bankBefore = await program.account.bank.fetch(solBankAccount);
+    await program.methods
+      .borrow(new BN(1_000_000))
+      .accounts({
+        signer: signer.publicKey,
+        mint: mintSOL,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        priceUpdate: solUsdPriceFeedAccount,
+        referrerFees,
+      })
+      .rpc({ commitment: "confirmed" });
+
+    // The market's SOL mint is the user's placeholder USDC side
+    const userAfter = await program.account.user.fetch(userAccount);
+    if (userAfter.borrowedUsdc.sub(userBefore.borrowedUsdc).toNumber() < 1_010_000) {
+      throw new Error("debt should include the origination fee");
+    }
+    const fees = await program.account.referrerFees.fetch(referrerFees);
+    if (fees.pending.toNumber() !== 5_000 || fees.accrued.toNumber() !== 0) {
+      throw new Error("referrer's half of the fee should be pending until the borrower repays");
+    }
+    const bankAfter = await program.account.bank.fetch(solBankAccount);
+    if (bankAfter.totalReferralFees.sub(bankBefore.totalReferralFees).toNumber() !== 5_000) {
+      throw new Error("bank should hold the unclaimed referral fees");
+    }
+
+    const claim = () => program.methods
+      .claimReferrerFees()
+      .accounts({ signer: referrer.publicKey, mint: mintSOL, tokenProgram: TOKEN_PROGRAM_ID })
+      .signers([referrer])
+      .rpc({ commitment: "confirmed" });
+    let rejected = false;
+    try {
+      await claim();
+    } catch (e) {
+      rejected = String(e).includes("NothingToClaim");
+    }
+    if (!rejected) {
+      throw new Error("fees on unrepaid debt should not be claimable");
+    }
+
+    // Repaying without the referrer account would leave the fee unrealized
+    const repay = (withReferrer: boolean) => program.methods
+      .repay(userAfter.borrowedUsdc)
+      .accounts({
+        signer: signer.publicKey,
+        mint: mintSOL,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        referrerFees: withReferrer ? referrerFees : null,
+      })
+      .rpc({ commitment: "confirmed" });
+    rejected = false;
+    try {
+      await repay(false);
+    } catch (e) {
+      rejected = String(e).includes("InvalidReferrer");
+    }
+    if (!rejected) {
+      throw new Error("repaying debt with a pending referral fee should require the referrer account");
+    }
+    await repay(true);
+    const realized = await program.account.referrerFees.fetch(referrerFees);
+    if (realized.pending.toNumber() !== 0 || realized.accrued.toNumber() !== 5_000) {
+      throw new Error("repaying the debt should make the referral fee claimable");
+    }
+
+    await claim();
+    const claimed = await program.account.referrerFees.fetch(referrerFees);
+    if (claimed.accrued.toNumber() !== 0 || claimed.totalClaimed.toNumber() !== 5_000) {
+      throw new Error("claim should pay out the accrued referral fees");
+    }
+  });
+});
@@ -0,0 +1,256 @@
+import { describe, it } from "node:test";
+import { BN } from "@coral-xyz/anchor";
+import { TOKEN_PROGRAM_ID, getAssociatedTokenAddressSync } from "@solana/spl-token";
+import { createAccount, createMint, mintTo, getAccount } from "spl-token-bankrun";
+import { PublicKey, Keypair } from "@solana/web3.js";
+import {
+  setupSolMarket,
+  setPythPrice,
+  SOL_PRICE_FEED_ID,
+  USDC_PRICE_FEED_ID,
+} from "../bankrun-utils/solMarket";
+
+describe("Lending - Fixed-Rate Loans", async () => {
+  const {
+    context,
+    program,
+    banksClient,
+    bankrunContextWrapper,
+    signer,
+    mintSOL,
+    solBankAccount,
+    solTreasuryAccount,
+    solUsdPriceFeedAccount,
+  } = await setupSolMarket();
+
+  it("fixed-rate loan keeps its quoted rate and is repaid with interest", async () => {
+    const loanId = new BN(1);
+    const [fixedLoan] = PublicKey.findProgramAddressSync(
+      [Buffer.from("fixed_loan"), signer.publicKey.toBuffer(), mintSOL.toBuffer(), loanId.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+
+    const quote = await program.methods
+      .getBankState()
+      .accounts({ bank: solBankAccount, bankTokenAccount: solTreasuryAccount })
+      .view();
+
+    await program.methods
+      .borrowFixed(loanId, new BN(100_000_000), new BN(30 * 86_400), new BN(100_000))
+      .accounts({
+        signer: signer.publicKey,
+        mint: mintSOL,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        priceUpdate: solUsdPriceFeedAccount,
+      })
+      .rpc({ commitment: "confirmed" });
+
+    const loan = await program.account.fixedLoan.fetch(fixedLoan);
+    if (loan.rateBps.toNumber() !== quote.borrowAprBps.toNumber() + 200) {
+      throw new Error("fixed rate should be the variable rate plus the premium");
+    }
+    const bankAfterBorrow = await program.account.bank.fetch(solBankAccount);
+    if (bankAfterBorrow.totalFixedBorrowed.toNumber() !== 100_000_000) {
+      throw new Error("bank should track fixed principal separately");
+    }
+
+    await bankrunContextWrapper.moveTimeForward(15 * 86_400);
+    await program.methods
+      .repayFixed()
+      .accounts({
+        signer: signer.publicKey,
+        mint: mintSOL,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        fixedLoan,
+      })
+      .rpc({ commitment: "confirmed" });
+
+    const bankAfterRepay = await program.account.bank.fetch(solBankAccount);
+    if (bankAfterRepay.totalFixedBorrowed.toNumber() !== 0) {
+      throw new Error("repaid principal should leave the bank's fixed book");
+    }
+    if (await banksClient.getAccount(fixedLoan)) {
+      throw new Error("repaid loan account should be closed");
+    }
+  });
+
+  it("overdue fixed-rate loan is liquidated for collateral worth the amount due", async () => {
+    // A borrower posts 10 SOL (9 decimals) and takes a fixed-rate loan of 100 USDC (6 decimals)
+    const mintUSDC = await createMint(
+      // @ts-ignore
+      banksClient,
+      signer,
+      signer.publicKey,
+      null,
+      6
+    );
+    const [usdcTreasury] = PublicKey.findProgramAddressSync(
+      [Buffer.from("treasury"), mintUSDC.toBuffer()],
+      program.programId
+    );
+    await program.methods
+      .initBank(new BN(8000), new BN(7500))
+      .accounts({ signer: signer.publicKey, mint: mintUSDC, tokenProgram: TOKEN_PROGRAM_ID })
+      .rpc({ commitment: "confirmed" });
+    // @ts-ignore
+    await mintTo(banksClient, signer, mintUSDC, usdcTreasury, signer, new BN(1_000_000 * 10 ** 6));
+
+    const borrower = Keypair.generate();
+    context.setAccount(borrower.publicKey, {
+      lamports: 10 * 10 ** 9,
+      data: Buffer.alloc(0),
+      owner: PublicKey.default,
+      executable: false,
+    });
+    const [borrowerAccount] = PublicKey.findProgramAddressSync([borrower.publicKey.toBuffer()], program.programId);
+    // @ts-ignore
+    const borrowerSol = await createAccount(banksClient, signer, mintSOL, borrower.publicKey);
+    // @ts-ignore
+    await mintTo(banksClient, signer, mintSOL, borrowerSol, signer, new BN(10 * 10 ** 9));
+
+    // SOL at 150 USD and USDC at 1 USD from Pyth, the USDC update passed as a remaining account
+    const priceUpdate = Keypair.generate().publicKey;
+    const usdcPriceUpdate = Keypair.generate().publicKey;
+    const refreshPrices = async () => {
+      const now = Number((await banksClient.getClock()).unixTimestamp);
+      setPythPrice(context, priceUpdate, SOL_PRICE_FEED_ID, 150 * 10 ** 8, 10 ** 6, now);
+      setPythPrice(context, usdcPriceUpdate, USDC_PRICE_FEED_ID, 10 ** 8, 10 ** 4, now);
+    };
+    const fallbacks = [{ pubkey: usdcPriceUpdate, isSigner: false, isWritable: false }];
+    await refreshPrices();
+
+    await program.methods
+      .initUser(mintUSDC)
+      .accounts({ signer: borrower.publicKey })
+      .signers([borrower])
+      .rpc({ commitment: "confirmed" });
+    await program.methods
+      .deposit(new BN(10 * 10 ** 9))
+      .accounts({ signer: borrower.publicKey, mint: mintSOL, priceUpdate, tokenProgram: TOKEN_PROGRAM_ID })
+      .remainingAccounts(fallbacks)
+      .signers([borrower])
+      .rpc({ commitment: "confirmed" });
+
+    const loanId = new BN(7);
+    const principal = new BN(100 * 10 ** 6);
+    const [fixedLoan] = PublicKey.findProgramAddressSync(
+      [Buffer.from("fixed_loan"), borrower.publicKey.toBuffer(), mintUSDC.toBuffer(), loanId.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    // 10 SOL at 150 USD and a 75% max LTV cover at most 1125 USDC
+    let overBorrowRejected = false;
+    try {
+      await program.methods
+        .borrowFixed(new BN(8), new BN(1_200 * 10 ** 6), new BN(86_400), new BN(100_000))
+        .accounts({ signer: borrower.publicKey, mint: mintUSDC, priceUpdate, tokenProgram: TOKEN_PROGRAM_ID })
+        .remainingAccounts(fallbacks)
+        .signers([borrower])
+        .rpc({ commitment: "confirmed" });
+    } catch (e) {
+      overBorrowRejected = String(e).includes("OverBorrowableAmount");
+    }
+    if (!overBorrowRejected) {
+      throw new Error("borrows beyond the max LTV of the collateral's USD value should be rejected");
+    }
+
+    await program.methods
+      .borrowFixed(loanId, principal, new BN(86_400), new BN(100_000))
+      .accounts({ signer: borrower.publicKey, mint: mintUSDC, priceUpdate, tokenProgram: TOKEN_PROGRAM_ID })
+      .remainingAccounts(fallbacks)
+      .signers([borrower])
+      .rpc({ commitment: "confirmed" });
+
+    // The liquidator repays the loan from its own USDC
+    // @ts-ignore
+    const liquidatorUsdc = await createAccount(banksClient, signer, mintUSDC, signer.publicKey);
+    // @ts-ignore
+    await mintTo(banksClient, signer, mintUSDC, liquidatorUsdc, signer, new BN(1_000 * 10 ** 6));
+    const liquidate = (collateralMint: PublicKey) => program.methods
+      .liquidateFixedLoan()
+      .accounts({
+        liquidator: signer.publicKey,
+        priceUpdate,
+        collateralMint,
+        loanMint: mintUSDC,
+        userAccount: borrowerAccount,
+        fixedLoan,
+        owner: borrower.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .remainingAccounts(fallbacks)
+      .rpc({ commitment: "confirmed" });
+
+    let rejected = false;
+    try {
+      await liquidate(mintSOL);
+    } catch (e) {
+      rejected = String(e).includes("LoanNotOverdue");
+    }
+    if (!rejected) {
+      throw new Error("a loan inside its term and grace period should not be liquidatable");
+    }
+
+    // Past maturity (1 day) and the 3 day grace period
+    await bankrunContextWrapper.moveTimeForward(86_400 + 259_200 + 60);
+    await refreshPrices();
+
+    // Only the borrower's SOL or USDC deposits can be seized
+    const otherMint = await createMint(
+      // @ts-ignore
+      banksClient,
+      signer,
+      signer.publicKey,
+      null,
+      9
+    );
+    await program.methods
+      .initBank(new BN(8000), new BN(7500))
+      .accounts({ signer: signer.publicKey, mint: otherMint, tokenProgram: TOKEN_PROGRAM_ID })
+      .rpc({ commitment: "confirmed" });
+    rejected = false;
+    try {
+      await liquidate(otherMint);
+    } catch (e) {
+      rejected = String(e).includes("InvalidAssetPair");
+    }
+    if (!rejected) {
+      throw new Error("collateral outside the borrower's position should be rejected");
+    }
+    // Nor can the borrower move collateral into another non-USDC mint once SOL is pinned
+    // @ts-ignore
+    const borrowerOther = await createAccount(banksClient, signer, otherMint, borrower.publicKey);
+    // @ts-ignore
+    await mintTo(banksClient, signer, otherMint, borrowerOther, signer, new BN(10 ** 9));
+    rejected = false;
+    try {
+      await program.methods
+        .deposit(new BN(10 ** 9))
+        .accounts({ signer: borrower.publicKey, mint: otherMint, priceUpdate, tokenProgram: TOKEN_PROGRAM_ID })
+        .remainingAccounts(fallbacks)
+        .signers([borrower])
+        .rpc({ commitment: "confirmed" });
+    } catch (e) {
+      rejected = String(e).includes("UnsupportedPositionMint");
+    }
+    if (!rejected) {
+      throw new Error("deposits in a second non-USDC mint should be rejected");
+    }
+
+    await liquidate(mintSOL);
+
+    // 100 USDC plus interest at 150 USD/SOL is a little over 0.6666 SOL, in 9 decimal base units
+    const liquidatorSol = getAssociatedTokenAddressSync(mintSOL, signer.publicKey);
+    // @ts-ignore
+    const seized = Number((await getAccount(banksClient, liquidatorSol)).amount) - 40 * 10 ** 9;
+    if (seized < 666_666_666 || seized > 700_000_000) {
+      throw new Error(`seized ${seized} lamports, expected the USD value of the amount due in SOL`);
+    }
+    const position = await program.account.user.fetch(borrowerAccount);
+    if (!position.fixedBorrowedUsdc.isZero() || position.depositedSol.toNumber() !== 10 * 10 ** 9 - seized) {
+      throw new Error("the loan should be settled against the seized collateral");
+    }
+    if (await banksClient.getAccount(fixedLoan)) {
+      throw new Error("liquidated loan account should be closed");
+    }
+  });
+});
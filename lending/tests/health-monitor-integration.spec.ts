@@ -75,7 +75,7 @@ describe("Health Monitoring Integration Tests", () => {
       .accounts({ signer: user.publicKey, userAccount: userAccount, systemProgram: SystemProgram.programId })
       .signers([user]).rpc();
 
-    await program.methods.updateHealthThreshold(new anchor.BN(200), 1) // 2.0x threshold, 1 hour frequency
+    await program.methods.updateHealthThreshold(new anchor.BN(20000), 1) // 2.0x threshold, 1 hour frequency
       .accounts({ signer: user.publicKey, userAccount: userAccount, systemProgram: SystemProgram.programId })
       .signers([user]).rpc();
 
@@ -198,7 +198,7 @@ describe("Health Monitoring Integration Tests", () => {
 
     // Verify health monitoring is working
     expect(userData.isMonitoringEnabled).to.be.true;
-    expect(userData.alertThreshold.toNumber()).to.equal(200);
+    expect(userData.alertThreshold.toNumber()).to.equal(20000);
     expect(userData.healthHistoryCount).to.be.greaterThan(0);
     expect(userData.lastHealthCheck.toNumber()).to.be.greaterThan(0);
 
@@ -222,7 +222,7 @@ describe("Health Monitoring Integration Tests", () => {
 
     // Test invalid threshold updates
     try {
-      await program.methods.updateHealthThreshold(new anchor.BN(50), 12) // Too low
+      await program.methods.updateHealthThreshold(new anchor.BN(5000), 12) // Too low
         .accounts({ signer: user.publicKey, userAccount: userAccount, systemProgram: SystemProgram.programId })
         .signers([user]).rpc();
       expect.fail("Should have rejected invalid threshold");
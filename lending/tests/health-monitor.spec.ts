@@ -151,7 +151,9 @@ describe("Health Monitoring System", () => {
   it("Should initialize user with default health monitoring settings", async () => {
     const userData = await program.account.user.fetch(userAccount);
     
-    expect(userData.alertThreshold.toNumber()).to.equal(150); // 1.5x threshold
+    expect(userData.alertThreshold.toNumber()).to.equal(15000); // 1.5x threshold (bps)
+    expect(userData.criticalAlertThreshold.toNumber()).to.equal(12500);
+    expect(userData.imminentAlertThreshold.toNumber()).to.equal(11000);
     expect(userData.isMonitoringEnabled).to.be.false;
     expect(userData.alertFrequencyHours).to.equal(24);
     expect(userData.healthHistoryCount).to.equal(0);
@@ -174,7 +176,7 @@ describe("Health Monitoring System", () => {
 
   it("Should update health threshold", async () => {
     await program.methods
-      .updateHealthThreshold(new anchor.BN(200), 12) // 2.0x threshold, 12 hour frequency
+      .updateHealthThreshold(new anchor.BN(20000), 12) // 2.0x threshold, 12 hour frequency
       .accounts({
         signer: user.publicKey,
         userAccount: userAccount,
@@ -184,14 +186,14 @@ describe("Health Monitoring System", () => {
       .rpc();
 
     const userData = await program.account.user.fetch(userAccount);
-    expect(userData.alertThreshold.toNumber()).to.equal(200);
+    expect(userData.alertThreshold.toNumber()).to.equal(20000);
     expect(userData.alertFrequencyHours).to.equal(12);
   });
 
   it("Should reject invalid thresholds", async () => {
     try {
       await program.methods
-        .updateHealthThreshold(new anchor.BN(50), 12) // Too low
+        .updateHealthThreshold(new anchor.BN(5000), 12) // Too low
         .accounts({
           signer: user.publicKey,
           userAccount: userAccount,
@@ -206,7 +208,7 @@ describe("Health Monitoring System", () => {
 
     try {
       await program.methods
-        .updateHealthThreshold(new anchor.BN(200), 200) // Too high frequency
+        .updateHealthThreshold(new anchor.BN(20000), 200) // Too high frequency
         .accounts({
           signer: user.publicKey,
           userAccount: userAccount,
@@ -220,6 +222,95 @@ describe("Health Monitoring System", () => {
     }
   });
 
+  it("Should update and validate alert tiers", async () => {
+    await program.methods
+      .updateAlertTier({ critical: {} }, new anchor.BN(13000), 4)
+      .accounts({
+        signer: user.publicKey,
+        userAccount: userAccount,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user])
+      .rpc();
+
+    await program.methods
+      .updateAlertTier({ liquidationImminent: {} }, new anchor.BN(0), 1) // Disable tier
+      .accounts({
+        signer: user.publicKey,
+        userAccount: userAccount,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user])
+      .rpc();
+
+    const userData = await program.account.user.fetch(userAccount);
+    expect(userData.criticalAlertThreshold.toNumber()).to.equal(13000);
+    expect(userData.criticalAlertFrequencyHours).to.equal(4);
+    expect(userData.imminentAlertThreshold.toNumber()).to.equal(0);
+
+    try {
+      await program.methods
+        .updateAlertTier({ critical: {} }, new anchor.BN(25000), 4) // Above warning tier
+        .accounts({
+          signer: user.publicKey,
+          userAccount: userAccount,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user])
+        .rpc();
+      expect.fail("Should have rejected out of order tier");
+    } catch (error) {
+      expect(error.message).to.include("InvalidAlertTierOrder");
+    }
+  });
+
+  it("Should reject bank risk parameters outside bps bounds", async () => {
+    const mint = await createMint(connection, authority, authority.publicKey, null, 6);
+    // Percent values from before thresholds moved to bps, and an LTV above the threshold
+    for (const [liquidationThreshold, maxLtv] of [[80, 0], [8000, 8000], [12000, 7500]]) {
+      try {
+        await program.methods
+          .initBank(new anchor.BN(liquidationThreshold), new anchor.BN(maxLtv))
+          .accounts({ signer: authority.publicKey, mint, tokenProgram: TOKEN_PROGRAM_ID, systemProgram: SystemProgram.programId })
+          .signers([authority])
+          .rpc();
+        expect.fail("Should have rejected invalid risk parameters");
+      } catch (error) {
+        expect(error.message).to.include("InvalidBankConfig");
+      }
+    }
+  });
+
+  it("Should let the bank authority migrate risk parameters to bps", async () => {
+    await program.methods
+      .updateBankRiskParams(new anchor.BN(8500), new anchor.BN(7000))
+      .accounts({ signer: authority.publicKey, bank: solBank })
+      .signers([authority])
+      .rpc();
+    let bank = await program.account.bank.fetch(solBank);
+    expect(bank.liquidationThreshold.toNumber()).to.equal(8500);
+    expect(bank.maxLtv.toNumber()).to.equal(7000);
+
+    try {
+      await program.methods
+        .updateBankRiskParams(new anchor.BN(80), new anchor.BN(75))
+        .accounts({ signer: authority.publicKey, bank: solBank })
+        .signers([authority])
+        .rpc();
+      expect.fail("Should have rejected percent values");
+    } catch (error) {
+      expect(error.message).to.include("InvalidBankConfig");
+    }
+
+    await program.methods
+      .updateBankRiskParams(new anchor.BN(8000), new anchor.BN(7500))
+      .accounts({ signer: authority.publicKey, bank: solBank })
+      .signers([authority])
+      .rpc();
+    bank = await program.account.bank.fetch(solBank);
+    expect(bank.liquidationThreshold.toNumber()).to.equal(8000);
+  });
+
   it("Should calculate health factor correctly after deposit", async () => {
     // Deposit SOL
     await program.methods
@@ -268,6 +359,29 @@ describe("Health Monitoring System", () => {
     expect(userData.healthFactor.toNumber()).to.be.greaterThan(0);
   });
 
+  it("Should return the position view with the balances borrow and liquidate use", async () => {
+    const position = await program.methods
+      .getUserPosition()
+      .accounts({
+        userAccount: userAccount,
+        solBank: solBank,
+        usdcBank: usdcBank,
+        priceUpdate: priceUpdate,
+      })
+      .view();
+
+    const userData = await program.account.user.fetch(userAccount);
+    expect(position.owner.toString()).to.equal(user.publicKey.toString());
+    expect(position.depositedSol.toNumber()).to.equal(userData.depositedSol.toNumber());
+    expect(position.borrowedUsdc.toNumber()).to.equal(userData.borrowedUsdc.toNumber());
+    // Share balances redeemed with interest accrued to now never fall below the principal
+    expect(position.depositedSolWithInterest.toNumber()).to.be.at.least(userData.depositedSol.toNumber() - 1);
+    expect(position.borrowedUsdcWithInterest.toNumber()).to.be.at.least(userData.borrowedUsdc.toNumber());
+    expect(position.healthFactor.toNumber()).to.be.greaterThan(0);
+    expect(position.liquidationHealthFactor.toNumber()).to.be.lessThan(position.healthFactor.toNumber());
+    expect(position.liquidationSolPrice.toNumber()).to.be.lessThan(position.solPrice.toNumber());
+  });
+
   it("Should create health snapshots", async () => {
     const [healthSnapshot] = PublicKey.findProgramAddressSync(
       [
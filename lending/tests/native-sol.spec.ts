@@ -0,0 +1,62 @@
+import { describe, it } from "node:test";
+import { BN } from "@coral-xyz/anchor";
+import { TOKEN_PROGRAM_ID, NATIVE_MINT, getAssociatedTokenAddressSync } from "@solana/spl-token";
+import { getAccount } from "spl-token-bankrun";
+import { PublicKey } from "@solana/web3.js";
+import { setupSolMarket } from "../bankrun-utils/solMarket";
+
+describe("Lending - Native SOL", async () => {
+  const {
+    program,
+    banksClient,
+    signer,
+    solUsdPriceFeedAccount,
+  } = await setupSolMarket();
+
+  it("native SOL is wrapped into the treasury and unwrapped back to the signer", async () => {
+    const [nativeTreasury] = PublicKey.findProgramAddressSync(
+      [Buffer.from("treasury"), NATIVE_MINT.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .initBank(new BN(8000), new BN(7500))
+      .accounts({ signer: signer.publicKey, mint: NATIVE_MINT, tokenProgram: TOKEN_PROGRAM_ID })
+      .rpc({ commitment: "confirmed" });
+
+    const amount = new BN(2 * 10 ** 9);
+    await program.methods
+      .depositNative(amount)
+      .accounts({
+        signer: signer.publicKey,
+        mint: NATIVE_MINT,
+        priceUpdate: solUsdPriceFeedAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc({ commitment: "confirmed" });
+    // @ts-ignore
+    const treasury = await getAccount(banksClient, nativeTreasury);
+    if (treasury.amount !== BigInt(amount.toString())) {
+      throw new Error("deposited lamports should be wrapped into the treasury");
+    }
+
+    const lamportsBefore = await banksClient.getBalance(signer.publicKey);
+    await program.methods
+      .withdrawNative(amount.divn(2))
+      .accounts({
+        signer: signer.publicKey,
+        mint: NATIVE_MINT,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc({ commitment: "confirmed" });
+    const lamportsAfter = await banksClient.getBalance(signer.publicKey);
+    // Only the transaction fee is lost, the temporary account's rent comes back on close
+    if (lamportsAfter - lamportsBefore < BigInt(amount.divn(2).toString()) - BigInt(10_000)) {
+      throw new Error("withdrawn wSOL should be unwrapped to the signer as lamports");
+    }
+    const signerWsol = getAssociatedTokenAddressSync(NATIVE_MINT, signer.publicKey);
+    if (await banksClient.getAccount(signerWsol)) {
+      throw new Error("native SOL instructions should not create a wSOL account for the signer");
+    }
+  });
+});
@@ -129,8 +129,8 @@ describe("Lending Protocol - On-Chain Tests (Devnet)", () => {
     console.log("💰 USDC Treasury Account:", usdcTreasuryAccount.toBase58());
 
     try {
-      const liquidationThreshold = new BN(80); // 80%
-      const maxLtv = new BN(75); // 75%
+      const liquidationThreshold = new BN(8000); // 80% in bps
+      const maxLtv = new BN(7500); // 75% in bps
 
       const tx = await program.methods
         .initBank(liquidationThreshold, maxLtv)
@@ -194,8 +194,8 @@ describe("Lending Protocol - On-Chain Tests (Devnet)", () => {
     console.log("💰 SOL Treasury Account:", solTreasuryAccount.toBase58());
 
     try {
-      const liquidationThreshold = new BN(80);
-      const maxLtv = new BN(75);
+      const liquidationThreshold = new BN(8000);
+      const maxLtv = new BN(7500);
 
       const tx = await program.methods
         .initBank(liquidationThreshold, maxLtv)
@@ -0,0 +1,290 @@
+import { describe, it } from "node:test";
+import { BN } from "@coral-xyz/anchor";
+import { TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { createAccount, createMint, mintTo } from "spl-token-bankrun";
+import { PublicKey, Keypair } from "@solana/web3.js";
+import {
+  setupSolMarket,
+  setPythPrice,
+  feedIdBytes,
+  programDataAddress,
+  SOL_PRICE_FEED_ID,
+  USDC_PRICE_FEED_ID,
+} from "../bankrun-utils/solMarket";
+
+describe("Lending - Oracle Valuation and Fallbacks", async () => {
+  const {
+    context,
+    program,
+    banksClient,
+    signer,
+    mintSOL,
+    solBankAccount,
+  } = await setupSolMarket();
+
+  it("banks choose separate valuation modes for borrow limits and liquidations", async () => {
+    await program.methods
+      .updateBankValuationModes({ ema: {} }, { conservative: {} })
+      .accounts({ signer: signer.publicKey, bank: solBankAccount })
+      .rpc({ commitment: "confirmed" });
+
+    const bank = await program.account.bank.fetch(solBankAccount);
+    if (!("ema" in bank.borrowValuationMode) || !("conservative" in bank.liquidationValuationMode)) {
+      throw new Error("valuation modes should be stored on the bank");
+    }
+  });
+
+  it("only the program's upgrade authority can create the oracle config", async () => {
+    const intruder = Keypair.generate();
+    context.setAccount(intruder.publicKey, {
+      lamports: 10 ** 9,
+      data: Buffer.alloc(0),
+      owner: PublicKey.default,
+      executable: false,
+    });
+
+    let rejected = false;
+    try {
+      await program.methods
+        .initOracleConfig()
+        .accounts({ signer: intruder.publicKey, programData: programDataAddress(program.programId) })
+        .signers([intruder])
+        .rpc({ commitment: "confirmed" });
+    } catch (e) {
+      rejected = String(e).includes("Unauthorized");
+    }
+    if (!rejected) {
+      throw new Error("init_oracle_config should be restricted to the upgrade authority");
+    }
+  });
+
+  it("stale Pyth prices fall back to the secondary oracle and guardian prices", async () => {
+    const now = Number((await banksClient.getClock()).unixTimestamp);
+
+    // Local mocks of PriceUpdateV2 accounts whose SOL and USDC prices were published an hour ago
+    const stalePriceUpdate = Keypair.generate().publicKey;
+    const staleUsdcUpdate = Keypair.generate().publicKey;
+    setPythPrice(context, stalePriceUpdate, SOL_PRICE_FEED_ID, 150 * 10 ** 8, 10 ** 7, now - 3600);
+    setPythPrice(context, staleUsdcUpdate, USDC_PRICE_FEED_ID, 10 ** 8, 10 ** 4, now - 3600);
+
+    const depositWith = (remainingAccounts: PublicKey[]) => program.methods
+      .deposit(new BN(1_000))
+      .accounts({
+        signer: signer.publicKey,
+        mint: mintSOL,
+        priceUpdate: stalePriceUpdate,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .remainingAccounts(remainingAccounts.map((pubkey) => ({ pubkey, isSigner: false, isWritable: false })))
+      .rpc({ commitment: "confirmed" });
+
+    let rejected = false;
+    try {
+      await depositWith([staleUsdcUpdate]);
+    } catch (e) {
+      rejected = true;
+    }
+    if (!rejected) {
+      throw new Error("a stale Pyth price without fallbacks should be rejected");
+    }
+
+    // The signer, as upgrade authority, becomes guardian and pushes short-lived manual prices for both feeds
+    const [oracleConfig] = PublicKey.findProgramAddressSync([Buffer.from("oracle_config")], program.programId);
+    await program.methods
+      .initOracleConfig()
+      .accounts({ signer: signer.publicKey, programData: programDataAddress(program.programId) })
+      .rpc({ commitment: "confirmed" });
+
+    const setManualPrice = (feedId: string, price: number, validFor: number) => program.methods
+      .setManualPrice(feedIdBytes(feedId), new BN(price), new BN(0), -8, new BN(validFor))
+      .accounts({ signer: signer.publicKey })
+      .rpc({ commitment: "confirmed" });
+
+    rejected = false;
+    try {
+      await setManualPrice(SOL_PRICE_FEED_ID, 150 * 10 ** 8, 3600);
+    } catch (e) {
+      rejected = String(e).includes("InvalidManualPrice");
+    }
+    if (!rejected) {
+      throw new Error("manual prices should not outlive MAX_MANUAL_PRICE_VALIDITY");
+    }
+
+    await setManualPrice(SOL_PRICE_FEED_ID, 150 * 10 ** 8, 600);
+    await setManualPrice(USDC_PRICE_FEED_ID, 10 ** 8, 600);
+    const [manualSol] = PublicKey.findProgramAddressSync(
+      [Buffer.from("manual_price"), Buffer.from(feedIdBytes(SOL_PRICE_FEED_ID))],
+      program.programId
+    );
+    const [manualUsdc] = PublicKey.findProgramAddressSync(
+      [Buffer.from("manual_price"), Buffer.from(feedIdBytes(USDC_PRICE_FEED_ID))],
+      program.programId
+    );
+
+    // Fallbacks only replace a stale update for the feed, not a missing one
+    rejected = false;
+    try {
+      await depositWith([manualSol, manualUsdc]);
+    } catch (e) {
+      rejected = String(e).includes("InvalidPriceUpdate");
+    }
+    if (!rejected) {
+      throw new Error("a feed without its Pyth update should be rejected rather than fall back");
+    }
+
+    await depositWith([staleUsdcUpdate, manualSol, manualUsdc]);
+
+    // Local mock secondary oracle account, owned by the program named in the oracle config
+    const secondaryProgram = Keypair.generate().publicKey;
+    await program.methods
+      .updateOracleConfig(signer.publicKey, secondaryProgram)
+      .accounts({ signer: signer.publicKey })
+      .rpc({ commitment: "confirmed" });
+
+    const secondarySol = Keypair.generate().publicKey;
+    const setSecondaryPrice = (price: number) => {
+      const data = Buffer.alloc(32 + 8 + 8 + 4 + 8);
+      Buffer.from(feedIdBytes(SOL_PRICE_FEED_ID)).copy(data, 0);
+      data.writeBigInt64LE(BigInt(price), 32);
+      data.writeBigUInt64LE(BigInt(0), 40);
+      data.writeInt32LE(-6, 48);
+      data.writeBigInt64LE(BigInt(now), 52);
+      context.setAccount(secondarySol, { lamports: 1_000_000_000, data, owner: secondaryProgram, executable: false });
+    };
+
+    // 100 USD on the secondary oracle against 150 USD from the guardian
+    setSecondaryPrice(100 * 10 ** 6);
+    rejected = false;
+    try {
+      await depositWith([staleUsdcUpdate, oracleConfig, secondarySol, manualSol, manualUsdc]);
+    } catch (e) {
+      rejected = String(e).includes("OracleSourcesDisagree");
+    }
+    if (!rejected) {
+      throw new Error("sources outside the tolerance of each other should be rejected");
+    }
+
+    // Within tolerance, the secondary price wins over the manual one
+    setSecondaryPrice(151 * 10 ** 6);
+    await depositWith([staleUsdcUpdate, oracleConfig, secondarySol, manualSol, manualUsdc]);
+
+    // A fresh Pyth price at 148 USD is only cross-checked: the guardian's 150 agrees with it and the
+    // secondary 151 does not, so the deposit fails rather than settling on the 150 both others accept
+    const freshPriceUpdate = Keypair.generate().publicKey;
+    const freshUsdcUpdate = Keypair.generate().publicKey;
+    setPythPrice(context, freshPriceUpdate, SOL_PRICE_FEED_ID, 148 * 10 ** 8, 10 ** 6, now);
+    setPythPrice(context, freshUsdcUpdate, USDC_PRICE_FEED_ID, 10 ** 8, 10 ** 4, now);
+    const depositAtFreshPrice = (remainingAccounts: PublicKey[]) => program.methods
+      .deposit(new BN(1_000))
+      .accounts({
+        signer: signer.publicKey,
+        mint: mintSOL,
+        priceUpdate: freshPriceUpdate,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .remainingAccounts(remainingAccounts.map((pubkey) => ({ pubkey, isSigner: false, isWritable: false })))
+      .rpc({ commitment: "confirmed" });
+
+    rejected = false;
+    try {
+      await depositAtFreshPrice([freshUsdcUpdate, oracleConfig, secondarySol, manualSol]);
+    } catch (e) {
+      rejected = String(e).includes("OracleSourcesDisagree");
+    }
+    if (!rejected) {
+      throw new Error("fallback sources should only cross-check a fresh Pyth price, not replace it");
+    }
+    await depositAtFreshPrice([freshUsdcUpdate, manualSol]);
+  });
+
+  it("the bank's oracle policy rejects wide-confidence and EMA-divergent prices", async () => {
+    // A depositor whose USDC side is a fresh mint, so mintSOL is valued as SOL under its bank's policy
+    const mintUSDC = await createMint(
+      // @ts-ignore
+      banksClient,
+      signer,
+      signer.publicKey,
+      null,
+      6
+    );
+    const depositor = Keypair.generate();
+    context.setAccount(depositor.publicKey, {
+      lamports: 10 * 10 ** 9,
+      data: Buffer.alloc(0),
+      owner: PublicKey.default,
+      executable: false,
+    });
+    // @ts-ignore
+    const depositorSol = await createAccount(banksClient, signer, mintSOL, depositor.publicKey);
+    // @ts-ignore
+    await mintTo(banksClient, signer, mintSOL, depositorSol, signer, new BN(10 * 10 ** 9));
+    await program.methods
+      .initUser(mintUSDC)
+      .accounts({ signer: depositor.publicKey })
+      .signers([depositor])
+      .rpc({ commitment: "confirmed" });
+
+    // At most 0.5% confidence and 3% away from the EMA
+    let rejected = false;
+    try {
+      await program.methods
+        .updateBankOraclePolicy(new BN(100), new BN(50_000), new BN(300))
+        .accounts({ signer: signer.publicKey, bank: solBankAccount })
+        .rpc({ commitment: "confirmed" });
+    } catch (e) {
+      rejected = String(e).includes("InvalidBankConfig");
+    }
+    if (!rejected) {
+      throw new Error("confidence bounds above 100% should be rejected");
+    }
+    await program.methods
+      .updateBankOraclePolicy(new BN(100), new BN(50), new BN(300))
+      .accounts({ signer: signer.publicKey, bank: solBankAccount })
+      .rpc({ commitment: "confirmed" });
+
+    // SOL at 150 USD and USDC at 1 USD from Pyth
+    const priceUpdate = Keypair.generate().publicKey;
+    const usdcPriceUpdate = Keypair.generate().publicKey;
+    const depositAt = async (conf: number, emaPrice: number) => {
+      const now = Number((await banksClient.getClock()).unixTimestamp);
+      setPythPrice(context, priceUpdate, SOL_PRICE_FEED_ID, 150 * 10 ** 8, conf, now, emaPrice);
+      setPythPrice(context, usdcPriceUpdate, USDC_PRICE_FEED_ID, 10 ** 8, 10 ** 4, now);
+      await program.methods
+        .deposit(new BN(10 ** 9))
+        .accounts({ signer: depositor.publicKey, mint: mintSOL, priceUpdate, tokenProgram: TOKEN_PROGRAM_ID })
+        .remainingAccounts([{ pubkey: usdcPriceUpdate, isSigner: false, isWritable: false }])
+        .signers([depositor])
+        .rpc({ commitment: "confirmed" });
+    };
+
+    // 1.5 USD confidence on 150 USD is 1%
+    rejected = false;
+    try {
+      await depositAt(150 * 10 ** 6, 150 * 10 ** 8);
+    } catch (e) {
+      rejected = String(e).includes("PriceConfidenceTooWide");
+    }
+    if (!rejected) {
+      throw new Error("prices with a confidence interval above the bank's bound should be rejected");
+    }
+
+    // Spot 150 USD against an EMA of 141 USD is about 6% away
+    rejected = false;
+    try {
+      await depositAt(10 ** 6, 141 * 10 ** 8);
+    } catch (e) {
+      rejected = String(e).includes("PriceDeviatesFromEma");
+    }
+    if (!rejected) {
+      throw new Error("prices too far from their EMA should be rejected");
+    }
+
+    await depositAt(10 ** 6, 149 * 10 ** 8);
+    const depositorAccount = await program.account.user.fetch(
+      PublicKey.findProgramAddressSync([depositor.publicKey.toBuffer()], program.programId)[0]
+    );
+    if (depositorAccount.depositedSol.toNumber() !== 10 ** 9) {
+      throw new Error("prices within the bank's policy should be accepted");
+    }
+  });
+});
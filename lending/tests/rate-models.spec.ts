@@ -0,0 +1,118 @@
+import { describe, it } from "node:test";
+import { BN } from "@coral-xyz/anchor";
+import { TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { PublicKey, Keypair } from "@solana/web3.js";
+import { setupSolMarket } from "../bankrun-utils/solMarket";
+
+// Pluggable rate model accounts: a fixed model drives the borrow APR directly and the adaptive
+// model moves its rate at target toward optimal utilization.
+
+describe("Lending - Interest Rate Models", async () => {
+  const {
+    program,
+    bankrunContextWrapper,
+    signer,
+    mintSOL,
+    solBankAccount,
+    solTreasuryAccount,
+    solUsdPriceFeedAccount,
+  } = await setupSolMarket();
+  let fixedRateModel: PublicKey;
+
+  it("switching to a fixed rate model drives the borrow APR", async () => {
+    const rateModel = Keypair.generate();
+    await program.methods
+      .initInterestRateModel({ fixed: { rateBps: new BN(500) } })
+      .accounts({ signer: signer.publicKey, interestRateModel: rateModel.publicKey })
+      .signers([rateModel])
+      .rpc({ commitment: "confirmed" });
+
+    await program.methods
+      .setBankRateModel()
+      .accounts({
+        signer: signer.publicKey,
+        bank: solBankAccount,
+        currentRateModel: null,
+        newRateModel: rateModel.publicKey,
+      })
+      .rpc({ commitment: "confirmed" });
+
+    fixedRateModel = rateModel.publicKey;
+    const bank = await program.account.bank.fetch(solBankAccount);
+    if (!bank.interestRateModel.equals(rateModel.publicKey)) {
+      throw new Error("bank should reference the new rate model");
+    }
+
+    const state = await program.methods
+      .getBankState()
+      .accounts({ bank: solBankAccount, bankTokenAccount: solTreasuryAccount, interestRateModel: rateModel.publicKey })
+      .view();
+    if (state.borrowAprBps.toNumber() !== 500) {
+      throw new Error("borrow APR should come from the fixed rate model");
+    }
+
+    // Models are rejected when they do not cover 0-100% utilization
+    const badModel = Keypair.generate();
+    try {
+      await program.methods
+        .initInterestRateModel({
+          piecewise: {
+            points: Array.from({ length: 8 }, (_, i) => ({ utilizationBps: new BN(i * 1000), rateBps: new BN(i * 100) })),
+            len: 3,
+          },
+        })
+        .accounts({ signer: signer.publicKey, interestRateModel: badModel.publicKey })
+        .signers([badModel])
+        .rpc();
+      throw new Error("expected InvalidRateModel");
+    } catch (err) {
+      if (!String(err).includes("InvalidRateModel")) throw err;
+    }
+  });
+
+  it("adaptive model lowers its rate at target while utilization is below optimal", async () => {
+    const rateModel = Keypair.generate();
+    const initialRateAtTarget = 1_000;
+    await program.methods
+      .initInterestRateModel({
+        adaptive: {
+          initialRateAtTargetBps: new BN(initialRateAtTarget),
+          minRateAtTargetBps: new BN(10),
+          maxRateAtTargetBps: new BN(20_000),
+          adjustmentSpeed: new BN(1_585), // ~50x per year at full error
+        },
+      })
+      .accounts({ signer: signer.publicKey, interestRateModel: rateModel.publicKey })
+      .signers([rateModel])
+      .rpc({ commitment: "confirmed" });
+
+    await program.methods
+      .setBankRateModel()
+      .accounts({
+        signer: signer.publicKey,
+        bank: solBankAccount,
+        currentRateModel: fixedRateModel,
+        newRateModel: rateModel.publicKey,
+      })
+      .rpc({ commitment: "confirmed" });
+
+    // Utilization in this market sits well below the 80% optimal
+    await bankrunContextWrapper.moveTimeForward(86_400);
+    await program.methods
+      .borrow(new BN(1_000_000))
+      .accounts({
+        signer: signer.publicKey,
+        mint: mintSOL,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        priceUpdate: solUsdPriceFeedAccount,
+        interestRateModel: rateModel.publicKey,
+      })
+      .rpc({ commitment: "confirmed" });
+
+    const bank = await program.account.bank.fetch(solBankAccount);
+    const rateAtTarget = bank.rateAtTargetBps.toNumber();
+    if (rateAtTarget >= initialRateAtTarget || rateAtTarget < 10) {
+      throw new Error("rate at target should drift down within its bounds");
+    }
+  });
+});
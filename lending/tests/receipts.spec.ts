@@ -0,0 +1,78 @@
+import { describe, it } from "node:test";
+import { BN } from "@coral-xyz/anchor";
+import { TOKEN_PROGRAM_ID, TOKEN_2022_PROGRAM_ID, getAssociatedTokenAddressSync } from "@solana/spl-token";
+import { getAccount } from "spl-token-bankrun";
+import { PublicKey } from "@solana/web3.js";
+import { setupSolMarket } from "../bankrun-utils/solMarket";
+
+describe("Lending - Deposit Receipts", async () => {
+  const {
+    program,
+    banksClient,
+    signer,
+    mintSOL,
+    solBankAccount,
+    userAccount,
+    solUsdPriceFeedAccount,
+  } = await setupSolMarket();
+
+  it("deposit receipts are minted per share and can be locked as collateral", async () => {
+    const [receiptMint] = PublicKey.findProgramAddressSync(
+      [Buffer.from("receipt"), mintSOL.toBuffer()],
+      program.programId
+    );
+    const userReceiptAccount = getAssociatedTokenAddressSync(receiptMint, signer.publicKey, false, TOKEN_2022_PROGRAM_ID);
+
+    await program.methods
+      .initReceiptMint()
+      .accounts({ signer: signer.publicKey, mint: mintSOL })
+      .rpc({ commitment: "confirmed" });
+
+    const bankBefore = await program.account.bank.fetch(solBankAccount);
+    await program.methods
+      .depositForReceipt(new BN(1 * 10 ** 9))
+      .accounts({
+        signer: signer.publicKey,
+        mint: mintSOL,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc({ commitment: "confirmed" });
+
+    const bankAfter = await program.account.bank.fetch(solBankAccount);
+    const mintedShares = bankAfter.totalDepositShares.sub(bankBefore.totalDepositShares);
+    // @ts-ignore
+    const receipts = await getAccount(banksClient, userReceiptAccount, undefined, TOKEN_2022_PROGRAM_ID);
+    if (receipts.amount.toString() !== mintedShares.toString()) {
+      throw new Error("one receipt should be minted per deposit share");
+    }
+
+    const half = mintedShares.divn(2);
+    const userBefore = await program.account.user.fetch(userAccount);
+    await program.methods
+      .depositReceiptAsCollateral(half)
+      .accounts({
+        signer: signer.publicKey,
+        mint: mintSOL,
+        priceUpdate: solUsdPriceFeedAccount,
+      })
+      .rpc({ commitment: "confirmed" });
+    const userAfter = await program.account.user.fetch(userAccount);
+    if (!userAfter.depositedSolShares.sub(userBefore.depositedSolShares).eq(half)) {
+      throw new Error("locked receipts should move their shares into the position");
+    }
+
+    await program.methods
+      .withdrawReceipt(mintedShares.sub(half))
+      .accounts({
+        signer: signer.publicKey,
+        mint: mintSOL,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc({ commitment: "confirmed" });
+    // @ts-ignore
+    const remaining = await getAccount(banksClient, userReceiptAccount, undefined, TOKEN_2022_PROGRAM_ID);
+    if (remaining.amount !== BigInt(0)) {
+      throw new Error("redeemed receipts should be burned");
+    }
+  });
+});
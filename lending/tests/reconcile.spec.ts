@@ -0,0 +1,44 @@
+import { describe, it } from "node:test";
+import { TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { createAccount, getAccount } from "spl-token-bankrun";
+import { Keypair } from "@solana/web3.js";
+import { setupSolMarket } from "../bankrun-utils/solMarket";
+
+describe("Lending - Treasury Reconciliation", async () => {
+  const {
+    program,
+    banksClient,
+    signer,
+    mintSOL,
+  } = await setupSolMarket();
+
+  it("verify_bank reports treasury donations and skim_excess sweeps them", async () => {
+    // The treasury was funded directly at setup, which the books never recorded
+    const [verified] = await program.methods
+      .verifyBank()
+      .accounts({ mint: mintSOL })
+      .simulate()
+      .then((res) => res.events.filter((e) => e.name === "bankVerified"));
+    if (!verified || verified.data.excess.isZero() || !verified.data.healthy) {
+      throw new Error("donated tokens should show up as excess on a healthy bank");
+    }
+
+    const feeReceiver = await createAccount(
+      // @ts-ignore
+      banksClient,
+      signer,
+      mintSOL,
+      Keypair.generate().publicKey
+    );
+    await program.methods
+      .skimExcess()
+      .accounts({ signer: signer.publicKey, mint: mintSOL, feeReceiver, tokenProgram: TOKEN_PROGRAM_ID })
+      .rpc({ commitment: "confirmed" });
+
+    // @ts-ignore
+    const received = await getAccount(banksClient, feeReceiver);
+    if (received.amount.toString() !== verified.data.excess.toString()) {
+      throw new Error("the whole unaccounted balance should be skimmed");
+    }
+  });
+});
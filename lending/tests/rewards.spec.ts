@@ -0,0 +1,213 @@
+import { describe, it } from "node:test";
+import { BN } from "@coral-xyz/anchor";
+import { TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { createAccount, createMint, mintTo } from "spl-token-bankrun";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { SOL_PRICE_FEED_ID, USDC_PRICE_FEED_ID, setPythPrice, setupSolMarket } from "../bankrun-utils/solMarket";
+
+describe("Lending - Liquidity Mining", async () => {
+  const {
+    context,
+    program,
+    banksClient,
+    bankrunContextWrapper,
+    signer,
+    mintSOL,
+    solBankAccount,
+  } = await setupSolMarket();
+
+  it("depositors earn liquidity mining rewards and can claim them", async () => {
+    const rewardMint = await createMint(
+      // @ts-ignore
+      banksClient,
+      signer,
+      signer.publicKey,
+      null,
+      6
+    );
+    const funder = await createAccount(
+      // @ts-ignore
+      banksClient,
+      signer,
+      rewardMint,
+      signer.publicKey
+    );
+    await mintTo(
+      // @ts-ignore
+      banksClient,
+      signer,
+      rewardMint,
+      funder,
+      signer,
+      new BN(1_000_000_000)
+    );
+
+    const now = Number((await banksClient.getClock()).unixTimestamp);
+    const startTs = new BN(now);
+    const [rewardPool] = PublicKey.findProgramAddressSync(
+      [Buffer.from("reward_pool"), solBankAccount.toBuffer(), rewardMint.toBuffer(), startTs.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    const [userRewards] = PublicKey.findProgramAddressSync(
+      [Buffer.from("user_rewards"), rewardPool.toBuffer(), signer.publicKey.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .createRewardPool({ deposit: {} }, new BN(100), startTs, new BN(now + 86_400))
+      .accounts({
+        signer: signer.publicKey,
+        bank: solBankAccount,
+        rewardMint,
+        funderTokenAccount: funder,
+        previousRewardPool: null,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc({ commitment: "confirmed" });
+    await program.methods
+      .initUserRewards()
+      .accounts({ signer: signer.publicKey, bank: solBankAccount, rewardPool })
+      .rpc({ commitment: "confirmed" });
+
+    // Deposits must checkpoint against the active pool
+    try {
+      await program.methods
+        .deposit(new BN(1_000))
+        .accounts({
+          signer: signer.publicKey,
+          mint: mintSOL,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+      throw new Error("expected RewardPoolMismatch");
+    } catch (err) {
+      if (!String(err).includes("RewardPoolMismatch")) throw err;
+    }
+
+    // The signer is the only depositor, so it earns the whole emission
+    await bankrunContextWrapper.moveTimeForward(3_600);
+    await program.methods
+      .claimRewards()
+      .accounts({ signer: signer.publicKey, bank: solBankAccount, rewardPool, rewardMint, tokenProgram: TOKEN_PROGRAM_ID })
+      .rpc({ commitment: "confirmed" });
+
+    const claimed = await program.account.userRewards.fetch(userRewards);
+    if (claimed.totalClaimed.toNumber() < 3_600 * 100 * 0.99) {
+      throw new Error("sole depositor should receive the emissions for the elapsed hour");
+    }
+  });
+
+  it("a replaced pool only pays on the shares held while it was emitting", async () => {
+    const rewardMint = await createMint(
+      // @ts-ignore
+      banksClient,
+      signer,
+      signer.publicKey,
+      null,
+      6
+    );
+    // @ts-ignore
+    const funder = await createAccount(banksClient, signer, rewardMint, signer.publicKey);
+    // @ts-ignore
+    await mintTo(banksClient, signer, rewardMint, funder, signer, new BN(1_000_000_000));
+
+    const priceUpdate = Keypair.generate().publicKey;
+    const usdcPriceUpdate = Keypair.generate().publicKey;
+    const refreshPrices = async () => {
+      const now = Number((await banksClient.getClock()).unixTimestamp);
+      setPythPrice(context, priceUpdate, SOL_PRICE_FEED_ID, 150 * 10 ** 8, 10 ** 6, now);
+      setPythPrice(context, usdcPriceUpdate, USDC_PRICE_FEED_ID, 10 ** 8, 10 ** 4, now);
+    };
+    const fallbacks = [{ pubkey: usdcPriceUpdate, isSigner: false, isWritable: false }];
+    const poolAddresses = (startTs: BN) => {
+      const [rewardPool] = PublicKey.findProgramAddressSync(
+        [Buffer.from("reward_pool"), solBankAccount.toBuffer(), rewardMint.toBuffer(), startTs.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      const [userRewards] = PublicKey.findProgramAddressSync(
+        [Buffer.from("user_rewards"), rewardPool.toBuffer(), signer.publicKey.toBuffer()],
+        program.programId
+      );
+      return { rewardPool, userRewards };
+    };
+    const createPool = async (startTs: BN, endTs: BN, previousRewardPool: PublicKey) => {
+      await program.methods
+        .createRewardPool({ deposit: {} }, new BN(100), startTs, endTs)
+        .accounts({
+          signer: signer.publicKey,
+          bank: solBankAccount,
+          rewardMint,
+          funderTokenAccount: funder,
+          previousRewardPool,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc({ commitment: "confirmed" });
+    };
+
+    // The first test's pool is the bank's active one, wait for it to end before replacing it
+    const bank = await program.account.bank.fetch(solBankAccount);
+    const previous = await program.account.rewardPool.fetch(bank.depositRewardPool);
+    const now = Number((await banksClient.getClock()).unixTimestamp);
+    await bankrunContextWrapper.moveTimeForward(Math.max(previous.endTs.toNumber() - now, 0) + 1);
+
+    // Old pool: 1_000 seconds of emissions, the signer opts in half way through
+    let start = Number((await banksClient.getClock()).unixTimestamp);
+    const oldPool = poolAddresses(new BN(start));
+    await createPool(new BN(start), new BN(start + 1_000), bank.depositRewardPool);
+    await bankrunContextWrapper.moveTimeForward(500);
+    await program.methods
+      .initUserRewards()
+      .accounts({ signer: signer.publicKey, bank: solBankAccount, rewardPool: oldPool.rewardPool })
+      .rpc({ commitment: "confirmed" });
+
+    // Replace it once it has ended, then quadruple the deposit against the new pool. The signer has
+    // not opted into the new pool, so only the pool itself is checkpointed.
+    await bankrunContextWrapper.moveTimeForward(600);
+    start = Number((await banksClient.getClock()).unixTimestamp);
+    const newPool = poolAddresses(new BN(start));
+    await createPool(new BN(start), new BN(start + 1_000), oldPool.rewardPool);
+    await refreshPrices();
+    await program.methods
+      .deposit(new BN(30 * 10 ** 9))
+      .accounts({
+        signer: signer.publicKey,
+        mint: mintSOL,
+        rewardPool: newPool.rewardPool,
+        userRewards: null,
+        priceUpdate,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .remainingAccounts(fallbacks)
+      .rpc({ commitment: "confirmed" });
+
+    // The signer held every share for the second half of the old pool, and only that half is paid
+    await program.methods
+      .claimRewards()
+      .accounts({ signer: signer.publicKey, bank: solBankAccount, rewardPool: oldPool.rewardPool, rewardMint, tokenProgram: TOKEN_PROGRAM_ID })
+      .rpc({ commitment: "confirmed" });
+    const claimed = (await program.account.userRewards.fetch(oldPool.userRewards)).totalClaimed.toNumber();
+    if (claimed > 500 * 100 || claimed < 500 * 100 * 0.99) {
+      throw new Error(`claim on the replaced pool paid ${claimed}, expected the 50_000 emitted after opting in`);
+    }
+
+    // Once the new pool has stopped emitting, deposits and withdrawals no longer need the reward accounts
+    await bankrunContextWrapper.moveTimeForward(1_001);
+    await refreshPrices();
+    await program.methods
+      .withdraw(new BN(10 ** 9))
+      .accounts({ signer: signer.publicKey, mint: mintSOL, rewardPool: null, userRewards: null, tokenProgram: TOKEN_PROGRAM_ID })
+      .rpc({ commitment: "confirmed" });
+    await program.methods
+      .deposit(new BN(10 ** 9))
+      .accounts({
+        signer: signer.publicKey,
+        mint: mintSOL,
+        rewardPool: null,
+        userRewards: null,
+        priceUpdate,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .remainingAccounts(fallbacks)
+      .rpc({ commitment: "confirmed" });
+  });
+});
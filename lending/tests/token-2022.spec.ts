@@ -0,0 +1,92 @@
+import { describe, it } from "node:test";
+import { BN } from "@coral-xyz/anchor";
+import {
+  TOKEN_2022_PROGRAM_ID,
+  ExtensionType,
+  getMintLen,
+  createInitializeMintInstruction,
+  createInitializePermanentDelegateInstruction,
+  createInitializeTransferFeeConfigInstruction,
+} from "@solana/spl-token";
+import { createAccount, mintTo } from "spl-token-bankrun";
+import { PublicKey, Keypair, SystemProgram, Transaction } from "@solana/web3.js";
+import { setupSolMarket } from "../bankrun-utils/solMarket";
+
+describe("Lending - Token-2022 Mints", async () => {
+  const {
+    context,
+    program,
+    banksClient,
+    signer,
+    solUsdPriceFeedAccount,
+  } = await setupSolMarket();
+
+  it("transfer fees are netted out of deposits and unsafe Token-2022 mints are rejected", async () => {
+    // Create a Token-2022 mint carrying a single extension
+    const createToken2022Mint = async (extension: ExtensionType): Promise<PublicKey> => {
+      const mintKeypair = Keypair.generate();
+      const space = getMintLen([extension]);
+      const rent = await banksClient.getRent();
+      const tx = new Transaction().add(
+        SystemProgram.createAccount({
+          fromPubkey: signer.publicKey,
+          newAccountPubkey: mintKeypair.publicKey,
+          space,
+          lamports: Number(rent.minimumBalance(BigInt(space))),
+          programId: TOKEN_2022_PROGRAM_ID,
+        }),
+        extension === ExtensionType.TransferFeeConfig
+          // 1% fee
+          ? createInitializeTransferFeeConfigInstruction(mintKeypair.publicKey, signer.publicKey, signer.publicKey, 100, BigInt(10 ** 12), TOKEN_2022_PROGRAM_ID)
+          : createInitializePermanentDelegateInstruction(mintKeypair.publicKey, signer.publicKey, TOKEN_2022_PROGRAM_ID),
+        createInitializeMintInstruction(mintKeypair.publicKey, 6, signer.publicKey, null, TOKEN_2022_PROGRAM_ID)
+      );
+      tx.recentBlockhash = context.lastBlockhash;
+      tx.feePayer = signer.publicKey;
+      tx.sign(signer, mintKeypair);
+      await banksClient.processTransaction(tx);
+      return mintKeypair.publicKey;
+    };
+
+    const delegateMint = await createToken2022Mint(ExtensionType.PermanentDelegate);
+    let rejected = false;
+    try {
+      await program.methods
+        .initBank(new BN(8000), new BN(7500))
+        .accounts({ signer: signer.publicKey, mint: delegateMint, tokenProgram: TOKEN_2022_PROGRAM_ID })
+        .rpc({ commitment: "confirmed" });
+    } catch (e) {
+      rejected = String(e).includes("UnsupportedMintExtension");
+    }
+    if (!rejected) {
+      throw new Error("mints with a permanent delegate should not be listed");
+    }
+
+    const feeMint = await createToken2022Mint(ExtensionType.TransferFeeConfig);
+    const [feeBank] = PublicKey.findProgramAddressSync([feeMint.toBuffer()], program.programId);
+    await program.methods
+      .initBank(new BN(8000), new BN(7500))
+      .accounts({ signer: signer.publicKey, mint: feeMint, tokenProgram: TOKEN_2022_PROGRAM_ID })
+      .rpc({ commitment: "confirmed" });
+
+    // @ts-ignore
+    const feeTokenAccount = await createAccount(banksClient, signer, feeMint, signer.publicKey, undefined, TOKEN_2022_PROGRAM_ID);
+    // @ts-ignore
+    await mintTo(banksClient, signer, feeMint, feeTokenAccount, signer, 10 * 10 ** 6, [], TOKEN_2022_PROGRAM_ID);
+
+    await program.methods
+      .deposit(new BN(1_000_000))
+      .accounts({
+        signer: signer.publicKey,
+        mint: feeMint,
+        priceUpdate: solUsdPriceFeedAccount,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+      })
+      .rpc({ commitment: "confirmed" });
+
+    const bank = await program.account.bank.fetch(feeBank);
+    if (bank.totalDeposits.toNumber() !== 990_000) {
+      throw new Error("only the amount left after the transfer fee should be credited");
+    }
+  });
+});
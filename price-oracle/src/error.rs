@@ -0,0 +1,25 @@
+use pyth_solana_receiver_sdk::error::GetPriceError;
+
+// Why a price source was rejected. Each program maps these onto its own error codes so existing
+// client error handling keeps working.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OracleError {
+    InvalidFeedId,
+    StalePrice,         // older than the policy allows
+    InvalidPriceUpdate, // for another feed, or not fully verified
+    NonPositivePrice,
+    ConfidenceTooWide,
+    DeviatesFromEma,
+    SourcesDisagree,
+    PriceOverflow,      // not representable at PRICE_EXPONENT
+}
+
+impl From<GetPriceError> for OracleError {
+    fn from(error: GetPriceError) -> Self {
+        match error {
+            GetPriceError::FeedIdMustBe32Bytes | GetPriceError::FeedIdNonHexCharacter => OracleError::InvalidFeedId,
+            GetPriceError::PriceTooOld => OracleError::StalePrice,
+            _ => OracleError::InvalidPriceUpdate,
+        }
+    }
+}
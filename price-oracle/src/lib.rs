@@ -0,0 +1,29 @@
+// Oracle prices as both programs read them: every source (Pyth, the secondary oracle, guardian
+// prices) is normalized to an OraclePrice at PRICE_EXPONENT and checked against one policy per feed,
+// so the lending and gold programs value the same asset identically.
+
+mod error;
+mod price;
+mod sources;
+
+pub use error::OracleError;
+pub use price::{OraclePolicy, OraclePrice, parse_feed_id, scale_to_price_exponent};
+pub use sources::{SecondaryPrice, cross_check, pyth_price, secondary_price, select_price};
+
+// Exponent every source is normalized to (Pyth SOL/USD, USDC/USD and XAU/USD quote 1e-8)
+pub const PRICE_EXPONENT: i32 = -8;
+// Basis points denominator (100% = 10_000 bps)
+pub const BPS_DENOMINATOR: u64 = 10_000;
+// Fresh sources must agree within this band for one of them to be used
+pub const ORACLE_SOURCE_TOLERANCE_BPS: u64 = 200;
+// Largest accepted deviation of the Pyth spot price from its EMA price
+pub const MAX_EMA_DEVIATION_BPS: u64 = 1_000;
+
+pub const SOL_USD_FEED_ID: &str = "0xef0d8b6fda2ceba41da15d4095d1da392a0d2f8ed0c6c7bc0f4cfac8c280b56d";
+
+// SOL is collateral in both programs and must be accepted and valued the same way by each
+pub const SOL_USD_POLICY: OraclePolicy = OraclePolicy {
+    maximum_age: 100,
+    max_confidence_bps: 200,
+    max_ema_deviation_bps: MAX_EMA_DEVIATION_BPS,
+};
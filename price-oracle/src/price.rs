@@ -0,0 +1,89 @@
+use anchor_lang::prelude::*;
+use pyth_solana_receiver_sdk::price_update::{get_feed_id_from_hex, FeedId};
+use crate::error::OracleError;
+use crate::{BPS_DENOMINATOR, ORACLE_SOURCE_TOLERANCE_BPS, PRICE_EXPONENT};
+
+// Acceptance rules for one feed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OraclePolicy {
+    pub maximum_age: u64,           // seconds since publish
+    pub max_confidence_bps: u64,    // widest confidence interval, as bps of the price
+    pub max_ema_deviation_bps: u64, // largest spot deviation from the EMA price
+}
+
+// Positive spot and EMA prices with their confidence intervals, all fixed-point at PRICE_EXPONENT.
+// Sources without an EMA report their spot price as the EMA.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OraclePrice {
+    pub price: u64,
+    pub confidence: u64,
+    pub ema_price: u64,
+    pub ema_confidence: u64,
+    pub publish_time: i64,
+}
+
+impl OraclePrice {
+    // Normalize a price quoted as `price * 10^exponent` with no separate EMA
+    pub fn from_parts(price: i64, conf: u64, exponent: i32, publish_time: i64) -> std::result::Result<Self, OracleError> {
+        if price <= 0 {
+            return Err(OracleError::NonPositivePrice);
+        }
+        let price = scale_to_price_exponent(price as u64, exponent)?;
+        let confidence = scale_to_price_exponent(conf, exponent)?;
+        Ok(Self { price, confidence, ema_price: price, ema_confidence: confidence, publish_time })
+    }
+
+    pub fn is_fresh(&self, clock: &Clock, policy: &OraclePolicy) -> bool {
+        self.publish_time.saturating_add(policy.maximum_age.min(i64::MAX as u64) as i64) >= clock.unix_timestamp
+    }
+
+    pub fn within_confidence(&self, policy: &OraclePolicy) -> bool {
+        (self.confidence as u128) * (BPS_DENOMINATOR as u128) <= (self.price as u128) * (policy.max_confidence_bps as u128)
+    }
+
+    pub fn within_ema_deviation(&self, policy: &OraclePolicy) -> bool {
+        (self.price.abs_diff(self.ema_price) as u128) * (BPS_DENOMINATOR as u128)
+            <= (self.ema_price as u128) * (policy.max_ema_deviation_bps as u128)
+    }
+
+    // Whether two sources agree within ORACLE_SOURCE_TOLERANCE_BPS of the lower price
+    pub fn agrees_with(&self, other: &OraclePrice) -> bool {
+        (self.price.abs_diff(other.price) as u128) * (BPS_DENOMINATOR as u128)
+            <= (self.price.min(other.price) as u128) * (ORACLE_SOURCE_TOLERANCE_BPS as u128)
+    }
+
+    // Bounds of the spot and EMA confidence intervals
+    pub fn lower_bound(&self) -> u64 {
+        self.price.saturating_sub(self.confidence)
+    }
+
+    pub fn upper_bound(&self) -> u64 {
+        self.price.saturating_add(self.confidence)
+    }
+
+    pub fn ema_lower_bound(&self) -> u64 {
+        self.ema_price.saturating_sub(self.ema_confidence)
+    }
+
+    pub fn ema_upper_bound(&self) -> u64 {
+        self.ema_price.saturating_add(self.ema_confidence)
+    }
+}
+
+pub fn parse_feed_id(feed_id: &str) -> std::result::Result<FeedId, OracleError> {
+    get_feed_id_from_hex(feed_id).map_err(OracleError::from)
+}
+
+// Rescale a value quoted with `exponent` to PRICE_EXPONENT, rounding down
+pub fn scale_to_price_exponent(value: u64, exponent: i32) -> std::result::Result<u64, OracleError> {
+    let shift = exponent - PRICE_EXPONENT;
+    let factor = 10u128
+        .checked_pow(shift.unsigned_abs())
+        .ok_or(OracleError::PriceOverflow)?;
+    let scaled = if shift >= 0 {
+        (value as u128).checked_mul(factor).ok_or(OracleError::PriceOverflow)?
+    } else {
+        value as u128 / factor
+    };
+    u64::try_from(scaled).map_err(|_| OracleError::PriceOverflow)
+}
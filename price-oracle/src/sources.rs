@@ -0,0 +1,239 @@
+use anchor_lang::prelude::*;
+use pyth_solana_receiver_sdk::price_update::{FeedId, PriceUpdateV2};
+use crate::error::OracleError;
+use crate::price::{OraclePolicy, OraclePrice};
+
+// Price account layout read from the secondary oracle program: feed id, price, confidence,
+// exponent and publish time, borsh encoded at the start of the account data
+#[derive(AnchorDeserialize, Clone, Debug)]
+pub struct SecondaryPrice {
+    pub feed_id: FeedId,
+    pub price: i64,
+    pub conf: u64,
+    pub exponent: i32,
+    pub publish_time: i64,
+}
+
+// Fresh, fully verified Pyth price for the feed that passes the policy's confidence and EMA checks
+pub fn pyth_price(
+    price_update: &PriceUpdateV2,
+    feed_id: &FeedId,
+    policy: &OraclePolicy,
+    clock: &Clock,
+) -> std::result::Result<OraclePrice, OracleError> {
+    let price = price_update.get_price_no_older_than(clock, policy.maximum_age, feed_id)?;
+    let mut oracle_price = OraclePrice::from_parts(price.price, price.conf, price.exponent, price.publish_time)?;
+    if !oracle_price.within_confidence(policy) {
+        return Err(OracleError::ConfidenceTooWide);
+    }
+
+    let message = &price_update.price_message;
+    let ema = OraclePrice::from_parts(message.ema_price, message.ema_conf, price.exponent, price.publish_time)?;
+    oracle_price.ema_price = ema.price;
+    oracle_price.ema_confidence = ema.confidence;
+    if !oracle_price.within_ema_deviation(policy) {
+        return Err(OracleError::DeviatesFromEma);
+    }
+    Ok(oracle_price)
+}
+
+// Fresh price for the feed from `account`, if it is owned by the secondary oracle program
+pub fn secondary_price(
+    account: &AccountInfo,
+    secondary_oracle_program: &Pubkey,
+    feed_id: &FeedId,
+    policy: &OraclePolicy,
+    clock: &Clock,
+) -> Option<OraclePrice> {
+    if *secondary_oracle_program == Pubkey::default() || account.owner != secondary_oracle_program {
+        return None;
+    }
+    let data = account.try_borrow_data().ok()?;
+    let secondary = SecondaryPrice::deserialize(&mut &data[..]).ok()?;
+    if secondary.feed_id != *feed_id {
+        return None;
+    }
+    OraclePrice::from_parts(secondary.price, secondary.conf, secondary.exponent, secondary.publish_time)
+        .ok()
+        .filter(|price| price.is_fresh(clock, policy) && price.within_confidence(policy))
+}
+
+// `primary` as long as every other fresh source agrees with it. The other sources can fail the
+// price but never move it, so callers cannot steer it through the accounts they pass.
+pub fn cross_check(primary: OraclePrice, others: &[OraclePrice]) -> std::result::Result<OraclePrice, OracleError> {
+    if others.iter().all(|other| primary.agrees_with(other)) {
+        Ok(primary)
+    } else {
+        Err(OracleError::SourcesDisagree)
+    }
+}
+
+// First candidate, in priority order, that agrees with every other fresh candidate
+pub fn select_price(candidates: &[OraclePrice]) -> std::result::Result<OraclePrice, OracleError> {
+    candidates
+        .iter()
+        .find(|candidate| candidates.iter().all(|other| candidate.agrees_with(other)))
+        .copied()
+        .ok_or(OracleError::SourcesDisagree)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pyth_solana_receiver_sdk::price_update::{PriceFeedMessage, VerificationLevel};
+
+    const FEED: FeedId = [7; 32];
+    const OTHER_FEED: FeedId = [8; 32];
+    const NOW: i64 = 1_700_000_000;
+    const POLICY: OraclePolicy = OraclePolicy { maximum_age: 60, max_confidence_bps: 100, max_ema_deviation_bps: 500 };
+
+    fn clock() -> Clock {
+        Clock { unix_timestamp: NOW, ..Clock::default() }
+    }
+
+    // Fully verified update for FEED quoted at expo -6
+    fn update(price: i64, conf: u64, ema_price: i64, publish_time: i64) -> PriceUpdateV2 {
+        PriceUpdateV2 {
+            write_authority: Pubkey::default(),
+            verification_level: VerificationLevel::Full,
+            price_message: PriceFeedMessage {
+                feed_id: FEED,
+                price,
+                conf,
+                exponent: -6,
+                publish_time,
+                prev_publish_time: publish_time - 1,
+                ema_price,
+                ema_conf: conf,
+            },
+            posted_slot: 0,
+        }
+    }
+
+    fn secondary_data(feed_id: FeedId, price: i64, conf: u64, publish_time: i64) -> Vec<u8> {
+        let mut data = feed_id.to_vec();
+        data.extend_from_slice(&price.to_le_bytes());
+        data.extend_from_slice(&conf.to_le_bytes());
+        data.extend_from_slice(&(-6i32).to_le_bytes());
+        data.extend_from_slice(&publish_time.to_le_bytes());
+        data
+    }
+
+    fn read_secondary(owner: Pubkey, program: Pubkey, mut data: Vec<u8>) -> Option<OraclePrice> {
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let account = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0);
+        secondary_price(&account, &program, &FEED, &POLICY, &clock())
+    }
+
+    fn usd(price: u64) -> OraclePrice {
+        OraclePrice::from_parts(price as i64, 0, 0, NOW).unwrap()
+    }
+
+    #[test]
+    fn pyth_price_is_normalized_with_its_ema() {
+        let price = pyth_price(&update(150_000_000, 1_000_000, 149_000_000, NOW), &FEED, &POLICY, &clock()).unwrap();
+        assert_eq!(price.price, 15_000_000_000);
+        assert_eq!(price.confidence, 100_000_000);
+        assert_eq!(price.ema_price, 14_900_000_000);
+        assert_eq!(price.ema_confidence, 100_000_000);
+        assert_eq!(price.publish_time, NOW);
+    }
+
+    #[test]
+    fn pyth_price_older_than_the_policy_is_stale() {
+        let at_limit = update(150_000_000, 0, 150_000_000, NOW - 60);
+        assert!(pyth_price(&at_limit, &FEED, &POLICY, &clock()).is_ok());
+
+        let stale = update(150_000_000, 0, 150_000_000, NOW - 61);
+        assert_eq!(pyth_price(&stale, &FEED, &POLICY, &clock()), Err(OracleError::StalePrice));
+    }
+
+    #[test]
+    fn pyth_update_for_another_feed_or_partially_verified_is_invalid() {
+        let fresh = update(150_000_000, 0, 150_000_000, NOW);
+        assert_eq!(pyth_price(&fresh, &OTHER_FEED, &POLICY, &clock()), Err(OracleError::InvalidPriceUpdate));
+
+        let partial = PriceUpdateV2 { verification_level: VerificationLevel::Partial { num_signatures: 3 }, ..fresh };
+        assert_eq!(pyth_price(&partial, &FEED, &POLICY, &clock()), Err(OracleError::InvalidPriceUpdate));
+    }
+
+    #[test]
+    fn pyth_price_must_be_positive() {
+        let zero = update(0, 0, 150_000_000, NOW);
+        assert_eq!(pyth_price(&zero, &FEED, &POLICY, &clock()), Err(OracleError::NonPositivePrice));
+    }
+
+    #[test]
+    fn pyth_confidence_wider_than_the_policy_is_rejected() {
+        // 1% of 150 USD is exactly the limit
+        let at_limit = update(150_000_000, 1_500_000, 150_000_000, NOW);
+        assert!(pyth_price(&at_limit, &FEED, &POLICY, &clock()).is_ok());
+
+        let wide = update(150_000_000, 1_500_001, 150_000_000, NOW);
+        assert_eq!(pyth_price(&wide, &FEED, &POLICY, &clock()), Err(OracleError::ConfidenceTooWide));
+    }
+
+    #[test]
+    fn pyth_price_too_far_from_its_ema_is_rejected() {
+        // 5% above and below an EMA of 100 USD is exactly the limit
+        assert!(pyth_price(&update(105_000_000, 0, 100_000_000, NOW), &FEED, &POLICY, &clock()).is_ok());
+        assert!(pyth_price(&update(95_000_000, 0, 100_000_000, NOW), &FEED, &POLICY, &clock()).is_ok());
+
+        let above = update(105_000_001, 0, 100_000_000, NOW);
+        assert_eq!(pyth_price(&above, &FEED, &POLICY, &clock()), Err(OracleError::DeviatesFromEma));
+        let below = update(94_999_999, 0, 100_000_000, NOW);
+        assert_eq!(pyth_price(&below, &FEED, &POLICY, &clock()), Err(OracleError::DeviatesFromEma));
+    }
+
+    #[test]
+    fn secondary_price_is_read_from_the_configured_program() {
+        let program = Pubkey::new_unique();
+        let price = read_secondary(program, program, secondary_data(FEED, 150_000_000, 1_000_000, NOW)).unwrap();
+        assert_eq!(price.price, 15_000_000_000);
+        assert_eq!(price.confidence, 100_000_000);
+        assert_eq!(price.ema_price, price.price);
+    }
+
+    #[test]
+    fn secondary_price_from_another_program_or_without_one_is_ignored() {
+        let program = Pubkey::new_unique();
+        let data = secondary_data(FEED, 150_000_000, 0, NOW);
+        assert_eq!(read_secondary(Pubkey::new_unique(), program, data.clone()), None);
+        assert_eq!(read_secondary(Pubkey::default(), Pubkey::default(), data), None);
+    }
+
+    #[test]
+    fn secondary_price_must_be_for_the_feed_fresh_and_narrow() {
+        let program = Pubkey::new_unique();
+        assert_eq!(read_secondary(program, program, secondary_data(OTHER_FEED, 150_000_000, 0, NOW)), None);
+        assert_eq!(read_secondary(program, program, secondary_data(FEED, 150_000_000, 0, NOW - 61)), None);
+        assert_eq!(read_secondary(program, program, secondary_data(FEED, 150_000_000, 1_500_001, NOW)), None);
+        assert_eq!(read_secondary(program, program, secondary_data(FEED, -1, 0, NOW)), None);
+        assert_eq!(read_secondary(program, program, vec![0; 16]), None);
+    }
+
+    #[test]
+    fn cross_check_keeps_the_primary_price_while_the_others_agree() {
+        assert_eq!(cross_check(usd(10_000), &[]), Ok(usd(10_000)));
+        assert_eq!(cross_check(usd(10_000), &[usd(10_150), usd(9_850)]), Ok(usd(10_000)));
+        // 101.5 agrees with both, but the primary price is never replaced by it
+        assert_eq!(cross_check(usd(10_000), &[usd(10_150), usd(10_300)]), Err(OracleError::SourcesDisagree));
+    }
+
+    #[test]
+    fn select_price_prefers_the_first_agreeing_source() {
+        assert_eq!(select_price(&[usd(100)]), Ok(usd(100)));
+        // 2% apart is within tolerance, so the higher priority source wins
+        assert_eq!(select_price(&[usd(102), usd(100)]), Ok(usd(102)));
+        // 100 and 103 disagree, but 101.5 is within tolerance of both
+        let candidates = [usd(10_000), usd(10_150), usd(10_300)];
+        assert_eq!(select_price(&candidates), Ok(usd(10_150)));
+    }
+
+    #[test]
+    fn select_price_without_agreement_is_rejected() {
+        assert_eq!(select_price(&[usd(100), usd(103)]), Err(OracleError::SourcesDisagree));
+        assert_eq!(select_price(&[]), Err(OracleError::SourcesDisagree));
+    }
+}
@@ -1,20 +1,41 @@
 use anchor_lang::prelude::*;
+use price_oracle::OraclePolicy;
 
 pub const SEED_CONFIG_ACCOUNT: &[u8] = b"config";
 pub const SEED_COLLATERAL_ACCOUNT: &[u8] = b"collateral";
 pub const SEED_SOL_ACCOUNT: &[u8] = b"sol";
 pub const SEED_MINT_ACCOUNT: &[u8] = b"mint";
+pub const SEED_MANUAL_PRICE_ACCOUNT: &[u8] = b"manual_price";
 
 #[constant]
 pub const GOLD_FEED_ID: &str = "0x765d2ba906dbc32ca17cc11f5310a89e9ee1f6420508c63861f2f8ba4ee34bb2";
-pub const SOL_FEED_ID: &str = "0xef0d8b6fda2ceba41da15d4095d1da392a0d2f8ed0c6c7bc0f4cfac8c280b56d";
-pub const MAXIMUM_AGE: u64 = 7200; // allow pricefeed 2 hours old to avoid stale price errors in tests
-pub const PRICE_FEED_DECIMAL_ADJUSTMENT: u128 = 10; // price feed returns 1e8, multiple by 10 to match lamports 10e9
+pub const SOL_FEED_ID: &str = price_oracle::SOL_USD_FEED_ID;
+pub const SOL_DECIMALS: u8 = 9; // lamports per SOL
+// Default oracle acceptance rules per feed for a new Config, which holds each feed's staleness limit,
+// confidence bound and EMA deviation bound. XAU/USD stops updating while gold markets are closed; a
+// GOLD price older than its limit puts the program in market-closed mode rather than failing outright.
+// SOL/USD uses the same policy as the lending program.
+pub const GOLD_PRICE_POLICY: OraclePolicy = OraclePolicy {
+    maximum_age: 300,
+    max_confidence_bps: 100, // reject GOLD prices with confidence wider than 1%
+    max_ema_deviation_bps: price_oracle::MAX_EMA_DEVIATION_BPS,
+};
+pub const SOL_PRICE_POLICY: OraclePolicy = price_oracle::SOL_USD_POLICY;
+pub const MAX_MANUAL_PRICE_VALIDITY: i64 = 900; // guardian prices expire within 15 minutes
+// Circuit breaker defaults: a move of more than 10% within 5 minutes pauses minting and liquidation
+// until the guardian resets it or prices hold for 30 minutes
+pub const MAX_PRICE_MOVE_BPS: u64 = 1_000;
+pub const PRICE_MOVE_WINDOW: i64 = 300;
+pub const BREAKER_HOLD_PERIOD: i64 = 1_800;
 
 // Constants for configuration values
 pub const LIQUIDATION_THRESHOLD: u64 = 50; // 200% over-collateralized
 pub const LIQUIDATION_BONUS: u64 = 10; // 10% bonus lamports when liquidating
-pub const MIN_HEALTH_FACTOR: u64 = 1;
+pub const MIN_HEALTH_FACTOR: u64 = 10_000; // bps, 1.0
+// Bounds enforced by update_config
+pub const MAX_LIQUIDATION_BONUS: u64 = 25;
+pub const MAX_PRICE_AGE: u64 = 604_800; // a week, long enough for a closed market over a holiday weekend
+pub const MAX_BREAKER_PERIOD: i64 = 86_400;
 pub const MINT_DECIMALS: u8 = 9;
 
 
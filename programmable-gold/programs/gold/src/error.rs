@@ -8,4 +8,43 @@ pub enum CustomError {
     AboveMinimumHealthFactor,
     #[msg("Price should not be negative")]
     InvalidPrice,
+    #[msg("Price confidence interval is too wide")]
+    PriceConfidenceTooWide,
+    #[msg("Price deviates too far from its EMA price")]
+    PriceDeviatesFromEma,
+    #[msg("Fresh price sources disagree")]
+    OracleSourcesDisagree,
+    #[msg("Manual price must be positive and expire soon")]
+    InvalidManualPrice,
+    #[msg("Signer is not the guardian")]
+    NotGuardian,
+    #[msg("Signer is not the config authority")]
+    NotAuthority,
+    #[msg("Config value out of bounds")]
+    InvalidConfig,
+    #[msg("No fresh oracle price available")]
+    StalePrice,
+    #[msg("GOLD market is closed, minting, withdrawing and liquidating are disabled")]
+    MarketClosed,
+    #[msg("Circuit breaker tripped by a sudden price move, minting, liquidation and withdrawals are paused")]
+    CircuitBreakerTripped,
+    #[msg("Price update is for another feed or not fully verified")]
+    InvalidPriceUpdate,
+    #[msg("Oracle feed id is malformed")]
+    InvalidFeedId,
+}
+
+impl From<price_oracle::OracleError> for CustomError {
+    fn from(error: price_oracle::OracleError) -> Self {
+        use price_oracle::OracleError;
+        match error {
+            OracleError::ConfidenceTooWide => CustomError::PriceConfidenceTooWide,
+            OracleError::DeviatesFromEma => CustomError::PriceDeviatesFromEma,
+            OracleError::SourcesDisagree => CustomError::OracleSourcesDisagree,
+            OracleError::InvalidPriceUpdate => CustomError::InvalidPriceUpdate,
+            OracleError::NonPositivePrice | OracleError::PriceOverflow => CustomError::InvalidPrice,
+            OracleError::InvalidFeedId => CustomError::InvalidFeedId,
+            OracleError::StalePrice => CustomError::StalePrice,
+        }
+    }
 }
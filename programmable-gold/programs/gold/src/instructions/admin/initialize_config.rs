@@ -2,7 +2,11 @@ use anchor_lang::prelude::*;
 use anchor_spl::token_interface::{
    Mint, Token2022,
 };
-use crate::{Config, LIQUIDATION_BONUS, LIQUIDATION_THRESHOLD, MINT_DECIMALS, MIN_HEALTH_FACTOR, SEED_CONFIG_ACCOUNT, SEED_MINT_ACCOUNT};
+use crate::{
+    Config, RecordedPrice, BREAKER_HOLD_PERIOD, GOLD_PRICE_POLICY, LIQUIDATION_BONUS, LIQUIDATION_THRESHOLD,
+    MAX_PRICE_MOVE_BPS, MINT_DECIMALS, MIN_HEALTH_FACTOR, PRICE_MOVE_WINDOW, SEED_CONFIG_ACCOUNT,
+    SEED_MINT_ACCOUNT, SOL_PRICE_POLICY,
+};
 
 #[derive(Accounts)]
 pub struct InitializeConfig<'info> {
@@ -41,6 +45,20 @@ pub fn process_initialize_config(ctx: Context<InitializeConfig>) -> Result<()> {
         min_health_factor: MIN_HEALTH_FACTOR,
         bump: ctx.bumps.config_account,
         bump_mint_account:  ctx.bumps.mint_account,
+        guardian: ctx.accounts.authority.key(),
+        secondary_oracle_program: Pubkey::default(),
+        gold_max_price_age: GOLD_PRICE_POLICY.maximum_age,
+        sol_max_price_age: SOL_PRICE_POLICY.maximum_age,
+        gold_max_confidence_bps: GOLD_PRICE_POLICY.max_confidence_bps,
+        gold_max_ema_deviation_bps: GOLD_PRICE_POLICY.max_ema_deviation_bps,
+        sol_max_confidence_bps: SOL_PRICE_POLICY.max_confidence_bps,
+        sol_max_ema_deviation_bps: SOL_PRICE_POLICY.max_ema_deviation_bps,
+        last_gold_price: RecordedPrice::default(),
+        last_sol_price: RecordedPrice::default(),
+        max_price_move_bps: MAX_PRICE_MOVE_BPS,
+        price_move_window: PRICE_MOVE_WINDOW,
+        breaker_hold_period: BREAKER_HOLD_PERIOD,
+        breaker_tripped_at: 0,
     };
     msg!("Initialized Config Account:{:#?}", ctx.accounts.config_account);
     Ok(())
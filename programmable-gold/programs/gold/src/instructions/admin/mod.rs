@@ -2,3 +2,7 @@ pub mod initialize_config;
 pub use initialize_config::*;
 pub mod update_config;
 pub use update_config::*;
+pub mod set_manual_price;
+pub use set_manual_price::*;
+pub mod reset_circuit_breaker;
+pub use reset_circuit_breaker::*;
@@ -0,0 +1,24 @@
+use crate::{error::CustomError, Config, SEED_CONFIG_ACCOUNT};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct ResetCircuitBreaker<'info> {
+    pub guardian: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_CONFIG_ACCOUNT],
+        bump = config_account.bump,
+        has_one = guardian @ CustomError::NotGuardian,
+    )]
+    pub config_account: Account<'info, Config>,
+}
+
+// Resume minting, liquidation and withdrawals once the guardian has confirmed a price move is genuine. The prices
+// recorded when the breaker tripped stay as the reference for the next move.
+pub fn process_reset_circuit_breaker(ctx: Context<ResetCircuitBreaker>) -> Result<()> {
+    let config_account = &mut ctx.accounts.config_account;
+    msg!("Reset circuit breaker tripped at {}", config_account.breaker_tripped_at);
+    config_account.breaker_tripped_at = 0;
+    Ok(())
+}
@@ -0,0 +1,52 @@
+use crate::{error::CustomError, Config, ManualPrice, MAX_MANUAL_PRICE_VALIDITY, SEED_CONFIG_ACCOUNT, SEED_MANUAL_PRICE_ACCOUNT};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+#[instruction(feed_id: [u8; 32])]
+pub struct SetManualPrice<'info> {
+    #[account(mut)]
+    pub guardian: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_CONFIG_ACCOUNT],
+        bump = config_account.bump,
+        has_one = guardian @ CustomError::NotGuardian,
+    )]
+    pub config_account: Account<'info, Config>,
+    #[account(
+        init_if_needed,
+        payer = guardian,
+        space = 8 + ManualPrice::INIT_SPACE,
+        seeds = [SEED_MANUAL_PRICE_ACCOUNT, feed_id.as_ref()],
+        bump,
+    )]
+    pub manual_price: Account<'info, ManualPrice>,
+    pub system_program: Program<'info, System>,
+}
+
+// Last-resort price for a feed, used only when Pyth and the secondary oracle are stale or agree with it.
+// Pass the ManualPrice account as a remaining account to instructions that need the price.
+pub fn process_set_manual_price(
+    ctx: Context<SetManualPrice>,
+    feed_id: [u8; 32],
+    price: i64,
+    conf: u64,
+    exponent: i32,
+    valid_for: i64,
+) -> Result<()> {
+    require!(price > 0, CustomError::InvalidManualPrice);
+    require!(valid_for > 0 && valid_for <= MAX_MANUAL_PRICE_VALIDITY, CustomError::InvalidManualPrice);
+
+    let now = Clock::get()?.unix_timestamp;
+    *ctx.accounts.manual_price = ManualPrice {
+        feed_id,
+        price,
+        conf,
+        exponent,
+        publish_time: now,
+        expires_at: now + valid_for,
+        bump: ctx.bumps.manual_price,
+    };
+    msg!("Set Manual Price:{:#?}", ctx.accounts.manual_price);
+    Ok(())
+}
@@ -1,21 +1,154 @@
-use crate::{Config, SEED_CONFIG_ACCOUNT};
+use crate::{
+    error::CustomError, Config, MAX_BREAKER_PERIOD, MAX_LIQUIDATION_BONUS, MAX_PRICE_AGE, SEED_CONFIG_ACCOUNT,
+};
 use anchor_lang::prelude::*;
+use price_oracle::BPS_DENOMINATOR;
 
 #[derive(Accounts)]
 pub struct UpdateConfig<'info> {
+    pub authority: Signer<'info>,
+
     #[account(
         mut,
         seeds = [SEED_CONFIG_ACCOUNT],
         bump = config_account.bump,
+        has_one = authority @ CustomError::NotAuthority,
     )]
     pub config_account: Account<'info, Config>,
 }
 
-// Change health factor to test liquidate instruction, no authority check, anyone can invoke
-pub fn process_update_config(ctx: Context<UpdateConfig>, min_health_factor: u64) -> Result<()> {
+// Values to change, fields left as None keep their current value
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default)]
+pub struct ConfigUpdate {
+    pub liquidation_threshold: Option<u64>,
+    pub liquidation_bonus: Option<u64>,
+    pub min_health_factor: Option<u64>,
+    pub guardian: Option<Pubkey>,
+    pub secondary_oracle_program: Option<Pubkey>,
+    pub gold_max_price_age: Option<u64>,
+    pub sol_max_price_age: Option<u64>,
+    pub gold_max_confidence_bps: Option<u64>,
+    pub gold_max_ema_deviation_bps: Option<u64>,
+    pub sol_max_confidence_bps: Option<u64>,
+    pub sol_max_ema_deviation_bps: Option<u64>,
+    pub max_price_move_bps: Option<u64>,
+    pub price_move_window: Option<i64>,
+    pub breaker_hold_period: Option<i64>,
+}
+
+// Tunable config values, as recorded in ConfigUpdated
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ConfigValues {
+    pub liquidation_threshold: u64,
+    pub liquidation_bonus: u64,
+    pub min_health_factor: u64,
+    pub guardian: Pubkey,
+    pub secondary_oracle_program: Pubkey,
+    pub gold_max_price_age: u64,
+    pub sol_max_price_age: u64,
+    pub gold_max_confidence_bps: u64,
+    pub gold_max_ema_deviation_bps: u64,
+    pub sol_max_confidence_bps: u64,
+    pub sol_max_ema_deviation_bps: u64,
+    pub max_price_move_bps: u64,
+    pub price_move_window: i64,
+    pub breaker_hold_period: i64,
+}
+
+impl ConfigValues {
+    fn from_config(config: &Config) -> Self {
+        Self {
+            liquidation_threshold: config.liquidation_threshold,
+            liquidation_bonus: config.liquidation_bonus,
+            min_health_factor: config.min_health_factor,
+            guardian: config.guardian,
+            secondary_oracle_program: config.secondary_oracle_program,
+            gold_max_price_age: config.gold_max_price_age,
+            sol_max_price_age: config.sol_max_price_age,
+            gold_max_confidence_bps: config.gold_max_confidence_bps,
+            gold_max_ema_deviation_bps: config.gold_max_ema_deviation_bps,
+            sol_max_confidence_bps: config.sol_max_confidence_bps,
+            sol_max_ema_deviation_bps: config.sol_max_ema_deviation_bps,
+            max_price_move_bps: config.max_price_move_bps,
+            price_move_window: config.price_move_window,
+            breaker_hold_period: config.breaker_hold_period,
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        require!((1..=100).contains(&self.liquidation_threshold), CustomError::InvalidConfig);
+        require!(self.liquidation_bonus <= MAX_LIQUIDATION_BONUS, CustomError::InvalidConfig);
+        // Below 1.0 positions past the liquidation threshold could no longer be liquidated
+        require!(self.min_health_factor >= BPS_DENOMINATOR, CustomError::InvalidConfig);
+        // Without a guardian manual prices could not be set nor the circuit breaker reset
+        require!(self.guardian != Pubkey::default(), CustomError::InvalidConfig);
+        require!((1..=MAX_PRICE_AGE).contains(&self.gold_max_price_age), CustomError::InvalidConfig);
+        require!((1..=MAX_PRICE_AGE).contains(&self.sol_max_price_age), CustomError::InvalidConfig);
+        for bound in [
+            self.gold_max_confidence_bps,
+            self.gold_max_ema_deviation_bps,
+            self.sol_max_confidence_bps,
+            self.sol_max_ema_deviation_bps,
+        ] {
+            require!((1..=BPS_DENOMINATOR).contains(&bound), CustomError::InvalidConfig);
+        }
+        require!(self.max_price_move_bps <= BPS_DENOMINATOR, CustomError::InvalidConfig);
+        require!((0..=MAX_BREAKER_PERIOD).contains(&self.price_move_window), CustomError::InvalidConfig);
+        require!((0..=MAX_BREAKER_PERIOD).contains(&self.breaker_hold_period), CustomError::InvalidConfig);
+        Ok(())
+    }
+}
+
+#[event]
+pub struct ConfigUpdated {
+    pub authority: Pubkey,
+    pub old: ConfigValues,
+    pub new: ConfigValues,
+}
+
+// Change any of the tunable config values, restricted to the config authority. The gold program
+// charges no fees, so there are none to configure here.
+pub fn process_update_config(ctx: Context<UpdateConfig>, update: ConfigUpdate) -> Result<()> {
     let config_account = &mut ctx.accounts.config_account;
-    config_account.min_health_factor = min_health_factor;
+    let old = ConfigValues::from_config(config_account);
+    let new = ConfigValues {
+        liquidation_threshold: update.liquidation_threshold.unwrap_or(old.liquidation_threshold),
+        liquidation_bonus: update.liquidation_bonus.unwrap_or(old.liquidation_bonus),
+        min_health_factor: update.min_health_factor.unwrap_or(old.min_health_factor),
+        guardian: update.guardian.unwrap_or(old.guardian),
+        secondary_oracle_program: update.secondary_oracle_program.unwrap_or(old.secondary_oracle_program),
+        gold_max_price_age: update.gold_max_price_age.unwrap_or(old.gold_max_price_age),
+        sol_max_price_age: update.sol_max_price_age.unwrap_or(old.sol_max_price_age),
+        gold_max_confidence_bps: update.gold_max_confidence_bps.unwrap_or(old.gold_max_confidence_bps),
+        gold_max_ema_deviation_bps: update.gold_max_ema_deviation_bps.unwrap_or(old.gold_max_ema_deviation_bps),
+        sol_max_confidence_bps: update.sol_max_confidence_bps.unwrap_or(old.sol_max_confidence_bps),
+        sol_max_ema_deviation_bps: update.sol_max_ema_deviation_bps.unwrap_or(old.sol_max_ema_deviation_bps),
+        max_price_move_bps: update.max_price_move_bps.unwrap_or(old.max_price_move_bps),
+        price_move_window: update.price_move_window.unwrap_or(old.price_move_window),
+        breaker_hold_period: update.breaker_hold_period.unwrap_or(old.breaker_hold_period),
+    };
+    new.validate()?;
+
+    config_account.liquidation_threshold = new.liquidation_threshold;
+    config_account.liquidation_bonus = new.liquidation_bonus;
+    config_account.min_health_factor = new.min_health_factor;
+    config_account.guardian = new.guardian;
+    config_account.secondary_oracle_program = new.secondary_oracle_program;
+    config_account.gold_max_price_age = new.gold_max_price_age;
+    config_account.sol_max_price_age = new.sol_max_price_age;
+    config_account.gold_max_confidence_bps = new.gold_max_confidence_bps;
+    config_account.gold_max_ema_deviation_bps = new.gold_max_ema_deviation_bps;
+    config_account.sol_max_confidence_bps = new.sol_max_confidence_bps;
+    config_account.sol_max_ema_deviation_bps = new.sol_max_ema_deviation_bps;
+    config_account.max_price_move_bps = new.max_price_move_bps;
+    config_account.price_move_window = new.price_move_window;
+    config_account.breaker_hold_period = new.breaker_hold_period;
 
-    msg!("Update Config Account:{:#?}", ctx.accounts.config_account);
+    msg!("Update Config Account:{:#?}", config_account);
+    emit!(ConfigUpdated {
+        authority: ctx.accounts.authority.key(),
+        old,
+        new,
+    });
     Ok(())
 }
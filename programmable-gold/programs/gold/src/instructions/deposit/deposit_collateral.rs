@@ -0,0 +1,61 @@
+use crate::{deposit_sol_internal, Collateral, Config, SEED_COLLATERAL_ACCOUNT, SEED_CONFIG_ACCOUNT, SEED_SOL_ACCOUNT};
+use anchor_lang::prelude::*;
+use anchor_spl::{associated_token::get_associated_token_address_with_program_id, token_interface::Token2022};
+use decimal::MathError;
+
+#[derive(Accounts)]
+pub struct DepositCollateral<'info> {
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_CONFIG_ACCOUNT],
+        bump = config_account.bump,
+    )]
+    pub config_account: Account<'info, Config>,
+    #[account(
+        init_if_needed,
+        payer = depositor,
+        space = 8 + Collateral::INIT_SPACE,
+        seeds = [SEED_COLLATERAL_ACCOUNT, depositor.key().as_ref()],
+        bump,
+    )]
+    pub collateral_account: Account<'info, Collateral>,
+    #[account(
+        mut,
+        seeds = [SEED_SOL_ACCOUNT, depositor.key().as_ref()],
+        bump,
+    )]
+    pub sol_account: SystemAccount<'info>,
+    pub token_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
+}
+
+// Adding collateral only makes a position safer, so it needs no prices
+pub fn process_deposit_collateral(ctx: Context<DepositCollateral>, amount_collateral: u64) -> Result<()> {
+    let sol_lamports = ctx.accounts.sol_account.lamports();
+    // GOLD for this position is minted to the depositor's associated token account, which may not
+    // exist yet
+    let token_account = get_associated_token_address_with_program_id(
+        &ctx.accounts.depositor.key(),
+        &ctx.accounts.config_account.mint_account,
+        &ctx.accounts.token_program.key(),
+    );
+
+    let collateral_account = &mut ctx.accounts.collateral_account;
+    collateral_account.lamport_balance = sol_lamports.checked_add(amount_collateral).ok_or(MathError::Overflow)?;
+    collateral_account.initialize_if_needed(
+        ctx.accounts.depositor.key(),
+        ctx.accounts.sol_account.key(),
+        token_account,
+        ctx.bumps.collateral_account,
+        ctx.bumps.sol_account,
+    );
+
+    deposit_sol_internal(
+        &ctx.accounts.depositor,
+        &ctx.accounts.sol_account,
+        &ctx.accounts.system_program,
+        amount_collateral,
+    )
+}
@@ -1,6 +1,6 @@
 use crate::{
-    check_health_factor, deposit_sol_internal, mint_tokens_internal, Collateral, Config,
-    SEED_COLLATERAL_ACCOUNT, SEED_CONFIG_ACCOUNT, SEED_SOL_ACCOUNT,
+    check_health_factor, deposit_sol_internal, mint_tokens_internal, refresh_prices, Collateral, Config,
+    PriceSources, SEED_COLLATERAL_ACCOUNT, SEED_CONFIG_ACCOUNT, SEED_SOL_ACCOUNT,
 };
 use anchor_lang::prelude::*;
 use anchor_spl::{
@@ -15,6 +15,7 @@ pub struct DepositCollateralAndMintTokens<'info> {
     pub depositor: Signer<'info>,
 
     #[account(
+        mut,
         seeds = [SEED_CONFIG_ACCOUNT],
         bump = config_account.bump,
         has_one = mint_account
@@ -56,10 +57,7 @@ pub fn process_deposit_collateral_and_mint_tokens(
     amount_collateral: u64,
     amount_to_mint: u64,
 ) -> Result<()> {
-    let sol_lamports = {
-        let lamports_ref = ctx.accounts.sol_account.to_account_info().lamports.borrow();
-        *lamports_ref
-    };
+    let sol_lamports = ctx.accounts.sol_account.lamports();
     let config_bump_mint = {
         ctx.accounts.config_account.bump_mint_account
     };
@@ -68,21 +66,27 @@ pub fn process_deposit_collateral_and_mint_tokens(
     collateral_account.lamport_balance = sol_lamports + amount_collateral;
     collateral_account.amount_minted += amount_to_mint;
 
-    if !collateral_account.is_initialized {
-        collateral_account.is_initialized = true;
-        collateral_account.depositor = ctx.accounts.depositor.key();
-        collateral_account.sol_account = ctx.accounts.sol_account.key();
-        collateral_account.token_account = ctx.accounts.token_account.key();
-        collateral_account.bump = ctx.bumps.collateral_account;
-        collateral_account.bump_sol_account = ctx.bumps.sol_account;
-    }
+    collateral_account.initialize_if_needed(
+        ctx.accounts.depositor.key(),
+        ctx.accounts.sol_account.key(),
+        ctx.accounts.token_account.key(),
+        ctx.bumps.collateral_account,
+        ctx.bumps.sol_account,
+    );
 
-    check_health_factor(
-        collateral_account,
-        &ctx.accounts.config_account,
+    let price_sources = PriceSources::new(
         &ctx.accounts.gold_price_update,
         &ctx.accounts.sol_price_update,
-    )?;
+        ctx.remaining_accounts,
+        &ctx.accounts.config_account,
+    );
+    // Minting needs live prices. Collateral can still be added while the circuit breaker is tripped,
+    // or while the GOLD market is closed, valued at the last recorded price
+    let price_status = refresh_prices(&mut ctx.accounts.config_account, &price_sources)?;
+    if amount_to_mint > 0 {
+        price_status.require_live_prices()?;
+    }
+    check_health_factor(collateral_account, &ctx.accounts.config_account, &price_status.prices)?;
 
     deposit_sol_internal(
         &ctx.accounts.depositor,
@@ -0,0 +1,74 @@
+use crate::{
+    check_health_factor, mint_tokens_internal, refresh_prices, Collateral, Config, PriceSources,
+    SEED_COLLATERAL_ACCOUNT, SEED_CONFIG_ACCOUNT,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, Token2022, TokenAccount},
+};
+use decimal::MathError;
+use pyth_solana_receiver_sdk::price_update::PriceUpdateV2;
+
+#[derive(Accounts)]
+pub struct MintGold<'info> {
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_CONFIG_ACCOUNT],
+        bump = config_account.bump,
+        has_one = mint_account
+    )]
+    pub config_account: Box<Account<'info, Config>>,
+    #[account(
+        mut,
+        seeds = [SEED_COLLATERAL_ACCOUNT, depositor.key().as_ref()],
+        bump = collateral_account.bump,
+        has_one = token_account
+    )]
+    pub collateral_account: Account<'info, Collateral>,
+    #[account(mut)]
+    pub mint_account: InterfaceAccount<'info, Mint>,
+    pub gold_price_update: Account<'info, PriceUpdateV2>,
+    pub sol_price_update: Account<'info, PriceUpdateV2>,
+    #[account(
+        init_if_needed,
+        payer = depositor,
+        associated_token::mint = mint_account,
+        associated_token::authority = depositor,
+        associated_token::token_program = token_program
+    )]
+    pub token_account: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Program<'info, Token2022>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+// Mint GOLD against collateral already deposited, which needs live prices and a healthy position
+pub fn process_mint_gold(ctx: Context<MintGold>, amount_to_mint: u64) -> Result<()> {
+    let collateral_account = &mut ctx.accounts.collateral_account;
+    collateral_account.amount_minted = collateral_account
+        .amount_minted
+        .checked_add(amount_to_mint)
+        .ok_or(MathError::Overflow)?;
+
+    let price_sources = PriceSources::new(
+        &ctx.accounts.gold_price_update,
+        &ctx.accounts.sol_price_update,
+        ctx.remaining_accounts,
+        &ctx.accounts.config_account,
+    );
+    let price_status = refresh_prices(&mut ctx.accounts.config_account, &price_sources)?;
+    price_status.require_live_prices()?;
+    check_health_factor(&ctx.accounts.collateral_account, &ctx.accounts.config_account, &price_status.prices)?;
+
+    mint_tokens_internal(
+        &ctx.accounts.mint_account,
+        &ctx.accounts.token_account,
+        &ctx.accounts.token_program,
+        ctx.accounts.config_account.bump_mint_account,
+        amount_to_mint,
+    )
+}
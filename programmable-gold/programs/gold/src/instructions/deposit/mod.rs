@@ -1,3 +1,7 @@
+pub mod deposit_collateral;
+pub use deposit_collateral::*;
+pub mod mint_gold;
+pub use mint_gold::*;
 pub mod deposit_collateral_and_mint_tokens;
 pub use deposit_collateral_and_mint_tokens::*;
 pub mod utils;
@@ -4,5 +4,7 @@ pub mod deposit;
 pub use deposit::*;
 pub mod withdraw;
 pub use withdraw::*;
+pub mod refresh_price_feeds;
+pub use refresh_price_feeds::*;
 pub mod utils;
 pub use utils::*;
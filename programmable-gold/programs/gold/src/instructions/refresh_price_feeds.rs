@@ -0,0 +1,34 @@
+use crate::{refresh_prices, Config, PriceSources, SEED_CONFIG_ACCOUNT};
+use anchor_lang::prelude::*;
+use pyth_solana_receiver_sdk::price_update::PriceUpdateV2;
+
+#[derive(Accounts)]
+pub struct RefreshPriceFeeds<'info> {
+    pub gold_price_update: Account<'info, PriceUpdateV2>,
+    pub sol_price_update: Account<'info, PriceUpdateV2>,
+    #[account(
+        mut,
+        seeds = [SEED_CONFIG_ACCOUNT],
+        bump = config_account.bump,
+    )]
+    pub config_account: Account<'info, Config>,
+}
+
+// Record the latest prices and update the circuit breaker without touching any position. Anyone can
+// call it: a price shock seen by a failing mint or liquidation is rolled back with it, so keepers
+// refresh prices to make the trip stick and start its hold period.
+pub fn process_refresh_price_feeds(ctx: Context<RefreshPriceFeeds>) -> Result<()> {
+    let price_sources = PriceSources::new(
+        &ctx.accounts.gold_price_update,
+        &ctx.accounts.sol_price_update,
+        ctx.remaining_accounts,
+        &ctx.accounts.config_account,
+    );
+    let price_status = refresh_prices(&mut ctx.accounts.config_account, &price_sources)?;
+    msg!(
+        "GOLD market open: {}, circuit breaker tripped: {}",
+        price_status.gold_market_open,
+        price_status.breaker_tripped
+    );
+    Ok(())
+}
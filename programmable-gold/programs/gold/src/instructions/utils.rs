@@ -1,17 +1,107 @@
 use crate::{
-    error::CustomError, Collateral, Config, GOLD_FEED_ID, SOL_FEED_ID, MAXIMUM_AGE, PRICE_FEED_DECIMAL_ADJUSTMENT,
+    error::CustomError, Collateral, Config, ManualPrice, GOLD_FEED_ID, SOL_FEED_ID, MINT_DECIMALS, SOL_DECIMALS,
 };
-use anchor_lang::{prelude::*, solana_program::native_token::LAMPORTS_PER_SOL};
-use pyth_solana_receiver_sdk::price_update::{get_feed_id_from_hex, PriceUpdateV2};
+use anchor_lang::prelude::*;
+use decimal::{Decimal, Rounding};
+use price_oracle::{
+    cross_check, parse_feed_id, pyth_price, secondary_price, select_price, OracleError, OraclePolicy, OraclePrice,
+    PRICE_EXPONENT,
+};
+use pyth_solana_receiver_sdk::price_update::{FeedId, PriceUpdateV2};
+
+// Price sources a handler values collateral with: the GOLD/USD and SOL/USD Pyth updates, plus any
+// fallback oracle accounts passed as remaining accounts
+pub struct PriceSources<'a, 'info> {
+    pub gold_price_update: &'a PriceUpdateV2,
+    pub sol_price_update: &'a PriceUpdateV2,
+    pub fallback_sources: &'a [AccountInfo<'info>],
+    pub secondary_oracle_program: Pubkey,
+    pub gold_policy: OraclePolicy,
+    pub sol_policy: OraclePolicy,
+}
+
+impl<'a, 'info> PriceSources<'a, 'info> {
+    pub fn new(
+        gold_price_update: &'a PriceUpdateV2,
+        sol_price_update: &'a PriceUpdateV2,
+        fallback_sources: &'a [AccountInfo<'info>],
+        config: &Config,
+    ) -> Self {
+        Self {
+            gold_price_update,
+            sol_price_update,
+            fallback_sources,
+            secondary_oracle_program: config.secondary_oracle_program,
+            gold_policy: config.gold_price_policy(),
+            sol_policy: config.sol_price_policy(),
+        }
+    }
+}
+
+// State of the price feeds after refresh_prices
+pub struct PriceStatus {
+    pub prices: GoldPrices,
+    pub gold_market_open: bool, // some source has a GOLD price within its staleness limit
+    pub breaker_tripped: bool,
+}
+
+impl PriceStatus {
+    // Prices to value collateral at: the fresh GOLD price, or the last recorded one while the market
+    // is closed. Fails with MarketClosed if no GOLD price was ever recorded.
+    pub fn new(config: &Config, gold_price: Option<OraclePrice>, sol_price: OraclePrice) -> Result<Self> {
+        let gold = match gold_price {
+            Some(price) => price,
+            None => config.last_gold_price.get().ok_or(CustomError::MarketClosed)?,
+        };
+        Ok(Self {
+            prices: GoldPrices { gold, sol: sol_price },
+            gold_market_open: gold_price.is_some(),
+            breaker_tripped: config.is_breaker_tripped(),
+        })
+    }
+
+    // Minting, liquidation and collateral withdrawals need a live GOLD price and no recent price shock
+    pub fn require_live_prices(&self) -> Result<()> {
+        require!(self.gold_market_open, CustomError::MarketClosed);
+        require!(!self.breaker_tripped, CustomError::CircuitBreakerTripped);
+        Ok(())
+    }
+}
+
+// Record fresh GOLD and SOL prices in the config and update the circuit breaker. A price moving
+// more than max_price_move_bps from the last one within the window trips it; it resets itself
+// once prices have held for the hold period without another such move. While the GOLD market is
+// closed GOLD is valued at the last recorded price.
+pub fn refresh_prices(config: &mut Config, sources: &PriceSources) -> Result<PriceStatus> {
+    let gold_price = match get_oracle_price(sources.gold_price_update, sources, GOLD_FEED_ID, &sources.gold_policy) {
+        Ok(price) => Some(price),
+        // Only a stale price means the market is closed; any other oracle error fails the instruction
+        Err(err) if err == CustomError::StalePrice.into() => {
+            msg!("GOLD market closed, last price published at {}", config.last_gold_price.publish_time);
+            None
+        }
+        Err(err) => return Err(err),
+    };
+    let sol_price = get_oracle_price(sources.sol_price_update, sources, SOL_FEED_ID, &sources.sol_policy)?;
+
+    let (max_move_bps, window) = (config.max_price_move_bps, config.price_move_window);
+    let mut moved = config.last_sol_price.record(&sol_price, max_move_bps, window);
+    if let Some(price) = gold_price {
+        moved |= config.last_gold_price.record(&price, max_move_bps, window);
+    }
+
+    config.update_circuit_breaker(moved, Clock::get()?.unix_timestamp);
+
+    PriceStatus::new(config, gold_price, sol_price)
+}
 
 // Check health factor for Collateral account is greater than minimum required health factor
 pub fn check_health_factor(
     collateral: &Account<Collateral>,
     config: &Account<Config>,
-    gold_price_feed: &Account<PriceUpdateV2>,
-    sol_price_feed: &Account<PriceUpdateV2>,
+    prices: &GoldPrices,
 ) -> Result<()> {
-    let health_factor = calculate_health_factor(collateral, config, gold_price_feed, sol_price_feed)?;
+    let health_factor = calculate_health_factor(collateral, config, prices)?;
     require!(
         health_factor >= config.min_health_factor,
         CustomError::BelowMinimumHealthFactor
@@ -19,22 +109,19 @@ pub fn check_health_factor(
     Ok(())
 }
 
-// Calculate health factor for a given Collateral account
+// Calculate health factor for a given Collateral account in bps (10_000 = 1.0): the collateral value
+// weighted by the liquidation threshold over the minted GOLD, rounded down. u64::MAX when nothing is
+// minted or the ratio is too large to represent.
 pub fn calculate_health_factor(
     collateral: &Account<Collateral>,
     config: &Account<Config>,
-    gold_price_feed: &Account<PriceUpdateV2>,
-    sol_price_feed: &Account<PriceUpdateV2>,
+    prices: &GoldPrices,
 ) -> Result<u64> {
-    // Get the SOL value in USD first
-    let sol_value_in_usd = get_sol_usd_value(&collateral.lamport_balance, sol_price_feed)?;
-    
-    // Convert SOL USD value to GOLD value using GOLD/USD price
-    let collateral_value_in_gold = get_gold_value_from_usd(&sol_value_in_usd, gold_price_feed, sol_price_feed)?;
+    let collateral_value_in_gold = prices.collateral_value_in_gold(collateral.lamport_balance)?;
 
     msg!(
-        "Minted Amount : {:.9}",
-        collateral.amount_minted as f64 / 1e9
+        "Minted Amount : {}",
+        Decimal::from_token_amount(collateral.amount_minted, MINT_DECIMALS)?
     );
 
     if collateral.amount_minted == 0 {
@@ -42,160 +129,240 @@ pub fn calculate_health_factor(
         return Ok(u64::MAX);
     }
 
-    // Calculate the health factor: collateral_value / amount_minted
-    // This represents how much collateral value exists per unit of minted tokens
-    let health_factor = collateral_value_in_gold / collateral.amount_minted;
-
-    msg!("Health Factor : {}", health_factor);
+    // Calculate the health factor: collateral_value * liquidation_threshold / 100 / amount_minted
+    // With a 50% threshold, collateral worth twice the minted GOLD gives a health factor of 1.0
+    let weighted_collateral = Decimal::from_integer(collateral_value_in_gold)
+        .try_mul_int(config.liquidation_threshold)?
+        .try_div_int(100, Rounding::Floor)?;
+    let health_factor = weighted_collateral
+        .try_div(Decimal::from_integer(collateral.amount_minted), Rounding::Floor)
+        .and_then(|ratio| ratio.try_to_bps(Rounding::Floor))
+        .unwrap_or(u64::MAX);
+
+    msg!("Health Factor : {} bps", health_factor);
     Ok(health_factor)
 }
 
-// Get SOL value in USD using SOL/USD price feed
-fn get_sol_usd_value(amount_in_lamports: &u64, sol_price_feed: &Account<PriceUpdateV2>) -> Result<u64> {
-    let sol_feed_id = get_feed_id_from_hex(SOL_FEED_ID)
-        .map_err(|e| {
-            msg!("Error parsing SOL feed ID: {:?}", e);
-            anchor_lang::error::Error::from(ProgramError::InvalidArgument)
-        })?;
+// GOLD/USD and SOL/USD prices an instruction values collateral with, fetched once by refresh_prices.
+// Conversions between lamports, USD and GOLD base units are fixed-point and round against the caller.
+#[derive(Clone, Copy, Debug)]
+pub struct GoldPrices {
+    pub gold: OraclePrice,
+    pub sol: OraclePrice,
+}
 
-    let sol_price = sol_price_feed
-        .get_price_no_older_than(&Clock::get()?, MAXIMUM_AGE, &sol_feed_id)
-        .map_err(|e| {
-            msg!("Error getting SOL price: {:?}", e);
-            anchor_lang::error::Error::from(ProgramError::InvalidArgument)
-        })?;
+impl GoldPrices {
+    // Value of `lamports` in GOLD base units, with SOL at the low end of its confidence interval and
+    // GOLD, being the minted debt, at the high end. Rounded down so collateral is never overvalued.
+    pub fn collateral_value_in_gold(&self, lamports: u64) -> Result<u64> {
+        let usd_value = Decimal::from_token_amount(lamports, SOL_DECIMALS)?
+            .try_mul(usd_price(self.sol.lower_bound())?, Rounding::Floor)?;
+        let gold_value = usd_value.try_div(usd_price(self.gold.upper_bound())?, Rounding::Floor)?;
+
+        msg!("*** SOL TO GOLD CONVERSION ***");
+        msg!("GOLD/USD Price : {}", usd_price(self.gold.price)?);
+        msg!("SOL/USD Price  : {}", usd_price(self.sol.price)?);
+        msg!("USD Amount     : {}", usd_value);
+        msg!("GOLD Value     : {}", gold_value);
+
+        Ok(gold_value.try_to_token_amount(MINT_DECIMALS, Rounding::Floor)?)
+    }
 
-    // Check price is positive
-    require!(sol_price.price > 0, CustomError::InvalidPrice);
+    // Lamports worth `amount_in_gold` at spot prices, rounded down so a liquidator never seizes more
+    // collateral than the burned GOLD covers
+    pub fn lamports_from_gold(&self, amount_in_gold: u64) -> Result<u64> {
+        let usd_value = Decimal::from_token_amount(amount_in_gold, MINT_DECIMALS)?
+            .try_mul(usd_price(self.gold.price)?, Rounding::Floor)?;
+        let sol_amount = usd_value.try_div(usd_price(self.sol.price)?, Rounding::Floor)?;
 
-    // Calculate actual SOL price using Pyth's price * 10^exponent format
-    let _actual_sol_price = (sol_price.price as f64) * (10.0_f64.powi(sol_price.exponent as i32));
+        Ok(sol_amount.try_to_token_amount(SOL_DECIMALS, Rounding::Floor)?)
+    }
+}
 
-    // Maintain 1e9 precision by scaling Pyth's 1e8 price by 10
-    let sol_price_in_usd = (sol_price.price as i128 * PRICE_FEED_DECIMAL_ADJUSTMENT as i128) as u128;
+// Oracle price, quoted at PRICE_EXPONENT whatever exponent the source published, as USD per token
+fn usd_price(price: u64) -> Result<Decimal> {
+    Ok(Decimal::from_price(price, PRICE_EXPONENT)?)
+}
 
-    // Calculate USD value of SOL
-    let sol_value_in_usd = (*amount_in_lamports as u128 * sol_price_in_usd) / (LAMPORTS_PER_SOL as u128);
+// Fetch a price for `feed_id` from the Pyth update while it is fresh, failing if a fresh secondary
+// oracle or guardian-set ManualPrice disagrees with it beyond the shared tolerance. Once Pyth is
+// stale the first of the secondary and manual prices that agrees with the other is used instead.
+// Pyth and secondary prices must pass the feed's policy; an update that fails it or is not a
+// verified update for the feed is an error.
+fn get_oracle_price(
+    price_feed: &PriceUpdateV2,
+    sources: &PriceSources,
+    feed_id: &str,
+    policy: &OraclePolicy,
+) -> Result<OraclePrice> {
+    let parsed_feed_id = parse_feed_id(feed_id).map_err(CustomError::from)?;
+    let clock = Clock::get()?;
+
+    let pyth = match pyth_price(price_feed, &parsed_feed_id, policy, &clock) {
+        Ok(price) => Some(price),
+        Err(OracleError::StalePrice) => {
+            msg!("Pyth price for feed {} is stale", feed_id);
+            None
+        }
+        Err(e) => {
+            msg!("Pyth price for feed {} rejected: {:?}", feed_id, e);
+            return Err(CustomError::from(e).into());
+        }
+    };
+
+    let mut fallbacks: Vec<OraclePrice> = Vec::with_capacity(2);
+    if let Some(price) = sources.fallback_sources.iter().find_map(|source| {
+        secondary_price(source, &sources.secondary_oracle_program, &parsed_feed_id, policy, &clock)
+    }) {
+        fallbacks.push(price);
+    }
+    if let Some(price) = find_manual_price(sources, &parsed_feed_id, &clock) {
+        fallbacks.push(price);
+    }
 
-    Ok(sol_value_in_usd as u64)
+    let price = match pyth {
+        Some(price) => cross_check(price, &fallbacks),
+        None if fallbacks.is_empty() => {
+            msg!("No fresh price source for feed {}", feed_id);
+            Err(OracleError::StalePrice)
+        }
+        None => select_price(&fallbacks),
+    };
+    price.map_err(|e| CustomError::from(e).into())
 }
 
-// Convert USD value to GOLD value using GOLD/USD price feed
-fn get_gold_value_from_usd(usd_amount: &u64, gold_price_feed: &Account<PriceUpdateV2>, sol_price_feed: &Account<PriceUpdateV2>) -> Result<u64> {
-    let gold_feed_id = get_feed_id_from_hex(GOLD_FEED_ID)
-        .map_err(|e| {
-            msg!("Error parsing GOLD feed ID: {:?}", e);
-            anchor_lang::error::Error::from(ProgramError::InvalidArgument)
-        })?;
-
-    let gold_price = gold_price_feed
-        .get_price_no_older_than(&Clock::get()?, MAXIMUM_AGE, &gold_feed_id)
-        .map_err(|e| {
-            msg!("Error getting GOLD price: {:?}", e);
-            anchor_lang::error::Error::from(ProgramError::InvalidArgument)
-        })?;
-
-    // Check price is positive
-    require!(gold_price.price > 0, CustomError::InvalidPrice);
-
-    // Calculate actual GOLD price using Pyth's price * 10^exponent format
-    let actual_gold_price = (gold_price.price as f64) * (10.0_f64.powi(gold_price.exponent as i32));
-
-    // Maintain 1e9 precision by scaling Pyth's 1e8 price by 10
-    let _gold_price_in_usd = (gold_price.price as i128 * PRICE_FEED_DECIMAL_ADJUSTMENT as i128) as u128;
-
-    // Calculate GOLD value from USD amount
-    // usd_amount is in 1e9 units (USD * 1e9)
-    // gold_price_in_usd is in 1e9 units (price * 1e9)
-    // We want: (USD / price) in 1e9 units
-    // Formula: (USD * 1e9) / (price * 1e9) = USD / price
-    // Result should be in 1e9 units: (USD * 1e9) / price
-    // Calculate GOLD value: (USD * 1e9) / (GOLD_price_in_USD)
-    // Since both are in 1e9 units, result is in 1e9 units
-    // Formula: (USD * 1e9) / (GOLD_price * 1e9) = USD / GOLD_price
-    // Result should be in 1e9 units: (USD * 1e9) / GOLD_price
-    // Calculate GOLD value: USD / GOLD_price in 1e9 units
-    // Both usd_amount and gold_price_in_usd are in 1e9 units
-    // Result: (USD * 1e9) / (GOLD_price * 1e9) = USD / GOLD_price in 1e9 units
-    // Calculate GOLD value using floating point for precision, then convert back
-    // This avoids division by zero issues and handles small prices correctly
-    let usd_amount_f64 = *usd_amount as f64 / 1e9;
-    let gold_price_f64 = gold_price.price as f64 * (10.0_f64.powi(gold_price.exponent as i32));
-    let gold_value_f64 = usd_amount_f64 / gold_price_f64;
-    let gold_value = (gold_value_f64 * 1e9) as u64;
-
-    // Convert USD amount to SOL equivalent for logging
-    // Get SOL price and calculate SOL equivalent of USD amount
-    let sol_feed_id = get_feed_id_from_hex(SOL_FEED_ID)
-        .map_err(|e| {
-            msg!("Error parsing SOL feed ID: {:?}", e);
-            anchor_lang::error::Error::from(ProgramError::InvalidArgument)
-        })?;
-
-    let sol_price = sol_price_feed
-        .get_price_no_older_than(&Clock::get()?, MAXIMUM_AGE, &sol_feed_id)
-        .map_err(|e| {
-            msg!("Error getting SOL price: {:?}", e);
-            anchor_lang::error::Error::from(ProgramError::InvalidArgument)
-        })?;
-
-    require!(sol_price.price > 0, CustomError::InvalidPrice);
-    let sol_price_f64 = (sol_price.price as f64) * (10.0_f64.powi(sol_price.exponent as i32));
-    let usd_in_sol = usd_amount_f64 / sol_price_f64;
-    
-    // Convert GOLD value to SOL equivalent for logging
-    let gold_to_sol_lamports = get_lamports_from_gold(&(gold_value as u64), gold_price_feed, sol_price_feed)?;
-
-    msg!("*** USD TO GOLD CONVERSION ***");
-    msg!("GOLD/USD Price : {:.2}", actual_gold_price);
-    msg!("SOL/USD Price  : {:.2}", sol_price_f64);
-    msg!("USD Amount     : {:.2}", *usd_amount as f64 / 1e9);
-    msg!("USD in SOL     : {:.9}", usd_in_sol);
-    msg!("GOLD Value     : {:.6}", gold_value as f64 / 1e9);
-    msg!("GOLD in SOL    : {:.9}", gold_to_sol_lamports as f64 / 1e9);
-
-    Ok(gold_value as u64)
+// Unexpired guardian price, if its ManualPrice account was passed. Only this program can create
+// ManualPrice accounts, so the owner and discriminator checks are enough to trust it.
+fn find_manual_price(sources: &PriceSources, feed_id: &FeedId, clock: &Clock) -> Option<OraclePrice> {
+    sources
+        .fallback_sources
+        .iter()
+        .filter(|source| *source.owner == crate::ID)
+        .find_map(|source| {
+            let data = source.try_borrow_data().ok()?;
+            let manual = ManualPrice::try_deserialize(&mut &data[..]).ok()?;
+            (manual.feed_id == *feed_id && clock.unix_timestamp <= manual.expires_at).then_some(manual)
+        })
+        .and_then(|manual| OraclePrice::from_parts(manual.price, manual.conf, manual.exponent, manual.publish_time).ok())
 }
 
-// Given GOLD amount, return lamports based on GOLD/USD and SOL/USD feeds
-pub fn get_lamports_from_gold(
-    amount_in_gold: &u64,
-    gold_price_feed: &Account<PriceUpdateV2>,
-    sol_price_feed: &Account<PriceUpdateV2>,
-) -> Result<u64> {
-    // Convert GOLD to USD first
-    let gold_feed_id = get_feed_id_from_hex(GOLD_FEED_ID)
-        .map_err(|_| anchor_lang::error::Error::from(ProgramError::InvalidArgument))?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RecordedPrice;
 
-    let gold_price = gold_price_feed
-        .get_price_no_older_than(&Clock::get()?, MAXIMUM_AGE, &gold_feed_id)
-        .map_err(|_| anchor_lang::error::Error::from(ProgramError::InvalidArgument))?;
+    const SOL: u64 = 1_000_000_000; // lamports
+    const GOLD: u64 = 1_000_000_000; // GOLD base units
 
-    require!(gold_price.price > 0, CustomError::InvalidPrice);
+    // USD price quoted with `exponent`
+    fn price(price: i64, conf: u64, exponent: i32) -> OraclePrice {
+        OraclePrice::from_parts(price, conf, exponent, 0).unwrap()
+    }
 
-    let _actual_gold_price = (gold_price.price as f64) * (10.0_f64.powi(gold_price.exponent as i32));
-    let gold_price_in_usd = (gold_price.price as i128 * PRICE_FEED_DECIMAL_ADJUSTMENT as i128) as u128;
+    fn prices(gold: u64, sol: u64) -> GoldPrices {
+        GoldPrices { gold: price(gold as i64, 0, 0), sol: price(sol as i64, 0, 0) }
+    }
 
-    // Convert GOLD to USD
-    let usd_value = (*amount_in_gold as u128 * gold_price_in_usd) / (LAMPORTS_PER_SOL as u128);
+    #[test]
+    fn conversions_at_spot_prices_are_inverse() {
+        let prices = prices(2_400, 150);
+        assert_eq!(prices.collateral_value_in_gold(SOL).unwrap(), 62_500_000);
+        assert_eq!(prices.lamports_from_gold(62_500_000).unwrap(), SOL);
+        assert_eq!(prices.collateral_value_in_gold(0).unwrap(), 0);
+        assert_eq!(prices.lamports_from_gold(0).unwrap(), 0);
+    }
 
-    // Convert USD to SOL lamports
-    let sol_feed_id = get_feed_id_from_hex(SOL_FEED_ID)
-        .map_err(|_| anchor_lang::error::Error::from(ProgramError::InvalidArgument))?;
+    #[test]
+    fn conversions_read_each_price_exponent() {
+        let expected = prices(2_400, 150);
+        let gold = price(240_000_000_000, 0, -8);
+        for sol in [price(1_500_000, 0, -4), price(15, 0, 1)] {
+            let prices = GoldPrices { gold, sol };
+            assert_eq!(prices.collateral_value_in_gold(SOL).unwrap(), expected.collateral_value_in_gold(SOL).unwrap());
+            assert_eq!(prices.lamports_from_gold(GOLD).unwrap(), expected.lamports_from_gold(GOLD).unwrap());
+        }
+    }
 
-    let sol_price = sol_price_feed
-        .get_price_no_older_than(&Clock::get()?, MAXIMUM_AGE, &sol_feed_id)
-        .map_err(|_| anchor_lang::error::Error::from(ProgramError::InvalidArgument))?;
+    #[test]
+    fn collateral_is_valued_at_the_conservative_bounds() {
+        // SOL at 150 - 15, GOLD at 2_400 + 300
+        let prices = GoldPrices { gold: price(2_400, 300, 0), sol: price(150, 15, 0) };
+        assert_eq!(prices.collateral_value_in_gold(SOL).unwrap(), 50_000_000);
+        // Seized collateral is valued at spot
+        assert_eq!(prices.lamports_from_gold(50_000_000).unwrap(), 800_000_000);
+    }
 
-    require!(sol_price.price > 0, CustomError::InvalidPrice);
+    #[test]
+    fn conversions_round_down() {
+        // 1 SOL at 100 against GOLD at 300 is a third of a GOLD
+        assert_eq!(prices(300, 100).collateral_value_in_gold(SOL).unwrap(), 333_333_333);
+        assert_eq!(prices(100, 300).lamports_from_gold(GOLD).unwrap(), 333_333_333);
+        // Less than one base unit is worth nothing
+        assert_eq!(prices(2_400, 150).collateral_value_in_gold(15).unwrap(), 0);
+        assert_eq!(prices(150, 2_400).lamports_from_gold(15).unwrap(), 0);
+        // A round trip never gives back more lamports than it started with
+        let prices = prices(2_399, 151);
+        for lamports in [1, 999, 123_456_789, SOL, 7 * SOL + 3] {
+            let gold = prices.collateral_value_in_gold(lamports).unwrap();
+            assert!(prices.lamports_from_gold(gold).unwrap() <= lamports);
+        }
+    }
 
-    let _actual_sol_price = (sol_price.price as f64) * (10.0_f64.powi(sol_price.exponent as i32));
-    let sol_price_in_usd = (sol_price.price as i128 * PRICE_FEED_DECIMAL_ADJUSTMENT as i128) as u128;
+    #[test]
+    fn large_amounts_convert_without_overflow() {
+        let prices = prices(2_400, 150);
+        assert_eq!(prices.collateral_value_in_gold(u64::MAX).unwrap(), u64::MAX / 16);
+        assert_eq!(prices.lamports_from_gold(u64::MAX / 16).unwrap(), u64::MAX / 16 * 16);
+    }
+
+    #[test]
+    fn results_beyond_u64_fail() {
+        assert!(prices(2_400, 150).lamports_from_gold(u64::MAX).is_err());
+        assert!(prices(1, 2_400).collateral_value_in_gold(u64::MAX).is_err());
+    }
+
+    fn config_with_last_gold_price(price: u64) -> Config {
+        Config {
+            last_gold_price: RecordedPrice { price, confidence: 0, publish_time: 1_000 },
+            ..Default::default()
+        }
+    }
 
-    // Convert USD to SOL lamports
-    let amount_in_lamports = (usd_value * (LAMPORTS_PER_SOL as u128)) / sol_price_in_usd;
+    #[test]
+    fn open_market_uses_the_fresh_gold_price() {
+        let config = config_with_last_gold_price(2_300);
+        let status = PriceStatus::new(&config, Some(price(2_400, 0, 0)), price(150, 0, 0)).unwrap();
+        assert!(status.gold_market_open);
+        assert_eq!(status.prices.gold.price, price(2_400, 0, 0).price);
+        status.require_live_prices().unwrap();
+    }
 
-    // Removed logging to clean up output
+    // Mint and liquidate require live prices; burn reads no prices, and a deposit that mints nothing
+    // only checks health, at the last recorded GOLD price
+    #[test]
+    fn closed_market_values_gold_at_the_last_recorded_price() {
+        let last = price(2_400, 0, 0);
+        let config = config_with_last_gold_price(last.price);
+        let status = PriceStatus::new(&config, None, price(150, 0, 0)).unwrap();
+        assert!(!status.gold_market_open);
+        assert_eq!(status.require_live_prices().unwrap_err(), CustomError::MarketClosed.into());
+        assert_eq!(status.prices.gold.price, last.price);
+        assert_eq!(status.prices.collateral_value_in_gold(SOL).unwrap(), 62_500_000);
+    }
 
-    Ok(amount_in_lamports as u64)
+    #[test]
+    fn closed_market_without_a_recorded_price_fails() {
+        let config = config_with_last_gold_price(0);
+        assert_eq!(
+            PriceStatus::new(&config, None, price(150, 0, 0)).err().unwrap(),
+            CustomError::MarketClosed.into()
+        );
+    }
+
+    #[test]
+    fn tripped_breaker_blocks_live_price_instructions() {
+        let config = Config { breaker_tripped_at: 1_000, ..config_with_last_gold_price(2_400) };
+        let status = PriceStatus::new(&config, Some(price(2_400, 0, 0)), price(150, 0, 0)).unwrap();
+        assert_eq!(status.require_live_prices().unwrap_err(), CustomError::CircuitBreakerTripped.into());
+    }
 }
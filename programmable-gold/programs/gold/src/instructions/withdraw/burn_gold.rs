@@ -0,0 +1,47 @@
+use crate::{burn_tokens_internal, Collateral, Config, SEED_COLLATERAL_ACCOUNT, SEED_CONFIG_ACCOUNT};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, Token2022, TokenAccount};
+use decimal::MathError;
+
+#[derive(Accounts)]
+pub struct BurnGold<'info> {
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_CONFIG_ACCOUNT],
+        bump = config_account.bump,
+        has_one = mint_account
+    )]
+    pub config_account: Account<'info, Config>,
+    #[account(
+        mut,
+        seeds = [SEED_COLLATERAL_ACCOUNT, depositor.key().as_ref()],
+        bump = collateral_account.bump,
+        has_one = token_account
+    )]
+    pub collateral_account: Account<'info, Collateral>,
+    #[account(mut)]
+    pub mint_account: InterfaceAccount<'info, Mint>,
+    #[account(mut)]
+    pub token_account: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Program<'info, Token2022>,
+}
+
+// Repaying GOLD only makes a position safer, so it needs no prices and works while the market is
+// closed or the circuit breaker is tripped
+pub fn process_burn_gold(ctx: Context<BurnGold>, amount_to_burn: u64) -> Result<()> {
+    let collateral_account = &mut ctx.accounts.collateral_account;
+    collateral_account.amount_minted = collateral_account
+        .amount_minted
+        .checked_sub(amount_to_burn)
+        .ok_or(MathError::Underflow)?;
+
+    burn_tokens_internal(
+        &ctx.accounts.mint_account,
+        &ctx.accounts.token_account,
+        &ctx.accounts.depositor,
+        &ctx.accounts.token_program,
+        amount_to_burn,
+    )
+}
@@ -1,8 +1,9 @@
 use crate::{
-    burn_tokens_internal, calculate_health_factor, error::CustomError, get_lamports_from_gold,
-    withdraw_sol_internal, Collateral, Config, SEED_CONFIG_ACCOUNT,
+    burn_tokens_internal, calculate_health_factor, error::CustomError, refresh_prices,
+    withdraw_sol_internal, Collateral, Config, PriceSources, SEED_CONFIG_ACCOUNT, SOL_DECIMALS,
 };
 use anchor_lang::prelude::*;
+use decimal::{Decimal, MathError, Rounding};
 use anchor_spl::token_interface::{Mint, Token2022, TokenAccount};
 use pyth_solana_receiver_sdk::price_update::PriceUpdateV2;
 
@@ -14,6 +15,7 @@ pub struct Liquidate<'info> {
     pub gold_price_update: Account<'info, PriceUpdateV2>,
     pub sol_price_update: Account<'info, PriceUpdateV2>,
     #[account(
+        mut,
         seeds = [SEED_CONFIG_ACCOUNT],
         bump = config_account.bump,
         has_one = mint_account
@@ -40,12 +42,20 @@ pub struct Liquidate<'info> {
 }
 
 // https://github.com/Cyfrin/foundry-defi-stablecoin-cu/blob/main/src/DSCEngine.sol#L215
-pub fn process_liquidate(mut ctx: Context<Liquidate>, amount_to_burn: u64) -> Result<()> {
+pub fn process_liquidate(ctx: Context<Liquidate>, amount_to_burn: u64) -> Result<()> {
+    let price_sources = PriceSources::new(
+        &ctx.accounts.gold_price_update,
+        &ctx.accounts.sol_price_update,
+        ctx.remaining_accounts,
+        &ctx.accounts.config_account,
+    );
+    let price_status = refresh_prices(&mut ctx.accounts.config_account, &price_sources)?;
+    price_status.require_live_prices()?;
+    let prices = price_status.prices;
     let health_factor = calculate_health_factor(
         &ctx.accounts.collateral_account,
         &ctx.accounts.config_account,
-        &ctx.accounts.gold_price_update,
-        &ctx.accounts.sol_price_update,
+        &prices,
     )?;
 
     require!(
@@ -53,14 +63,17 @@ pub fn process_liquidate(mut ctx: Context<Liquidate>, amount_to_burn: u64) -> Re
         CustomError::AboveMinimumHealthFactor
     );
 
-    let lamports = get_lamports_from_gold(&amount_to_burn, &ctx.accounts.gold_price_update, &ctx.accounts.sol_price_update)?;
-    let liquidation_bonus = lamports * ctx.accounts.config_account.liquidation_bonus / 100;
-    let amount_to_liquidate = lamports + liquidation_bonus;
+    let lamports = prices.lamports_from_gold(amount_to_burn)?;
+    let liquidation_bonus = Decimal::from_integer(lamports)
+        .try_mul_int(ctx.accounts.config_account.liquidation_bonus)?
+        .try_div_int(100, Rounding::Floor)?
+        .try_to_u64(Rounding::Floor)?;
+    let amount_to_liquidate = lamports.checked_add(liquidation_bonus).ok_or(MathError::Overflow)?;
 
     msg!("*** LIQUIDATION ***");
     msg!("Bonus {}%", ctx.accounts.config_account.liquidation_bonus);
-    msg!("Bonus Amount  : {:.9}", liquidation_bonus as f64 / 1e9);
-    msg!("SOL Liquidated: {:.9}", amount_to_liquidate as f64 / 1e9);
+    msg!("Bonus Amount  : {}", Decimal::from_token_amount(liquidation_bonus, SOL_DECIMALS)?);
+    msg!("SOL Liquidated: {}", Decimal::from_token_amount(amount_to_liquidate, SOL_DECIMALS)?);
 
     let depositor = ctx.accounts.collateral_account.depositor;
     let bump_sol_account = ctx.accounts.collateral_account.bump_sol_account;
@@ -91,8 +104,7 @@ pub fn process_liquidate(mut ctx: Context<Liquidate>, amount_to_burn: u64) -> Re
     calculate_health_factor(
         &ctx.accounts.collateral_account,
         &ctx.accounts.config_account,
-        &ctx.accounts.gold_price_update,
-        &ctx.accounts.sol_price_update,
+        &prices,
     )?;
     Ok(())
 }
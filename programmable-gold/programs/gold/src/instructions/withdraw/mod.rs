@@ -1,3 +1,7 @@
+pub mod burn_gold;
+pub use burn_gold::*;
+pub mod withdraw_collateral;
+pub use withdraw_collateral::*;
 pub mod redeem_collateral_and_burn_tokens;
 pub use redeem_collateral_and_burn_tokens::*;
 pub mod liquidate;
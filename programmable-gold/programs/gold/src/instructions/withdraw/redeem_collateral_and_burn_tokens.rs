@@ -1,6 +1,6 @@
 use crate::{
-    burn_tokens_internal, check_health_factor, withdraw_sol_internal, Collateral, Config,
-    SEED_COLLATERAL_ACCOUNT, SEED_CONFIG_ACCOUNT,
+    burn_tokens_internal, check_health_factor, refresh_prices, withdraw_sol_internal,
+    Collateral, Config, PriceSources, SEED_COLLATERAL_ACCOUNT, SEED_CONFIG_ACCOUNT,
 };
 use anchor_lang::prelude::*;
 use anchor_spl::token_interface::{Mint, Token2022, TokenAccount};
@@ -14,6 +14,7 @@ pub struct RedeemCollateralAndBurnTokens<'info> {
     pub gold_price_update: Account<'info, PriceUpdateV2>,
     pub sol_price_update: Account<'info, PriceUpdateV2>,
     #[account(
+        mut,
         seeds = [SEED_CONFIG_ACCOUNT],
         bump = config_account.bump,
         has_one = mint_account
@@ -43,17 +44,26 @@ pub fn process_redeem_collateral_and_burn_tokens(
     amount_collateral: u64,
     amount_to_burn: u64,
 ) -> Result<()> {
-    let accounts = &ctx.accounts;
+    let sol_lamports = ctx.accounts.sol_account.lamports();
     let collateral_account = &mut ctx.accounts.collateral_account;
-    collateral_account.lamport_balance = accounts.sol_account.lamports() - amount_collateral;
+    collateral_account.lamport_balance = sol_lamports - amount_collateral;
     collateral_account.amount_minted -= amount_to_burn;
 
-    check_health_factor(
-        &accounts.collateral_account,
-        &accounts.config_account,
-        &accounts.gold_price_update,
-        &accounts.sol_price_update,
-    )?;
+    let price_sources = PriceSources::new(
+        &ctx.accounts.gold_price_update,
+        &ctx.accounts.sol_price_update,
+        ctx.remaining_accounts,
+        &ctx.accounts.config_account,
+    );
+    // While the GOLD market is closed or the circuit breaker is tripped GOLD can still be burned,
+    // valued at the last recorded price, but collateral only leaves against live prices
+    let price_status = refresh_prices(&mut ctx.accounts.config_account, &price_sources)?;
+    if amount_collateral > 0 {
+        price_status.require_live_prices()?;
+    }
+
+    let accounts = &ctx.accounts;
+    check_health_factor(&accounts.collateral_account, &accounts.config_account, &price_status.prices)?;
 
     burn_tokens_internal(
         &accounts.mint_account,
@@ -0,0 +1,61 @@
+use crate::{
+    check_health_factor, refresh_prices, withdraw_sol_internal, Collateral, Config,
+    PriceSources, SEED_COLLATERAL_ACCOUNT, SEED_CONFIG_ACCOUNT,
+};
+use anchor_lang::prelude::*;
+use decimal::MathError;
+use pyth_solana_receiver_sdk::price_update::PriceUpdateV2;
+
+#[derive(Accounts)]
+pub struct WithdrawCollateral<'info> {
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+
+    pub gold_price_update: Account<'info, PriceUpdateV2>,
+    pub sol_price_update: Account<'info, PriceUpdateV2>,
+    #[account(
+        mut,
+        seeds = [SEED_CONFIG_ACCOUNT],
+        bump = config_account.bump,
+    )]
+    pub config_account: Account<'info, Config>,
+    #[account(
+        mut,
+        seeds = [SEED_COLLATERAL_ACCOUNT, depositor.key().as_ref()],
+        bump = collateral_account.bump,
+        has_one = sol_account
+    )]
+    pub collateral_account: Account<'info, Collateral>,
+    #[account(mut)]
+    pub sol_account: SystemAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// Withdraw collateral without burning GOLD, as long as the position stays healthy at a live GOLD price and the
+// circuit breaker is not tripped
+pub fn process_withdraw_collateral(ctx: Context<WithdrawCollateral>, amount_collateral: u64) -> Result<()> {
+    let sol_lamports = ctx.accounts.sol_account.lamports();
+    let collateral_account = &mut ctx.accounts.collateral_account;
+    collateral_account.lamport_balance = sol_lamports.checked_sub(amount_collateral).ok_or(MathError::Underflow)?;
+
+    let price_sources = PriceSources::new(
+        &ctx.accounts.gold_price_update,
+        &ctx.accounts.sol_price_update,
+        ctx.remaining_accounts,
+        &ctx.accounts.config_account,
+    );
+    let price_status = refresh_prices(&mut ctx.accounts.config_account, &price_sources)?;
+    price_status.require_live_prices()?;
+
+    let accounts = &ctx.accounts;
+    check_health_factor(&accounts.collateral_account, &accounts.config_account, &price_status.prices)?;
+
+    withdraw_sol_internal(
+        &accounts.sol_account,
+        &accounts.depositor.to_account_info(),
+        &accounts.system_program,
+        &accounts.depositor.key(),
+        accounts.collateral_account.bump_sol_account,
+        amount_collateral,
+    )
+}
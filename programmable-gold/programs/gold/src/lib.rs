@@ -17,8 +17,43 @@ pub mod gold {
         process_initialize_config(ctx)
     }
 
-    pub fn update_config(ctx: Context<UpdateConfig>, min_health_factor: u64) -> Result<()> {
-        process_update_config(ctx, min_health_factor)
+    pub fn update_config(ctx: Context<UpdateConfig>, update: ConfigUpdate) -> Result<()> {
+        process_update_config(ctx, update)
+    }
+
+    pub fn set_manual_price(
+        ctx: Context<SetManualPrice>,
+        feed_id: [u8; 32],
+        price: i64,
+        conf: u64,
+        exponent: i32,
+        valid_for: i64,
+    ) -> Result<()> {
+        process_set_manual_price(ctx, feed_id, price, conf, exponent, valid_for)
+    }
+
+    pub fn reset_circuit_breaker(ctx: Context<ResetCircuitBreaker>) -> Result<()> {
+        process_reset_circuit_breaker(ctx)
+    }
+
+    pub fn refresh_price_feeds(ctx: Context<RefreshPriceFeeds>) -> Result<()> {
+        process_refresh_price_feeds(ctx)
+    }
+
+    pub fn deposit_collateral(ctx: Context<DepositCollateral>, amount_collateral: u64) -> Result<()> {
+        process_deposit_collateral(ctx, amount_collateral)
+    }
+
+    pub fn mint_gold(ctx: Context<MintGold>, amount_to_mint: u64) -> Result<()> {
+        process_mint_gold(ctx, amount_to_mint)
+    }
+
+    pub fn burn_gold(ctx: Context<BurnGold>, amount_to_burn: u64) -> Result<()> {
+        process_burn_gold(ctx, amount_to_burn)
+    }
+
+    pub fn withdraw_collateral(ctx: Context<WithdrawCollateral>, amount_collateral: u64) -> Result<()> {
+        process_withdraw_collateral(ctx, amount_collateral)
     }
 
     pub fn deposit_collateral_and_mint(
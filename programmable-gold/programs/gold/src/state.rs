@@ -1,4 +1,5 @@
 use anchor_lang::prelude::*;
+use price_oracle::{OraclePolicy, OraclePrice, BPS_DENOMINATOR};
 
 #[account]
 #[derive(InitSpace, Debug)]
@@ -13,14 +14,217 @@ pub struct Collateral {
     pub is_initialized: bool, // indicate if account data has already been initialized (for check to prevent overriding certain fields)
 }
 
+impl Collateral {
+    // Set the account's addresses and bumps on its first deposit
+    pub fn initialize_if_needed(
+        &mut self,
+        depositor: Pubkey,
+        sol_account: Pubkey,
+        token_account: Pubkey,
+        bump: u8,
+        bump_sol_account: u8,
+    ) {
+        if self.is_initialized {
+            return;
+        }
+        self.is_initialized = true;
+        self.depositor = depositor;
+        self.sol_account = sol_account;
+        self.token_account = token_account;
+        self.bump = bump;
+        self.bump_sol_account = bump_sol_account;
+    }
+}
+
 #[account]
-#[derive(InitSpace, Debug)]
+#[derive(InitSpace, Debug, Default)]
 pub struct Config {
     pub authority: Pubkey,          // authority of the this program config account
     pub mint_account: Pubkey,       // the stablecoin mint address, which is a PDA
-    pub liquidation_threshold: u64, // determines how much extra collateral is required
+    pub liquidation_threshold: u64, // % of collateral value counted towards health, determines how much extra collateral is required
     pub liquidation_bonus: u64,     // % bonus lamports to liquidator for liquidating an account
-    pub min_health_factor: u64, // minimum health factor, if below min then Collateral account can be liquidated
+    pub min_health_factor: u64, // minimum health factor in bps, if below min then Collateral account can be liquidated
     pub bump: u8,               // store bump seed for this config account
     pub bump_mint_account: u8,  // store bump seed for the stablecoin mint account PDA
+    pub guardian: Pubkey,       // may set short-lived manual prices when the oracles are down
+    pub secondary_oracle_program: Pubkey, // owner of secondary oracle price accounts (default = none)
+    pub gold_max_price_age: u64,  // seconds before the GOLD feed is stale, treated as the market being closed
+    pub sol_max_price_age: u64,   // seconds before the SOL feed is stale
+    pub gold_max_confidence_bps: u64,     // widest accepted GOLD confidence interval, as bps of the price
+    pub gold_max_ema_deviation_bps: u64,  // largest accepted GOLD spot deviation from its EMA price
+    pub sol_max_confidence_bps: u64,      // widest accepted SOL confidence interval, as bps of the price
+    pub sol_max_ema_deviation_bps: u64,   // largest accepted SOL spot deviation from its EMA price
+    pub last_gold_price: RecordedPrice, // last accepted GOLD/USD price, used while the market is closed
+    pub last_sol_price: RecordedPrice,  // last accepted SOL/USD price
+    pub max_price_move_bps: u64,  // circuit breaker: largest move from the last price within the window (0 = disabled)
+    pub price_move_window: i64,   // seconds a new price is compared against the last one
+    pub breaker_hold_period: i64, // seconds prices must hold after a trip before the breaker resets itself
+    pub breaker_tripped_at: i64,  // when the circuit breaker last tripped (0 = not tripped)
+}
+
+impl Config {
+    pub fn gold_price_policy(&self) -> OraclePolicy {
+        OraclePolicy {
+            maximum_age: self.gold_max_price_age,
+            max_confidence_bps: self.gold_max_confidence_bps,
+            max_ema_deviation_bps: self.gold_max_ema_deviation_bps,
+        }
+    }
+
+    pub fn sol_price_policy(&self) -> OraclePolicy {
+        OraclePolicy {
+            maximum_age: self.sol_max_price_age,
+            max_confidence_bps: self.sol_max_confidence_bps,
+            max_ema_deviation_bps: self.sol_max_ema_deviation_bps,
+        }
+    }
+
+    pub fn is_breaker_tripped(&self) -> bool {
+        self.breaker_tripped_at != 0
+    }
+
+    // Trip the circuit breaker at `now` if a price moved, or reset it once prices have held for the
+    // hold period since it tripped
+    pub fn update_circuit_breaker(&mut self, moved: bool, now: i64) {
+        if moved {
+            msg!("Circuit breaker tripped");
+            self.breaker_tripped_at = now;
+        } else if self.is_breaker_tripped() && now - self.breaker_tripped_at >= self.breaker_hold_period {
+            msg!("Circuit breaker reset, prices held since {}", self.breaker_tripped_at);
+            self.breaker_tripped_at = 0;
+        }
+    }
+}
+
+// Last price accepted for a feed, at PRICE_EXPONENT
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, Debug, InitSpace)]
+pub struct RecordedPrice {
+    pub price: u64, // 0 = none recorded yet
+    pub confidence: u64,
+    pub publish_time: i64,
+}
+
+impl RecordedPrice {
+    // The recorded price, with its spot price standing in for the EMA
+    pub fn get(&self) -> Option<OraclePrice> {
+        (self.price > 0).then_some(OraclePrice {
+            price: self.price,
+            confidence: self.confidence,
+            ema_price: self.price,
+            ema_confidence: self.confidence,
+            publish_time: self.publish_time,
+        })
+    }
+
+    // Record a newer price and return whether it moved more than `max_move_bps` from the previous
+    // one within `window` seconds
+    pub fn record(&mut self, price: &OraclePrice, max_move_bps: u64, window: i64) -> bool {
+        if price.publish_time < self.publish_time {
+            return false;
+        }
+        let moved = max_move_bps > 0
+            && self.price > 0
+            && price.publish_time - self.publish_time <= window
+            && (self.price.abs_diff(price.price) as u128 * BPS_DENOMINATOR as u128)
+                > (max_move_bps as u128 * self.price as u128);
+        *self = RecordedPrice {
+            price: price.price,
+            confidence: price.confidence,
+            publish_time: price.publish_time,
+        };
+        moved
+    }
+}
+
+#[account]
+#[derive(InitSpace, Debug)]
+pub struct ManualPrice {
+    pub feed_id: [u8; 32],   // Pyth feed id this price stands in for
+    pub price: i64,          // price * 10^exponent
+    pub conf: u64,           // confidence interval, same exponent as price
+    pub exponent: i32,
+    pub publish_time: i64,   // when the guardian set it
+    pub expires_at: i64,     // ignored after this timestamp
+    pub bump: u8,            // store bump seed for this manual price PDA
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAX_MOVE_BPS: u64 = 1_000;
+    const WINDOW: i64 = 300;
+
+    fn price(price: u64, publish_time: i64) -> OraclePrice {
+        OraclePrice { price, confidence: 0, ema_price: price, ema_confidence: 0, publish_time }
+    }
+
+    fn recorded(price: u64, publish_time: i64) -> RecordedPrice {
+        RecordedPrice { price, confidence: 0, publish_time }
+    }
+
+    #[test]
+    fn first_record_does_not_trip() {
+        let mut last = RecordedPrice::default();
+        assert!(!last.record(&price(100, 1_000), MAX_MOVE_BPS, WINDOW));
+        assert_eq!(last.get().unwrap().price, 100);
+        assert_eq!(last.publish_time, 1_000);
+    }
+
+    #[test]
+    fn move_beyond_the_limit_inside_the_window_trips() {
+        let mut last = recorded(100, 1_000);
+        assert!(last.record(&price(111, 1_000 + WINDOW), MAX_MOVE_BPS, WINDOW));
+        assert_eq!(last.price, 111);
+        // Falls count the same as rises
+        assert!(last.record(&price(99, 1_000 + WINDOW), MAX_MOVE_BPS, WINDOW));
+    }
+
+    #[test]
+    fn move_up_to_the_limit_does_not_trip() {
+        let mut last = recorded(100, 1_000);
+        assert!(!last.record(&price(110, 1_010), MAX_MOVE_BPS, WINDOW));
+        assert!(!last.record(&price(99, 1_020), MAX_MOVE_BPS, WINDOW));
+    }
+
+    #[test]
+    fn move_outside_the_window_does_not_trip() {
+        let mut last = recorded(100, 1_000);
+        assert!(!last.record(&price(150, 1_001 + WINDOW), MAX_MOVE_BPS, WINDOW));
+        assert_eq!(last.price, 150);
+    }
+
+    #[test]
+    fn older_prices_are_ignored() {
+        let mut last = recorded(100, 1_000);
+        assert!(!last.record(&price(150, 999), MAX_MOVE_BPS, WINDOW));
+        assert_eq!(last.price, 100);
+        assert_eq!(last.publish_time, 1_000);
+    }
+
+    #[test]
+    fn zero_max_move_disables_the_breaker() {
+        let mut last = recorded(100, 1_000);
+        assert!(!last.record(&price(1_000, 1_001), 0, WINDOW));
+        assert_eq!(last.price, 1_000);
+    }
+
+    #[test]
+    fn breaker_trips_and_resets_after_the_hold_period() {
+        let mut config = Config { breaker_hold_period: 600, ..Default::default() };
+        config.update_circuit_breaker(false, 1_000);
+        assert!(!config.is_breaker_tripped());
+
+        config.update_circuit_breaker(true, 1_000);
+        assert_eq!(config.breaker_tripped_at, 1_000);
+        config.update_circuit_breaker(false, 1_599);
+        assert!(config.is_breaker_tripped());
+
+        // Another move restarts the hold period
+        config.update_circuit_breaker(true, 1_500);
+        config.update_circuit_breaker(false, 2_099);
+        assert_eq!(config.breaker_tripped_at, 1_500);
+        config.update_circuit_breaker(false, 2_100);
+        assert!(!config.is_breaker_tripped());
+    }
 }
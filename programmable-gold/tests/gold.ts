@@ -92,6 +92,61 @@ describe("gold", () => {
     console.log("Your transaction signature", tx);
   });
 
+  // Guardian fallback prices, used by price-reading instructions only while Pyth is stale
+  it("Set Manual Price", async () => {
+    for (const [feedId, price] of [
+      [GOLD_PRICE_FEED_ID, 2_400_00000000],
+      [SOL_PRICE_FEED_ID, 150_00000000],
+    ] as [string, number][]) {
+      const feedIdBytes = Array.from(Buffer.from(feedId.slice(2), "hex"));
+      const [manualPriceAccount] = anchor.web3.PublicKey.findProgramAddressSync(
+        [seed("manual_price"), Buffer.from(feedIdBytes)],
+        program.programId
+      );
+      const tx = await program.methods
+        .setManualPrice(feedIdBytes, new BN(price), new BN(0), -8, new BN(600))
+        .accounts({
+          guardian: wallet.publicKey,
+          configAccount,
+          manualPrice: manualPriceAccount,
+          systemProgram,
+        })
+        .rpc();
+      console.log("Your transaction signature", tx, `${explorerBase}/tx/${tx}?cluster=${explorerCluster}`);
+    }
+  });
+
+  // Guardian override for a tripped price circuit breaker, a no-op when it is not tripped
+  it("Reset Circuit Breaker", async () => {
+    const tx = await program.methods
+      .resetCircuitBreaker()
+      .accounts({ guardian: wallet.publicKey, configAccount })
+      .rpc();
+    console.log("Your transaction signature", tx, `${explorerBase}/tx/${tx}?cluster=${explorerCluster}`);
+    const config = await program.account.config.fetch(configAccount);
+    if (!config.breakerTrippedAt.isZero()) {
+      throw new Error("reset should clear the circuit breaker");
+    }
+  });
+
+  // Permissionless price refresh, so a circuit breaker trip persists even when the instruction that
+  // saw the price shock fails
+  it("Refresh Price Feeds", async () => {
+    const tx = await program.methods
+      .refreshPriceFeeds()
+      .accounts({
+        goldPriceUpdate: goldUsdPriceFeedAccount,
+        solPriceUpdate: solUsdPriceFeedAccount,
+        configAccount,
+      })
+      .rpc();
+    console.log("Your transaction signature", tx, `${explorerBase}/tx/${tx}?cluster=${explorerCluster}`);
+    const config = await program.account.config.fetch(configAccount);
+    if (config.lastSolPrice.price.isZero()) {
+      throw new Error("refresh should record the SOL price");
+    }
+  });
+
   it("Deposit Collateral and Mint GOLD", async () => {
     const amountCollateral = 1_000_000_000; // 1 SOL collateral
     const amountToMint = 100_000; // mint very small amount to satisfy health factor
@@ -117,6 +172,12 @@ describe("gold", () => {
       tx,
       `${explorerBase}/tx/${tx}?cluster=${explorerCluster}`
     );
+
+    // Minting needs a live GOLD price, which is kept as the fallback for when the market closes
+    const config = await program.account.config.fetch(configAccount);
+    if (config.lastGoldPrice.price.isZero()) {
+      throw new Error("minting should record the GOLD price it used");
+    }
   });
 
   it("Redeem Collateral and Burn GOLD", async () => {
@@ -145,12 +206,91 @@ describe("gold", () => {
     );
   });
 
+  it("Deposit Collateral", async () => {
+    const amountCollateral = 100_000_000; // top up 0.1 SOL, no price feeds needed
+    const before = await program.account.collateral.fetch(collateralAccount);
+    const tx = await program.methods
+      .depositCollateral(new BN(amountCollateral))
+      .accounts({
+        depositor: wallet.publicKey,
+        configAccount,
+        collateralAccount,
+        solAccount,
+        tokenProgram,
+        systemProgram,
+      })
+      .rpc();
+    console.log("Your transaction signature", tx, `${explorerBase}/tx/${tx}?cluster=${explorerCluster}`);
+    const after = await program.account.collateral.fetch(collateralAccount);
+    if (!after.lamportBalance.eq(before.lamportBalance.add(new BN(amountCollateral)))) {
+      throw new Error("deposit should add to the collateral balance");
+    }
+  });
+
+  it("Mint GOLD", async () => {
+    const amountToMint = 10_000;
+    const tx = await program.methods
+      .mintGold(new BN(amountToMint))
+      .accounts({
+        depositor: wallet.publicKey,
+        configAccount,
+        collateralAccount,
+        mintAccount,
+        goldPriceUpdate: goldUsdPriceFeedAccount,
+        solPriceUpdate: solUsdPriceFeedAccount,
+        tokenAccount,
+        tokenProgram,
+        associatedTokenProgram,
+        systemProgram,
+      })
+      .rpc();
+    console.log("Your transaction signature", tx, `${explorerBase}/tx/${tx}?cluster=${explorerCluster}`);
+  });
+
+  it("Burn GOLD", async () => {
+    const amountToBurn = 10_000; // no price feeds needed
+    const before = await program.account.collateral.fetch(collateralAccount);
+    const tx = await program.methods
+      .burnGold(new BN(amountToBurn))
+      .accounts({
+        depositor: wallet.publicKey,
+        configAccount,
+        collateralAccount,
+        mintAccount,
+        tokenAccount,
+        tokenProgram,
+      })
+      .rpc();
+    console.log("Your transaction signature", tx, `${explorerBase}/tx/${tx}?cluster=${explorerCluster}`);
+    const after = await program.account.collateral.fetch(collateralAccount);
+    if (!after.amountMinted.eq(before.amountMinted.sub(new BN(amountToBurn)))) {
+      throw new Error("burn should reduce the minted amount");
+    }
+  });
+
+  it("Withdraw Collateral", async () => {
+    const amountCollateral = 50_000_000; // withdraw 0.05 SOL
+    const tx = await program.methods
+      .withdrawCollateral(new BN(amountCollateral))
+      .accounts({
+        depositor: wallet.publicKey,
+        goldPriceUpdate: goldUsdPriceFeedAccount,
+        solPriceUpdate: solUsdPriceFeedAccount,
+        configAccount,
+        collateralAccount,
+        solAccount,
+        systemProgram,
+      })
+      .rpc();
+    console.log("Your transaction signature", tx, `${explorerBase}/tx/${tx}?cluster=${explorerCluster}`);
+  });
+
   // Increase minimum health threshold to test liquidate
   it("Update Config", async () => {
     // Set very high min_health_factor to force unhealthy state
     const tx = await program.methods
-      .updateConfig(new BN(1_000_000_000))
-      .accounts({ configAccount })
+      .updateConfig({ minHealthFactor: new BN(1_000_000_000) })
+      .accounts({ authority: wallet.publicKey, configAccount })
       .rpc();
     console.log(
       "Your transaction signature",
@@ -161,6 +301,7 @@ describe("gold", () => {
 
   it("Liquidate", async () => {
     const amountToBurn = 25_000; // keep burn small and <= remaining minted
+    const lamportsBefore = await connection.getBalance(solAccount);
     const tx = await program.methods
       .liquidate(new BN(amountToBurn))
       .accounts({
@@ -182,13 +323,73 @@ describe("gold", () => {
       tx,
       `${explorerBase}/tx/${tx}?cluster=${explorerCluster}`
     );
+
+    // The seized lamports come from the same GOLD and SOL prices the instruction recorded: the burned
+    // GOLD at spot, both rounded down, plus the bonus. GOLD and SOL both have 9 decimals.
+    const config = await program.account.config.fetch(configAccount);
+    const lamports = new BN(amountToBurn).mul(config.lastGoldPrice.price).div(config.lastSolPrice.price);
+    const expected = lamports.add(lamports.mul(config.liquidationBonus).divn(100));
+    const seized = lamportsBefore - (await connection.getBalance(solAccount));
+    if (!expected.eqn(seized)) {
+      throw new Error(`liquidation seized ${seized} lamports, expected ${expected.toString()}`);
+    }
   });
 
   it("Update Config", async () => {
     const tx = await program.methods
-      .updateConfig(new BN(1))
-      .accounts({ configAccount })
+      .updateConfig({ minHealthFactor: new BN(10_000) })
+      .accounts({ authority: wallet.publicKey, configAccount })
       .rpc();
     console.log("Your transaction signature", tx, "https://explorer.solana.com/tx/" + tx + "?cluster=devnet");
   });
+
+  it("Update Config stores the oracle policy of each feed", async () => {
+    await program.methods
+      .updateConfig({ goldMaxConfidenceBps: new BN(50), solMaxEmaDeviationBps: new BN(300) })
+      .accounts({ authority: wallet.publicKey, configAccount })
+      .rpc();
+
+    const config = await program.account.config.fetch(configAccount);
+    if (config.goldMaxConfidenceBps.toNumber() !== 50 || config.solMaxEmaDeviationBps.toNumber() !== 300) {
+      throw new Error("oracle policy should be stored on the config");
+    }
+  });
+
+  it("Update Config sets the guardian and secondary oracle program", async () => {
+    const secondaryOracleProgram = anchor.web3.Keypair.generate().publicKey;
+    await program.methods
+      .updateConfig({ guardian: wallet.publicKey, secondaryOracleProgram })
+      .accounts({ authority: wallet.publicKey, configAccount })
+      .rpc();
+
+    const config = await program.account.config.fetch(configAccount);
+    if (!config.guardian.equals(wallet.publicKey) || !config.secondaryOracleProgram.equals(secondaryOracleProgram)) {
+      throw new Error("guardian and secondary oracle program should be stored on the config");
+    }
+  });
+
+  it("Update Config rejects other signers and out of bounds values", async () => {
+    const intruder = anchor.web3.Keypair.generate();
+    for (const [signer, update] of [
+      [intruder, { minHealthFactor: new BN(10_000) }],
+      [wallet.payer, { liquidationThreshold: new BN(0) }],
+      [wallet.payer, { goldMaxConfidenceBps: new BN(20_000) }],
+      [wallet.payer, { solMaxEmaDeviationBps: new BN(0) }],
+      [wallet.payer, { guardian: anchor.web3.PublicKey.default }],
+    ] as [anchor.web3.Keypair, object][]) {
+      let rejected = false;
+      try {
+        await program.methods
+          .updateConfig(update)
+          .accounts({ authority: signer.publicKey, configAccount })
+          .signers([signer])
+          .rpc();
+      } catch (err) {
+        rejected = true;
+      }
+      if (!rejected) {
+        throw new Error("update_config should have been rejected");
+      }
+    }
+  });
 });